@@ -11,11 +11,44 @@ use s2_dbpf_raw_macros::DbpfKindsDerive;
 #[cfg(test)]
 use test_strategy::Arbitrary;
 
+use crate::types::bitmap_image::BitmapImage;
+use crate::types::draw_state_lighting::DrawStateLighting;
+use crate::types::drawgroup::Drawgroup;
+use crate::types::environment_cube_lighting::EnvironmentCubeLighting;
+use crate::types::image_color_palette::ImageColorPalette;
+use crate::types::jpeg_image::JpegImage;
+use crate::types::large_image::LargeImage;
+use crate::types::linear_fog_lighting::LinearFogLighting;
+use crate::types::name_reference::NameReference;
+use crate::types::neighborhood::three_d_reference::ThreeDReference;
+use crate::types::object_data::ObjectData;
+use crate::types::object_function::ObjectFunction;
+use crate::types::object_slot::ObjectSlot;
+use crate::types::pie_menu_functions::PieMenuFunctions;
+use crate::types::pie_menu_settings::PieMenuSettings;
+use crate::types::scenegraph::binary_index::BinaryIndex;
+use crate::types::scenegraph::creation_resource::CreationResource;
+use crate::types::scenegraph::geometric_data_container::GeometricDataContainer;
+use crate::types::scenegraph::geometric_node::GeometricNode;
+use crate::types::scenegraph::light::Light;
+use crate::types::scenegraph::material_object::MaterialObject;
+use crate::types::scenegraph::material_override::MaterialOverride;
+use crate::types::scenegraph::property_set::PropertySet;
+use crate::types::scenegraph::shape::Shape;
+use crate::types::scenegraph::texture_overlay_xml::TextureOverlayXml;
+use crate::types::scenegraph::textured_material::TexturedMaterial;
 use crate::types::simantic::behavior_constant::BehaviorConstants;
 use crate::types::simantic::behavior_constant_labels::BehaviorConstantLabels;
+use crate::types::simantic::behavior_flowchart_tree::BehaviorFlowchartTree;
 use crate::types::simantic::behavior_function::BehaviorFunction;
 use crate::types::simantic::behavior_function_labels::BehaviorFunctionLabels;
+use crate::types::simantic::global_data::GlobalData;
+use crate::types::skintone_xml::SkintoneXml;
+use crate::types::sprites::Sprites;
+use crate::types::text_lists::TextList;
+use crate::types::texture_resource::TextureResource;
 use crate::types::unimplemented::Unimplemented;
+use crate::types::unknown_object::UnknownObject;
 
 // NOTE!:
 // If you get this error (or a similar one):
@@ -43,11 +76,11 @@ pub enum DbpfId {
     TrackSettings = 0x0B9E_B87E,
     #[dbpf(short_name = "DESC")]
     LotDescription = 0x0BF9_99E7,
-    #[dbpf(short_name = "BINX")]
+    #[dbpf(short_name = "BINX", kind_type = "BinaryIndex")]
     BinaryIndex = 0x0C56_0F39,
     #[dbpf(short_name = "POOL")]
     PoolSurface = 0x0C90_0FDB,
-    #[dbpf(short_name = "TXTR")]
+    #[dbpf(short_name = "TXTR", kind_type = "TextureResource")]
     TextureResource = 0x1C4A_276C,
     #[dbpf(short_name = "XA")]
     AudioFile = 0x2026_960B,
@@ -55,7 +88,7 @@ pub enum DbpfId {
     SceneNode = 0x2523_2B11,
     #[dbpf(short_name = "3ARY")]
     ThreeDArray = 0x2A51_171B,
-    #[dbpf(short_name = "XTOL")]
+    #[dbpf(short_name = "XTOL", kind_type = "TextureOverlayXml")]
     TextureOverlayXML = 0x2C1F_D8A1,
     #[dbpf(short_name = "POPS")]
     Popups = 0x2C31_0F46,
@@ -65,7 +98,7 @@ pub enum DbpfId {
     BehaviorConstant = 0x4243_4F4E,
     #[dbpf(short_name = "BHAV", kind_type = "BehaviorFunction")]
     BehaviorFunction = 0x4248_4156,
-    #[dbpf(short_name = "BMP")]
+    #[dbpf(short_name = "BMP", kind_type = "BitmapImage")]
     BitmapImage = 0x424D_505F,
     #[dbpf(short_name = "CATS")]
     CatalogString = 0x4341_5453,
@@ -73,7 +106,7 @@ pub enum DbpfId {
     ImageLink = 0x4349_4745,
     #[dbpf(short_name = "CTSS")]
     CatalogDescription = 0x4354_5353,
-    #[dbpf(short_name = "DGRP")]
+    #[dbpf(short_name = "DGRP", kind_type = "Drawgroup")]
     Drawgroup = 0x4447_5250,
     #[dbpf(short_name = "FACE")]
     FaceProperties = 0x4641_4345,
@@ -85,33 +118,35 @@ pub enum DbpfId {
     Function = 0x4643_4E53,
     #[dbpf(short_name = "FWAV")]
     AudioReference = 0x4657_4156,
-    #[dbpf(short_name = "GLOB")]
+    #[dbpf(short_name = "GLOB", kind_type = "GlobalData")]
     GlobalData = 0x474C_4F42,
     #[dbpf(short_name = "HOUS")]
     HouseDescriptor = 0x484F_5553,
-    #[dbpf(short_name = "TXMT")]
+    #[dbpf(short_name = "TXMT", kind_type = "TexturedMaterial")]
     TexturedMaterial = 0x4959_6978,
     #[dbpf(short_name = "WRLD")]
     WorldDatabase = 0x49FF_7D76,
     #[dbpf(short_name = "LTTX")]
     LotTexture = 0x4B58_975B,
-    #[dbpf(short_name = "XSTN")]
+    #[dbpf(short_name = "XSTN", kind_type = "SkintoneXml")]
     SkinToneXml = 0x4C15_8081,
+    #[dbpf(short_name = "MMAT", kind_type = "MaterialOverride")]
+    MaterialOverride = 0x4C69_7E5A,
     #[dbpf(short_name = "CINE")]
     CinematicScene = 0x4D51_F042,
     #[dbpf(short_name = "NGBH")]
     NeighborhoodMemory = 0x4E47_4248,
-    #[dbpf(short_name = "NREF")]
+    #[dbpf(short_name = "NREF", kind_type = "NameReference")]
     NameReference = 0x4E52_4546,
     #[dbpf(short_name = "NMAP")]
     NameMap = 0x4E6D_6150,
-    #[dbpf(short_name = "OBJD")]
+    #[dbpf(short_name = "OBJD", kind_type = "ObjectData")]
     ObjectData = 0x4F42_4A44,
-    #[dbpf(short_name = "OBJF")]
+    #[dbpf(short_name = "OBJF", kind_type = "ObjectFunction")]
     ObjectFunction = 0x4F42_4A66,
     #[dbpf(short_name = "OBJM")]
     ObjectMetadata = 0x4F62_6A4D,
-    #[dbpf(short_name = "PALT")]
+    #[dbpf(short_name = "PALT", kind_type = "ImageColorPalette")]
     ImageColorPalette = 0x5041_4C54,
     #[dbpf(short_name = "PERS")]
     SimPersonalInformation = 0x5045_5253,
@@ -121,11 +156,11 @@ pub enum DbpfId {
     PackageToolkit = 0x5054_4250,
     #[dbpf(short_name = "SIMI")]
     SimInformation = 0x5349_4D49,
-    #[dbpf(short_name = "SLOT")]
+    #[dbpf(short_name = "SLOT", kind_type = "ObjectSlot")]
     ObjectSlot = 0x534C_4F54,
-    #[dbpf(short_name = "SPR2")]
+    #[dbpf(short_name = "SPR2", kind_type = "Sprites")]
     Sprites = 0x5350_5232,
-    #[dbpf(short_name = "STR#")]
+    #[dbpf(short_name = "STR#", kind_type = "TextList")]
     TextLists = 0x5354_5223,
     #[dbpf(short_name = "TTAT")]
     TTAT = 0x5441_5454,
@@ -133,17 +168,17 @@ pub enum DbpfId {
     BehaviorFunctionLabels = 0x5450_5250,
     #[dbpf(short_name = "TRCN", kind_type = "BehaviorConstantLabels")]
     BehaviorConstantLabels = 0x5452_434E,
-    #[dbpf(short_name = "TREE")]
+    #[dbpf(short_name = "TREE", kind_type = "BehaviorFlowchartTree")]
     BehaviorFlowchartTree = 0x5452_4545,
-    #[dbpf(short_name = "TTAB")]
+    #[dbpf(short_name = "TTAB", kind_type = "PieMenuFunctions")]
     PieMenuFunctions = 0x5454_4142,
-    #[dbpf(short_name = "TTAs")]
+    #[dbpf(short_name = "TTAs", kind_type = "PieMenuSettings")]
     PieMenuSettings = 0x5454_4173,
-    #[dbpf(short_name = "XMTO")]
+    #[dbpf(short_name = "XMTO", kind_type = "MaterialObject")]
     MaterialObject = 0x584D_544F,
-    #[dbpf(short_name = "XOBJ")]
+    #[dbpf(short_name = "XOBJ", kind_type = "UnknownObject")]
     UnknownObject = 0x584F_424A,
-    #[dbpf(short_name = "5EL")]
+    #[dbpf(short_name = "5EL", kind_type = "EnvironmentCubeLighting")]
     EnvironmentCubeLighting = 0x6A97_042F,
     #[dbpf(short_name = "2ARY")]
     TwoDArray = 0x6B94_3B43,
@@ -153,7 +188,7 @@ pub enum DbpfId {
     Object = 0x6F62_6A74,
     #[dbpf(short_name = "HLS")]
     Hitlist = 0x7B1A_CFCD,
-    #[dbpf(short_name = "GMND")]
+    #[dbpf(short_name = "GMND", kind_type = "GeometricNode")]
     GeometricNode = 0x7BA3_838C,
     #[dbpf(short_name = "LTMP")]
     Lightmap = 0x856D_DBAC,
@@ -161,7 +196,7 @@ pub enum DbpfId {
     WallLayer = 0x8A84_D7B0,
     #[dbpf(short_name = "UNK1")]
     Unknown1 = 0x8B0C_79D6,
-    #[dbpf(short_name = "JPG")]
+    #[dbpf(short_name = "JPG", kind_type = "JpegImage")]
     JpegImage = 0x8C3C_E95A,
     #[dbpf(short_name = "FAMt")]
     FamilyTies = 0x8C87_0743,
@@ -181,13 +216,13 @@ pub enum DbpfId {
     LotTerrainGeometry = 0xABCB_5DA4,
     #[dbpf(short_name = "NHTR")]
     NeighborhoodTerrain = 0xABD0_DC63,
-    #[dbpf(short_name = "5LF")]
+    #[dbpf(short_name = "5LF", kind_type = "LinearFogLighting")]
     LinearFogLighting = 0xAC06_A66F,
-    #[dbpf(short_name = "5DS")]
+    #[dbpf(short_name = "5DS", kind_type = "DrawStateLighting")]
     DrawStateLighting = 0xAC06_A676,
-    #[dbpf(short_name = "GMDC")]
+    #[dbpf(short_name = "GMDC", kind_type = "GeometricDataContainer")]
     GeometricDataContainer = 0xAC4F_8687,
-    #[dbpf(short_name = "3IDR")]
+    #[dbpf(short_name = "3IDR", kind_type = "ThreeDReference")]
     ThreeDReference = 0xAC50_6764,
     #[dbpf(short_name = "NID")]
     IdNumber = 0xAC8A_7A2E,
@@ -195,7 +230,7 @@ pub enum DbpfId {
     WeatherInfo = 0xB21B_E28B,
     #[dbpf(short_name = "TSSG")]
     TssgSystem = 0xBA35_3CE1,
-    #[dbpf(short_name = "LGHT")]
+    #[dbpf(short_name = "LGHT", kind_type = "Light")]
     Light = 0xC9C8_1B9B,
     #[dbpf(short_name = "SMAP")]
     StringMap = 0xCAC4_FC40,
@@ -215,25 +250,25 @@ pub enum DbpfId {
     SimWantsAndFears = 0xCD95_548E,
     #[dbpf(short_name = "CREG")]
     ContentRegistry = 0xCDB4_67B8,
-    #[dbpf(short_name = "CRES")]
+    #[dbpf(short_name = "CRES", kind_type = "CreationResource")]
     CreationResource = 0xE519_C933,
     #[dbpf(short_name = "DIR")]
     Directory = 0xE86B_1EEF,
     #[dbpf(short_name = "FX")]
     EffectsResourceTree = 0xEA51_18B0,
-    #[dbpf(short_name = "GZPS")]
+    #[dbpf(short_name = "GZPS", kind_type = "PropertySet")]
     PropertySet = 0xEBCF_3E27,
     #[dbpf(short_name = "VERS")]
     VersionInformation = 0xEBFE_E342,
     #[dbpf(short_name = "NHVW")]
     NeighborhoodView = 0xEC44_BDDC,
-    #[dbpf(short_name = "LIFO")]
+    #[dbpf(short_name = "LIFO", kind_type = "LargeImage")]
     LargeImage = 0xED53_4136,
     #[dbpf(short_name = "OBJT")]
     SingularLotObject = 0xFA1C_39F7,
     #[dbpf(short_name = "ANIM")]
     Animation = 0xFB00_791E,
-    #[dbpf(short_name = "SHPE")]
+    #[dbpf(short_name = "SHPE", kind_type = "Shape")]
     Shape = 0xFC6E_B1F7,
     #[dbpf(short_name = "UNIMP", kind_type = "Unimplemented")]
     Unimplemented = 0xFFFF_FFFF,
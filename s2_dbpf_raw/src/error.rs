@@ -13,6 +13,18 @@ pub enum Error {
     NotSims2Format,
     #[error("Failed to read file")]
     BinRWError(#[from] BinError),
+    #[error("Failed to convert image")]
+    ImageError(#[from] image::ImageError),
+    #[error("Failed to read mesh: {0}")]
+    MeshError(String),
+    #[error("Failed to read JSON")]
+    JsonError(#[from] serde_json::Error),
+    #[error("Failed to read XML")]
+    XmlError(#[from] quick_xml::Error),
+    #[error("Failed to read property set: {0}")]
+    CpfError(String),
+    #[error("Failed to access file")]
+    IoError(#[from] std::io::Error),
 }
 
 pub type DbpfResult<T> = Result<T, Error>;
@@ -11,6 +11,9 @@
 #![allow(clippy::default_trait_access)]
 // too many lines is a dumb metric
 #![allow(clippy::too_many_lines)]
+// image and the binrw and test-strategy macros pull in different versions of miniz_oxide and
+// syn, which can't be unified from this crate
+#![allow(clippy::multiple_crate_versions)]
 
 pub mod constants;
 pub mod error;
@@ -127,3 +127,70 @@ macro_rules! test_parsing_bhav_ins {
     };
 }
 pub(crate) use test_parsing_bhav_ins;
+
+/// A package holding `entries` uncompressed, for tests that look resources up across a `Dbpf`.
+pub(crate) fn dbpf_from(
+    entries: Vec<(
+        crate::types::package::database_packed_file::Key,
+        crate::constants::data_kinds::DbpfKind,
+    )>,
+) -> crate::types::package::database_packed_file::Dbpf {
+    use crate::types::package::database_packed_file::{Dbpf, Entry};
+
+    Dbpf {
+        header: crate::types::package::header::Header::default(),
+        entries: entries
+            .into_iter()
+            .map(|(key, data)| {
+                (
+                    key,
+                    Entry {
+                        compressed: false,
+                        data,
+                    },
+                )
+            })
+            .collect(),
+    }
+}
+
+/// The bytes of a package holding `entries` uncompressed, one after the other, with the index last.
+pub(crate) fn package_bytes(
+    entries: &[(crate::types::package::database_packed_file::Key, Vec<u8>)],
+) -> Vec<u8> {
+    use crate::types::package::header::Header;
+    use crate::types::package::index_table::Entry;
+    use crate::types::util::bytes::{Position, Size};
+    use binrw::BinWriterExt;
+    use std::io::Cursor;
+
+    // Written once to make room, and again once the index position is known
+    let mut cursor = Cursor::new(vec![]);
+    cursor.write_le(&Header::default()).unwrap();
+    let mut index = vec![];
+    for (key, data) in entries {
+        let location = Position(cursor.position() as u32);
+        cursor.write_le(data).unwrap();
+        index.push((
+            *key,
+            Entry {
+                location,
+                size: Size(data.len() as u32),
+            },
+        ));
+    }
+    let index_position = Position(cursor.position() as u32);
+    for (key, entry) in &index {
+        cursor.write_le(key).unwrap();
+        cursor.write_le(entry).unwrap();
+    }
+    let header = Header {
+        index_entry_count: index.len() as u32,
+        index_position,
+        index_size: Size(cursor.position() as u32 - index_position.0),
+        ..Header::default()
+    };
+    cursor.set_position(0);
+    cursor.write_le(&header).unwrap();
+    cursor.into_inner()
+}
@@ -4,4 +4,31 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct BitmapImage {}
+//! Bitmaps, stored as plain BMP files.
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId};
+use crate::types::util::image::ImageFile;
+use crate::types::util::parser_args::ParserArgs;
+use binrw::binrw;
+use derive_more::{Deref, DerefMut};
+
+pub type BMP = BitmapImage;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deref, DerefMut)]
+#[brw(little)]
+#[br(import_raw(args: ParserArgs))]
+pub struct BitmapImage {
+    #[br(args_raw = args)]
+    pub image: ImageFile,
+}
+
+impl DbpfEntry for BitmapImage {
+    fn id(&self) -> DbpfId {
+        DbpfId::BitmapImage
+    }
+
+    fn name(&self) -> Option<String> {
+        self.image.file_name.clone()
+    }
+}
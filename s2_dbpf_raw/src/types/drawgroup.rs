@@ -4,4 +4,595 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct Drawgroup {}
+//! Lists the SPR2 frames that make up an object for every rotation and zoom level, and where to
+//! draw each of them.
+
+use std::io::{Read, Seek, Write};
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId, DbpfKind};
+use crate::types::package::database_packed_file::{Dbpf, GroupId, InstanceId};
+use crate::types::sprites::Sprites;
+use crate::types::util::image::RgbaImage;
+use crate::types::util::parser_args::ParserArgs;
+use binrw::{binrw, BinRead, BinResult, BinWrite, NullString, ReadOptions, WriteOptions};
+#[cfg(test)]
+use proptest::collection::vec;
+#[cfg(test)]
+use proptest::prelude::*;
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type DGRP = Drawgroup;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(_args: ParserArgs))]
+pub struct Drawgroup {
+    #[br(try_map(NullString::try_into))]
+    #[bw(map(| x: &String | NullString::from(x.clone())))]
+    #[brw(pad_size_to = 64)]
+    #[cfg_attr(test, strategy("[a-zA-Z0-9 ]{0,63}"))]
+    pub file_name: String,
+    pub version: Version,
+    #[br(temp, if (! version.is_wide()))]
+    #[bw(calc = (! version.is_wide()).then(|| images.len() as u16))]
+    num_images_narrow: Option<u16>,
+    #[br(temp, if (version.is_wide()))]
+    #[bw(calc = version.is_wide().then(|| images.len() as u32))]
+    num_images_wide: Option<u32>,
+    #[br(args {
+        count: num_images_wide.map_or_else(|| num_images_narrow.map_or(0, usize::from), |count| count as usize),
+        inner: (version,)
+    })]
+    #[bw(args_raw = (* version,))]
+    #[cfg_attr(test, strategy(vec(any_with::< DrawgroupImage > ((# version,)), (0..12))))]
+    pub images: Vec<DrawgroupImage>,
+}
+
+impl DbpfEntry for Drawgroup {
+    fn id(&self) -> DbpfId {
+        DbpfId::Drawgroup
+    }
+
+    fn name(&self) -> Option<String> {
+        Some(self.file_name.clone())
+    }
+}
+
+impl Drawgroup {
+    #[must_use]
+    pub fn image(&self, direction: u32, zoom: u32) -> Option<&DrawgroupImage> {
+        self.images
+            .iter()
+            .find(|image| image.direction == direction && image.zoom == zoom)
+    }
+
+    /// Composites the view for `direction` and `zoom`, drawing the sprites in list order with
+    /// their SPR2 and PALT looked up in `group`. Returns the image along with the position of
+    /// its top left corner relative to the object's origin, or `None` if the view doesn't exist
+    /// or has no sprites that could be found.
+    #[must_use]
+    pub fn render(
+        &self,
+        direction: u32,
+        zoom: u32,
+        dbpf: &Dbpf,
+        group: GroupId,
+    ) -> Option<(RgbaImage, (i64, i64))> {
+        let layers: Vec<_> = self
+            .image(direction, zoom)?
+            .sprites
+            .iter()
+            .filter_map(|sprite| {
+                let sprites = match dbpf.find(DbpfId::Sprites, group, InstanceId(sprite.sprite_id))
+                {
+                    Some((_, DbpfKind::Sprites(sprites))) => sprites,
+                    _ => return None,
+                };
+                sprite.render(sprites, dbpf, group)
+            })
+            .collect();
+
+        let left = layers.iter().map(|(_, (x, _))| *x).min()?;
+        let top = layers.iter().map(|(_, (_, y))| *y).min()?;
+        let right = layers
+            .iter()
+            .map(|(image, (x, _))| x + i64::from(image.width))
+            .max()?;
+        let bottom = layers
+            .iter()
+            .map(|(image, (_, y))| y + i64::from(image.height))
+            .max()?;
+        let mut canvas = RgbaImage::new(
+            u32::try_from(right - left).ok()?,
+            u32::try_from(bottom - top).ok()?,
+        );
+        for (image, (x, y)) in layers {
+            canvas.blend(&image, x - left, y - top);
+        }
+        Some((canvas, (left, top)))
+    }
+}
+
+#[binrw]
+#[derive(Debug, PartialOrd, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub enum Version {
+    #[brw(magic(20000_u16))]
+    TwentyThousand,
+    /// Adds a depth offset to every sprite
+    #[brw(magic(20001_u16))]
+    TwentyThousandOne,
+    /// Widens every field to 32 bits
+    #[brw(magic(20003_u16))]
+    TwentyThousandThree,
+    /// Adds the object's offset within its tile to every sprite
+    #[brw(magic(20004_u16))]
+    TwentyThousandFour,
+}
+
+impl Default for Version {
+    fn default() -> Self {
+        Version::TwentyThousandFour
+    }
+}
+
+impl Version {
+    fn is_wide(self) -> bool {
+        self >= Version::TwentyThousandThree
+    }
+}
+
+fn narrow<T: TryFrom<U>, U: Copy + std::fmt::Display>(value: U, pos: u64) -> BinResult<T> {
+    T::try_from(value).map_err(|_| binrw::Error::AssertFail {
+        pos,
+        message: format!("{} doesn't fit in this drawgroup version", value),
+    })
+}
+
+/// The sprites drawn for one rotation at one zoom level.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrawgroupImage {
+    /// One of the `DIRECTION_` constants
+    pub direction: u32,
+    /// One of the `ZOOM_` constants
+    pub zoom: u32,
+    pub sprites: Vec<DrawgroupSprite>,
+}
+
+impl DrawgroupImage {
+    pub const DIRECTION_RIGHT_FRONT: u32 = 0x01;
+    pub const DIRECTION_RIGHT_BACK: u32 = 0x04;
+    pub const DIRECTION_LEFT_BACK: u32 = 0x10;
+    pub const DIRECTION_LEFT_FRONT: u32 = 0x40;
+
+    pub const ZOOM_FAR: u32 = 1;
+    pub const ZOOM_MEDIUM: u32 = 2;
+    pub const ZOOM_NEAR: u32 = 3;
+}
+
+impl BinRead for DrawgroupImage {
+    type Args = (Version,);
+
+    fn read_options<R: Read + Seek>(
+        reader: &mut R,
+        options: &ReadOptions,
+        args: Self::Args,
+    ) -> BinResult<Self> {
+        let (direction, zoom, count) = if args.0.is_wide() {
+            (
+                u32::read_options(reader, options, ())?,
+                u32::read_options(reader, options, ())?,
+                u32::read_options(reader, options, ())?,
+            )
+        } else {
+            let count = u32::from(u16::read_options(reader, options, ())?);
+            (
+                u32::from(u8::read_options(reader, options, ())?),
+                u32::from(u8::read_options(reader, options, ())?),
+                count,
+            )
+        };
+        let mut sprites = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            sprites.push(DrawgroupSprite::read_options(reader, options, args)?);
+        }
+        Ok(Self {
+            direction,
+            zoom,
+            sprites,
+        })
+    }
+}
+
+impl BinWrite for DrawgroupImage {
+    type Args = (Version,);
+
+    fn write_options<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+        args: Self::Args,
+    ) -> BinResult<()> {
+        let pos = writer.stream_position()?;
+        if args.0.is_wide() {
+            self.direction.write_options(writer, options, ())?;
+            self.zoom.write_options(writer, options, ())?;
+            (self.sprites.len() as u32).write_options(writer, options, ())?;
+        } else {
+            narrow::<u16, _>(self.sprites.len(), pos)?.write_options(writer, options, ())?;
+            narrow::<u8, _>(self.direction, pos)?.write_options(writer, options, ())?;
+            narrow::<u8, _>(self.zoom, pos)?.write_options(writer, options, ())?;
+        }
+        for sprite in &self.sprites {
+            sprite.write_options(writer, options, args)?;
+        }
+        Ok(())
+    }
+}
+
+/// A single SPR2 frame placed within a drawgroup image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrawgroupSprite {
+    /// Only stored before version 20003
+    pub kind: u16,
+    /// Instance of the SPR2 holding the frame
+    pub sprite_id: u32,
+    pub frame: u32,
+    /// Pixel offset of the frame's top left corner from the object's origin
+    pub x: i32,
+    pub y: i32,
+    /// Depth offset, only stored from version 20001
+    pub z: f32,
+    pub flags: u32,
+    /// Offset of the object within its tile, only stored from version 20004
+    pub object_x: f32,
+    pub object_y: f32,
+}
+
+impl DrawgroupSprite {
+    pub const FLAG_FLIP: u32 = 0x01;
+    pub const FLAG_LUMINOUS: u32 = 0x04;
+
+    /// Decodes the frame this sprite draws, flipped if needed, along with where it goes.
+    #[must_use]
+    pub fn render(
+        &self,
+        sprites: &Sprites,
+        dbpf: &Dbpf,
+        group: GroupId,
+    ) -> Option<(RgbaImage, (i64, i64))> {
+        let frame = sprites.frames.get(self.frame as usize)?;
+        let image = frame.to_rgba(sprites.palette(dbpf, group)?);
+        let image = if self.flags & Self::FLAG_FLIP == 0 {
+            image
+        } else {
+            image.flip_horizontal()
+        };
+        Some((image, (i64::from(self.x), i64::from(self.y))))
+    }
+}
+
+impl BinRead for DrawgroupSprite {
+    type Args = (Version,);
+
+    fn read_options<R: Read + Seek>(
+        reader: &mut R,
+        options: &ReadOptions,
+        args: Self::Args,
+    ) -> BinResult<Self> {
+        let version = args.0;
+        if version.is_wide() {
+            let sprite_id = u32::read_options(reader, options, ())?;
+            let frame = u32::read_options(reader, options, ())?;
+            let x = i32::read_options(reader, options, ())?;
+            let y = i32::read_options(reader, options, ())?;
+            let z = f32::read_options(reader, options, ())?;
+            let flags = u32::read_options(reader, options, ())?;
+            let (object_x, object_y) = if version == Version::TwentyThousandFour {
+                (
+                    f32::read_options(reader, options, ())?,
+                    f32::read_options(reader, options, ())?,
+                )
+            } else {
+                (0.0, 0.0)
+            };
+            Ok(Self {
+                kind: 0,
+                sprite_id,
+                frame,
+                x,
+                y,
+                z,
+                flags,
+                object_x,
+                object_y,
+            })
+        } else {
+            let kind = u16::read_options(reader, options, ())?;
+            let sprite_id = u32::from(u16::read_options(reader, options, ())?);
+            let frame = u32::from(u16::read_options(reader, options, ())?);
+            let flags = u32::from(u16::read_options(reader, options, ())?);
+            let x = i32::from(i16::read_options(reader, options, ())?);
+            let y = i32::from(i16::read_options(reader, options, ())?);
+            let z = if version == Version::TwentyThousandOne {
+                f32::read_options(reader, options, ())?
+            } else {
+                0.0
+            };
+            Ok(Self {
+                kind,
+                sprite_id,
+                frame,
+                x,
+                y,
+                z,
+                flags,
+                object_x: 0.0,
+                object_y: 0.0,
+            })
+        }
+    }
+}
+
+impl BinWrite for DrawgroupSprite {
+    type Args = (Version,);
+
+    fn write_options<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+        args: Self::Args,
+    ) -> BinResult<()> {
+        let version = args.0;
+        if version.is_wide() {
+            self.sprite_id.write_options(writer, options, ())?;
+            self.frame.write_options(writer, options, ())?;
+            self.x.write_options(writer, options, ())?;
+            self.y.write_options(writer, options, ())?;
+            self.z.write_options(writer, options, ())?;
+            self.flags.write_options(writer, options, ())?;
+            if version == Version::TwentyThousandFour {
+                self.object_x.write_options(writer, options, ())?;
+                self.object_y.write_options(writer, options, ())?;
+            }
+        } else {
+            let pos = writer.stream_position()?;
+            self.kind.write_options(writer, options, ())?;
+            narrow::<u16, _>(self.sprite_id, pos)?.write_options(writer, options, ())?;
+            narrow::<u16, _>(self.frame, pos)?.write_options(writer, options, ())?;
+            narrow::<u16, _>(self.flags, pos)?.write_options(writer, options, ())?;
+            narrow::<i16, _>(self.x, pos)?.write_options(writer, options, ())?;
+            narrow::<i16, _>(self.y, pos)?.write_options(writer, options, ())?;
+            if version == Version::TwentyThousandOne {
+                self.z.write_options(writer, options, ())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+prop_compose! {
+    fn drawgroup_sprite_mapper(
+        version: Version
+    )(
+        kind in any::<u16>(),
+        sprite_id in any::<u16>(),
+        frame in any::<u16>(),
+        x in any::<i16>(),
+        y in any::<i16>(),
+        z in -100.0_f32..100.0,
+        flags in any::<u16>(),
+        object_x in -100.0_f32..100.0,
+        object_y in -100.0_f32..100.0,
+    ) -> DrawgroupSprite {
+        DrawgroupSprite {
+            kind: if version.is_wide() { 0 } else { kind },
+            sprite_id: u32::from(sprite_id),
+            frame: u32::from(frame),
+            x: i32::from(x),
+            y: i32::from(y),
+            z: if version == Version::TwentyThousand { 0.0 } else { z },
+            flags: u32::from(flags),
+            object_x: if version == Version::TwentyThousandFour { object_x } else { 0.0 },
+            object_y: if version == Version::TwentyThousandFour { object_y } else { 0.0 },
+        }
+    }
+}
+
+#[cfg(test)]
+impl Arbitrary for DrawgroupSprite {
+    type Parameters = (Version,);
+
+    fn arbitrary_with(args: Self::Parameters) -> Self::Strategy {
+        drawgroup_sprite_mapper(args.0).boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
+
+#[cfg(test)]
+prop_compose! {
+    fn drawgroup_image_mapper(
+        version: Version
+    )(
+        direction in any::<u8>(),
+        zoom in any::<u8>(),
+        sprites in vec(any_with::<DrawgroupSprite>((version,)), 0..4),
+    ) -> DrawgroupImage {
+        DrawgroupImage {
+            direction: u32::from(direction),
+            zoom: u32::from(zoom),
+            sprites,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Arbitrary for DrawgroupImage {
+    type Parameters = (Version,);
+
+    fn arbitrary_with(args: Self::Parameters) -> Self::Strategy {
+        drawgroup_image_mapper(args.0).boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{dbpf_from, test_parsing};
+    use crate::types::image_color_palette::{Color, ImageColorPalette};
+    use crate::types::package::database_packed_file::Key;
+    use crate::types::sprites::{SpriteFrame, Version as SpritesVersion};
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+
+    test_parsing!(
+        [
+            0x54, 0x65, 0x73, 0x74, 0x46, 0x69, 0x6C, 0x65, //TestFile - in ascii bytes
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // first padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // second padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // third padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fourth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fifth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sixth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // seventh padding block
+            0x24, 0x4E, // version
+            0x01, 0x00, 0x00, 0x00, // number of images
+            0x01, 0x00, 0x00, 0x00, // direction
+            0x03, 0x00, 0x00, 0x00, // zoom
+            0x01, 0x00, 0x00, 0x00, // number of sprites
+            0x00, 0x10, 0x00, 0x00, // sprite id
+            0x00, 0x00, 0x00, 0x00, // frame
+            0xF0, 0xFF, 0xFF, 0xFF, // x
+            0x08, 0x00, 0x00, 0x00, // y
+            0x00, 0x00, 0x00, 0x00, // z
+            0x01, 0x00, 0x00, 0x00, // flags
+            0x00, 0x00, 0x00, 0x3F, // object x
+            0x00, 0x00, 0x00, 0x00, // object y
+        ],
+        Drawgroup {
+            file_name: "TestFile".to_string(),
+            version: Version::TwentyThousandFour,
+            images: vec![DrawgroupImage {
+                direction: DrawgroupImage::DIRECTION_RIGHT_FRONT,
+                zoom: DrawgroupImage::ZOOM_NEAR,
+                sprites: vec![DrawgroupSprite {
+                    kind: 0,
+                    sprite_id: 0x1000,
+                    frame: 0,
+                    x: -16,
+                    y: 8,
+                    z: 0.0,
+                    flags: DrawgroupSprite::FLAG_FLIP,
+                    object_x: 0.5,
+                    object_y: 0.0,
+                }]
+            }]
+        },
+        Drawgroup,
+        dgrp
+    );
+
+    #[test]
+    fn legacy_sprites_narrow() {
+        let sprite = DrawgroupSprite {
+            kind: 1,
+            sprite_id: 0x1_0000,
+            frame: 0,
+            x: 0,
+            y: 0,
+            z: 0.0,
+            flags: 0,
+            object_x: 0.0,
+            object_y: 0.0,
+        };
+        let mut cursor = Cursor::new(vec![]);
+
+        assert!(cursor
+            .write_le_args(&sprite, (Version::TwentyThousand,))
+            .is_err());
+    }
+
+    #[test]
+    fn render_composites_sprites() {
+        let group = GroupId(0x7F00_0001);
+        let mut frame = SpriteFrame::new(2, 1, false, false);
+        frame.color = vec![1, 2];
+        let sprites = Sprites {
+            file_name: String::new(),
+            version: SpritesVersion::OneThousand,
+            palette_id: 0x80,
+            frames: vec![frame],
+        };
+        let palette = ImageColorPalette {
+            file_name: String::new(),
+            version: 1,
+            reserved: [0; 8],
+            colors: vec![
+                Color::TRANSPARENT,
+                Color { r: 255, g: 0, b: 0 },
+                Color { r: 0, g: 0, b: 255 },
+            ],
+        };
+        let sprite = |x, flags| DrawgroupSprite {
+            kind: 0,
+            sprite_id: 0x1000,
+            frame: 0,
+            x,
+            y: -1,
+            z: 0.0,
+            flags,
+            object_x: 0.0,
+            object_y: 0.0,
+        };
+        let dgrp = Drawgroup {
+            file_name: String::new(),
+            version: Version::TwentyThousandFour,
+            images: vec![DrawgroupImage {
+                direction: DrawgroupImage::DIRECTION_LEFT_FRONT,
+                zoom: DrawgroupImage::ZOOM_FAR,
+                sprites: vec![sprite(0, 0), sprite(1, DrawgroupSprite::FLAG_FLIP)],
+            }],
+        };
+        let dbpf = dbpf_from(vec![
+            (
+                Key::new(DbpfId::Sprites, group, InstanceId(0x1000), None),
+                sprites.into(),
+            ),
+            (
+                Key::new(DbpfId::ImageColorPalette, group, InstanceId(0x80), None),
+                palette.into(),
+            ),
+        ]);
+
+        let (image, origin) = dgrp
+            .render(
+                DrawgroupImage::DIRECTION_LEFT_FRONT,
+                DrawgroupImage::ZOOM_FAR,
+                &dbpf,
+                group,
+            )
+            .unwrap();
+
+        assert_eq!(origin, (0, -1));
+        assert_eq!((image.width, image.height), (3, 1));
+        assert_eq!(image.pixel(0, 0), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(1, 0), [0, 0, 255, 255]);
+        assert_eq!(image.pixel(2, 0), [255, 0, 0, 255]);
+        assert!(dgrp
+            .render(
+                DrawgroupImage::DIRECTION_RIGHT_FRONT,
+                DrawgroupImage::ZOOM_FAR,
+                &dbpf,
+                group
+            )
+            .is_none());
+    }
+}
@@ -3,3 +3,122 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this         /
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
+
+//! Environment cube lighting (5EL), the cube map reflective surfaces on a lot mirror, tinted by
+//! the light's color.
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId};
+use crate::types::scenegraph::light::LightBase;
+use crate::types::util::parser_args::ParserArgs;
+use crate::types::util::resource_collection::{BlockHeader, ResourceCollection};
+use crate::types::util::strings::{read_seven_bit_string, write_seven_bit_string};
+use binrw::binrw;
+use derive_more::{Deref, DerefMut};
+#[cfg(test)]
+use proptest::prelude::*;
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Deref, DerefMut)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(args: ParserArgs))]
+pub struct EnvironmentCubeLighting {
+    #[br(args_raw = args)]
+    pub collection: ResourceCollection,
+}
+
+impl DbpfEntry for EnvironmentCubeLighting {
+    fn id(&self) -> DbpfId {
+        DbpfId::EnvironmentCubeLighting
+    }
+
+    fn name(&self) -> Option<String> {
+        self.light()
+            .map(|light| light.base.resource.file_name.clone())
+    }
+}
+
+impl EnvironmentCubeLighting {
+    #[must_use]
+    pub fn light(&self) -> Option<&EnvironmentCubeLight> {
+        self.block()
+    }
+
+    pub fn light_mut(&mut self) -> Option<&mut EnvironmentCubeLight> {
+        self.block_mut()
+    }
+}
+
+/// The `cEnvironmentCubeLight` block.
+#[binrw]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct EnvironmentCubeLight {
+    #[cfg_attr(test, strategy(Just(BlockHeader::of::<EnvironmentCubeLight>(1))))]
+    pub block: BlockHeader,
+    pub base: LightBase,
+    /// Name of the cube map texture
+    #[br(parse_with = read_seven_bit_string)]
+    #[bw(write_with = write_seven_bit_string)]
+    #[cfg_attr(test, strategy("[a-zA-Z0-9_]{0,16}"))]
+    pub cube_map: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+
+    test_parsing!(
+        [
+            0x15, // name length
+            0x63, 0x45, 0x6E, 0x76, 0x69, 0x72, 0x6F, 0x6E, 0x6D, 0x65, 0x6E, 0x74, 0x43, 0x75,
+            0x62, 0x65, 0x4C, 0x69, 0x67, 0x68, 0x74, // cEnvironmentCubeLight
+            0x2F, 0x04, 0x97, 0x6A, // block id
+            0x01, 0x00, 0x00, 0x00, // block version
+            0x12, // name length
+            0x63, 0x53, 0x74, 0x61, 0x6E, 0x64, 0x61, 0x72, 0x64, 0x4C, 0x69, 0x67, 0x68, 0x74,
+            0x42, 0x61, 0x73, 0x65, // cStandardLightBase
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x0B, 0x00, 0x00, 0x00, // block version
+            0x0B, // name length
+            0x63, 0x53, 0x47, 0x52, 0x65, 0x73, 0x6F, 0x75, 0x72, 0x63, 0x65, // cSGResource
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x02, 0x00, 0x00, 0x00, // block version
+            0x01, 0x4C, // L
+            0x07, // name length
+            0x63, 0x4C, 0x69, 0x67, 0x68, 0x74, 0x54, // cLightT
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x0B, 0x00, 0x00, 0x00, // block version
+            0x0D, // name length
+            0x63, 0x52, 0x65, 0x66, 0x65, 0x72, 0x65, 0x6E, 0x74, 0x4E, 0x6F, 0x64,
+            0x65, // cReferentNode
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x01, 0x00, 0x00, 0x00, // block version
+            0x10, // name length
+            0x63, 0x4F, 0x62, 0x6A, 0x65, 0x63, 0x74, 0x47, 0x72, 0x61, 0x70, 0x68, 0x4E, 0x6F,
+            0x64, 0x65, // cObjectGraphNode
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x04, 0x00, 0x00, 0x00, // block version
+            0x00, 0x00, 0x00, 0x00, // number of extensions
+            0x01, 0x4C, // L
+            0x00, 0x00, 0x80, 0x3F, // intensity
+            0x00, 0x00, 0x80, 0x3F, // red
+            0x00, 0x00, 0x00, 0x3F, // green
+            0x00, 0x00, 0x00, 0x00, // blue
+            0x04, 0x73, 0x6B, 0x79, 0x31, // sky1
+        ],
+        EnvironmentCubeLight {
+            block: BlockHeader::of::<EnvironmentCubeLight>(1),
+            base: LightBase::new("L".to_string(), 1.0, [1.0, 0.5, 0.0]),
+            cube_map: "sky1".to_string(),
+        },
+        EnvironmentCubeLight,
+        cenvironmentcubelight
+    );
+}
@@ -4,4 +4,197 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct ImageColorPalette {}
+//! The color table SPR2 frames index into.
+
+use std::collections::HashMap;
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId};
+use crate::types::util::image::RgbaImage;
+use crate::types::util::parser_args::ParserArgs;
+use binrw::{binrw, NullString};
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type PALT = ImageColorPalette;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(_args: ParserArgs))]
+pub struct ImageColorPalette {
+    #[br(try_map(NullString::try_into))]
+    #[bw(map(| x: &String | NullString::from(x.clone())))]
+    #[brw(pad_size_to = 64)]
+    #[cfg_attr(test, strategy("[a-zA-Z0-9 ]{0,63}"))]
+    pub file_name: String,
+    pub version: u32,
+    #[br(temp)]
+    #[bw(calc = colors.len() as u32)]
+    num_colors: u32,
+    pub reserved: [u8; 8],
+    #[br(count(num_colors as usize))]
+    pub colors: Vec<Color>,
+}
+
+impl DbpfEntry for ImageColorPalette {
+    fn id(&self) -> DbpfId {
+        DbpfId::ImageColorPalette
+    }
+
+    fn name(&self) -> Option<String> {
+        Some(self.file_name.clone())
+    }
+}
+
+impl ImageColorPalette {
+    /// The color at `index`, black for indices past the end of the palette.
+    #[must_use]
+    pub fn color(&self, index: u8) -> Color {
+        self.colors
+            .get(usize::from(index))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The index of the color closest to `rgb`, never picking `skip`.
+    #[must_use]
+    pub fn nearest(&self, rgb: [u8; 3], skip: Option<u8>) -> u8 {
+        self.colors
+            .iter()
+            .enumerate()
+            .take(256)
+            .filter(|(index, _)| skip.map_or(true, |skip| usize::from(skip) != *index))
+            .min_by_key(|(_, color)| color.distance(rgb))
+            .map_or(0, |(index, _)| index as u8)
+    }
+
+    /// Builds a palette for `images`, with index 0 left as a magenta transparent color and up to
+    /// 255 of the most common opaque colors after it.
+    #[must_use]
+    pub fn from_images(file_name: String, images: &[&RgbaImage]) -> Self {
+        let mut counts: HashMap<[u8; 3], usize> = HashMap::new();
+        for image in images {
+            for pixel in image.pixels().filter(|pixel| pixel[3] != 0) {
+                *counts.entry([pixel[0], pixel[1], pixel[2]]).or_default() += 1;
+            }
+        }
+        let mut colors = if counts.len() <= 255 {
+            counts.into_keys().collect::<Vec<_>>()
+        } else {
+            // Too many colors, merge them into buckets of 5 bits per channel and keep the most
+            // common buckets
+            let mut buckets: HashMap<[u8; 3], (usize, [usize; 3])> = HashMap::new();
+            for (rgb, count) in counts {
+                let bucket = buckets
+                    .entry([rgb[0] >> 3, rgb[1] >> 3, rgb[2] >> 3])
+                    .or_default();
+                bucket.0 += count;
+                for (sum, value) in bucket.1.iter_mut().zip(rgb) {
+                    *sum += usize::from(value) * count;
+                }
+            }
+            let mut buckets = buckets.into_values().collect::<Vec<_>>();
+            buckets.sort_by_key(|bucket| std::cmp::Reverse(bucket.0));
+            buckets
+                .into_iter()
+                .take(255)
+                .map(|(count, sums)| sums.map(|sum| (sum / count) as u8))
+                .collect()
+        };
+        colors.sort_unstable();
+        Self {
+            file_name,
+            version: 1,
+            reserved: [0; 8],
+            colors: std::iter::once(Color::TRANSPARENT)
+                .chain(colors.into_iter().map(|[r, g, b]| Color { r, g, b }))
+                .collect(),
+        }
+    }
+}
+
+#[binrw]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    /// The color Maxis conventionally reserves for transparency.
+    pub const TRANSPARENT: Color = Color {
+        r: 0xFF,
+        g: 0x00,
+        b: 0xFF,
+    };
+
+    #[must_use]
+    pub fn rgb(self) -> [u8; 3] {
+        [self.r, self.g, self.b]
+    }
+
+    fn distance(self, rgb: [u8; 3]) -> u32 {
+        self.rgb()
+            .iter()
+            .zip(rgb)
+            .map(|(&a, b)| (i32::from(a) - i32::from(b)).pow(2) as u32)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+    use proptest::prelude::*;
+
+    test_parsing!(
+        [
+            0x54, 0x65, 0x73, 0x74, 0x46, 0x69, 0x6C, 0x65, //TestFile - in ascii bytes
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // first padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // second padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // third padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fourth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fifth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sixth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // seventh padding block
+            0x01, 0x00, 0x00, 0x00, // version
+            0x02, 0x00, 0x00, 0x00, // number of colors
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // reserved
+            0xFF, 0x00, 0xFF, // first color
+            0x10, 0x20, 0x30, // second color
+        ],
+        ImageColorPalette {
+            file_name: "TestFile".to_string(),
+            version: 1,
+            reserved: [0; 8],
+            colors: vec![
+                Color::TRANSPARENT,
+                Color {
+                    r: 0x10,
+                    g: 0x20,
+                    b: 0x30
+                }
+            ]
+        },
+        ImageColorPalette,
+        palt
+    );
+
+    #[test]
+    fn palette_from_images() {
+        let mut image = RgbaImage::new(2, 1);
+        image.set_pixel(0, 0, [10, 20, 30, 255]);
+        let palette = ImageColorPalette::from_images(String::new(), &[&image]);
+
+        assert_eq!(palette.colors.len(), 2);
+        assert_eq!(palette.nearest([12, 20, 30], Some(0)), 1);
+        assert_eq!(palette.nearest([255, 0, 250], None), 0);
+    }
+}
@@ -4,4 +4,32 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct JpegImage {}
+//! JPEG images, such as lot previews and family portraits. Some are really PNG files, which
+//! decode just the same.
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId};
+use crate::types::util::image::ImageFile;
+use crate::types::util::parser_args::ParserArgs;
+use binrw::binrw;
+use derive_more::{Deref, DerefMut};
+
+pub type JPG = JpegImage;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deref, DerefMut)]
+#[brw(little)]
+#[br(import_raw(args: ParserArgs))]
+pub struct JpegImage {
+    #[br(args_raw = args)]
+    pub image: ImageFile,
+}
+
+impl DbpfEntry for JpegImage {
+    fn id(&self) -> DbpfId {
+        DbpfId::JpegImage
+    }
+
+    fn name(&self) -> Option<String> {
+        self.image.file_name.clone()
+    }
+}
@@ -4,4 +4,181 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct LargeImage {}
+//! Large images (LIFO), a single texture level split out of a TXTR so it can be loaded on its
+//! own. Lot imposters and neighborhood textures are stored this way.
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId};
+use crate::types::util::image::RgbaImage;
+use crate::types::util::parser_args::ParserArgs;
+use crate::types::util::resource_collection::{BlockHeader, ResourceCollection, SgResource};
+use crate::types::util::texture::TextureFormat;
+use binrw::binrw;
+use derive_more::{Deref, DerefMut};
+#[cfg(test)]
+use proptest::collection::vec;
+#[cfg(test)]
+use proptest::prelude::*;
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type LIFO = LargeImage;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Deref, DerefMut)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(args: ParserArgs))]
+pub struct LargeImage {
+    #[br(args_raw = args)]
+    pub collection: ResourceCollection,
+}
+
+impl DbpfEntry for LargeImage {
+    fn id(&self) -> DbpfId {
+        DbpfId::LargeImage
+    }
+
+    fn name(&self) -> Option<String> {
+        self.level_info()
+            .map(|level| level.resource.file_name.clone())
+    }
+}
+
+impl LargeImage {
+    /// Stores `image` in `format`.
+    #[must_use]
+    pub fn from_rgba(file_name: String, image: &RgbaImage, format: TextureFormat) -> Self {
+        Self {
+            collection: ResourceCollection::new(vec![LevelInfo::from_rgba(
+                file_name, image, format,
+            )
+            .into()]),
+        }
+    }
+
+    #[must_use]
+    pub fn level_info(&self) -> Option<&LevelInfo> {
+        self.block()
+    }
+
+    /// Decodes the image, guessing the format from its size since a LIFO doesn't record it.
+    /// Use [`LevelInfo::to_rgba`] instead when the format is known from the TXTR referencing it.
+    #[must_use]
+    pub fn to_rgba(&self) -> Option<RgbaImage> {
+        let level = self.level_info()?;
+        level.to_rgba(level.guess_format()?)
+    }
+}
+
+/// The `cLevelInfo` block, one mip level of a texture.
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct LevelInfo {
+    #[cfg_attr(test, strategy(Just(BlockHeader::of::<LevelInfo>(4))))]
+    pub block: BlockHeader,
+    pub resource: SgResource,
+    pub width: i32,
+    pub height: i32,
+    pub z_level: i32,
+    #[br(temp)]
+    #[bw(calc = data.len() as u32)]
+    data_size: u32,
+    #[br(count(data_size as usize))]
+    #[cfg_attr(test, strategy(vec(proptest::arbitrary::any::<u8>(), 0..64)))]
+    pub data: Vec<u8>,
+}
+
+impl LevelInfo {
+    #[must_use]
+    pub fn from_rgba(file_name: String, image: &RgbaImage, format: TextureFormat) -> Self {
+        Self {
+            block: BlockHeader::of::<Self>(4),
+            resource: SgResource::new(file_name),
+            width: i32::try_from(image.width).unwrap_or(i32::MAX),
+            height: i32::try_from(image.height).unwrap_or(i32::MAX),
+            z_level: 0,
+            data: format.encode(image),
+        }
+    }
+
+    /// The dimensions, `None` if they are negative.
+    #[must_use]
+    pub fn size(&self) -> Option<(u32, u32)> {
+        Some((
+            u32::try_from(self.width).ok()?,
+            u32::try_from(self.height).ok()?,
+        ))
+    }
+
+    #[must_use]
+    pub fn guess_format(&self) -> Option<TextureFormat> {
+        let (width, height) = self.size()?;
+        TextureFormat::guess(width, height, self.data.len())
+    }
+
+    /// Decodes the level, `None` if the data is too short for `format`.
+    #[must_use]
+    pub fn to_rgba(&self, format: TextureFormat) -> Option<RgbaImage> {
+        let (width, height) = self.size()?;
+        format.decode(width, height, &self.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+
+    test_parsing!(
+        [
+            0x00, 0x00, 0x00, 0x00, // number of links
+            0x01, 0x00, 0x00, 0x00, // number of blocks
+            0x36, 0x41, 0x53, 0xED, // block id
+            0x0A, // name length
+            0x63, 0x4C, 0x65, 0x76, 0x65, 0x6C, 0x49, 0x6E, 0x66, 0x6F, // cLevelInfo
+            0x36, 0x41, 0x53, 0xED, // block id
+            0x04, 0x00, 0x00, 0x00, // block version
+            0x0B, // name length
+            0x63, 0x53, 0x47, 0x52, 0x65, 0x73, 0x6F, 0x75, 0x72, 0x63, 0x65, // cSGResource
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x02, 0x00, 0x00, 0x00, // block version
+            0x04, 0x54, 0x65, 0x73, 0x74, // Test
+            0x01, 0x00, 0x00, 0x00, // width
+            0x01, 0x00, 0x00, 0x00, // height
+            0x00, 0x00, 0x00, 0x00, // z level
+            0x04, 0x00, 0x00, 0x00, // data size
+            0x30, 0x20, 0x10, 0xFF, // BGRA pixel
+        ],
+        LargeImage {
+            collection: ResourceCollection::new(vec![LevelInfo {
+                block: BlockHeader::of::<LevelInfo>(4),
+                resource: SgResource::new("Test".to_string()),
+                width: 1,
+                height: 1,
+                z_level: 0,
+                data: vec![0x30, 0x20, 0x10, 0xFF],
+            }
+            .into()]),
+        },
+        LargeImage,
+        lifo
+    );
+
+    #[test]
+    fn lifo_round_trip() {
+        let mut image = RgbaImage::new(4, 4);
+        image.set_pixel(1, 2, [10, 20, 30, 255]);
+        let lifo = LargeImage::from_rgba("Test".to_string(), &image, TextureFormat::Raw24);
+
+        assert_eq!(
+            lifo.level_info().unwrap().guess_format(),
+            Some(TextureFormat::Raw24)
+        );
+        assert_eq!(lifo.to_rgba().unwrap().pixel(1, 2), [10, 20, 30, 255]);
+        assert_eq!(lifo.name(), Some("Test".to_string()));
+    }
+}
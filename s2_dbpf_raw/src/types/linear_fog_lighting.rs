@@ -4,4 +4,122 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct LinearFogLighting {}
+//! Linear fog lighting (5LF), the fog color a lot fades into between two distances.
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId};
+use crate::types::scenegraph::light::LightBase;
+use crate::types::util::parser_args::ParserArgs;
+use crate::types::util::resource_collection::{BlockHeader, ResourceCollection};
+use binrw::binrw;
+use derive_more::{Deref, DerefMut};
+#[cfg(test)]
+use proptest::prelude::*;
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Deref, DerefMut)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(args: ParserArgs))]
+pub struct LinearFogLighting {
+    #[br(args_raw = args)]
+    pub collection: ResourceCollection,
+}
+
+impl DbpfEntry for LinearFogLighting {
+    fn id(&self) -> DbpfId {
+        DbpfId::LinearFogLighting
+    }
+
+    fn name(&self) -> Option<String> {
+        self.light()
+            .map(|light| light.base.resource.file_name.clone())
+    }
+}
+
+impl LinearFogLighting {
+    #[must_use]
+    pub fn light(&self) -> Option<&LinearFogLight> {
+        self.block()
+    }
+
+    pub fn light_mut(&mut self) -> Option<&mut LinearFogLight> {
+        self.block_mut()
+    }
+}
+
+/// The `cLinearFogLight` block.
+#[binrw]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct LinearFogLight {
+    #[cfg_attr(test, strategy(Just(BlockHeader::of::<LinearFogLight>(1))))]
+    pub block: BlockHeader,
+    pub base: LightBase,
+    /// Distance the fog starts at
+    #[cfg_attr(test, strategy(0.0_f32..1000.0))]
+    pub start: f32,
+    /// Distance the fog is fully opaque at
+    #[cfg_attr(test, strategy(0.0_f32..1000.0))]
+    pub end: f32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+
+    test_parsing!(
+        [
+            0x0F, // name length
+            0x63, 0x4C, 0x69, 0x6E, 0x65, 0x61, 0x72, 0x46, 0x6F, 0x67, 0x4C, 0x69, 0x67, 0x68,
+            0x74, // cLinearFogLight
+            0x6F, 0xA6, 0x06, 0xAC, // block id
+            0x01, 0x00, 0x00, 0x00, // block version
+            0x12, // name length
+            0x63, 0x53, 0x74, 0x61, 0x6E, 0x64, 0x61, 0x72, 0x64, 0x4C, 0x69, 0x67, 0x68, 0x74,
+            0x42, 0x61, 0x73, 0x65, // cStandardLightBase
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x0B, 0x00, 0x00, 0x00, // block version
+            0x0B, // name length
+            0x63, 0x53, 0x47, 0x52, 0x65, 0x73, 0x6F, 0x75, 0x72, 0x63, 0x65, // cSGResource
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x02, 0x00, 0x00, 0x00, // block version
+            0x01, 0x4C, // L
+            0x07, // name length
+            0x63, 0x4C, 0x69, 0x67, 0x68, 0x74, 0x54, // cLightT
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x0B, 0x00, 0x00, 0x00, // block version
+            0x0D, // name length
+            0x63, 0x52, 0x65, 0x66, 0x65, 0x72, 0x65, 0x6E, 0x74, 0x4E, 0x6F, 0x64,
+            0x65, // cReferentNode
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x01, 0x00, 0x00, 0x00, // block version
+            0x10, // name length
+            0x63, 0x4F, 0x62, 0x6A, 0x65, 0x63, 0x74, 0x47, 0x72, 0x61, 0x70, 0x68, 0x4E, 0x6F,
+            0x64, 0x65, // cObjectGraphNode
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x04, 0x00, 0x00, 0x00, // block version
+            0x00, 0x00, 0x00, 0x00, // number of extensions
+            0x01, 0x4C, // L
+            0x00, 0x00, 0x80, 0x3F, // intensity
+            0x00, 0x00, 0x80, 0x3F, // red
+            0x00, 0x00, 0x00, 0x3F, // green
+            0x00, 0x00, 0x00, 0x00, // blue
+            0x00, 0x00, 0x20, 0x41, // start
+            0x00, 0x00, 0xC8, 0x42, // end
+        ],
+        LinearFogLight {
+            block: BlockHeader::of::<LinearFogLight>(1),
+            base: LightBase::new("L".to_string(), 1.0, [1.0, 0.5, 0.0]),
+            start: 10.0,
+            end: 100.0,
+        },
+        LinearFogLight,
+        clinearfoglight
+    );
+}
@@ -4,4 +4,55 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct NameReference {}
+//! Name references (NREF), giving the resources that share their group and instance a name.
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId};
+use crate::types::util::parser_args::ParserArgs;
+use binrw::{binrw, NullString};
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type NREF = NameReference;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(_args: ParserArgs))]
+pub struct NameReference {
+    #[br(try_map(NullString::try_into))]
+    #[bw(map(| x: &String | NullString::from(x.clone())))]
+    #[cfg_attr(test, strategy("[a-zA-Z0-9_ ]{0,32}"))]
+    pub name: String,
+}
+
+impl DbpfEntry for NameReference {
+    fn id(&self) -> DbpfId {
+        DbpfId::NameReference
+    }
+
+    fn name(&self) -> Option<String> {
+        Some(self.name.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+    use proptest::prelude::*;
+
+    test_parsing!(
+        [
+            0x63, 0x68, 0x61, 0x69, 0x72, // chair
+            0x00, // null terminator
+        ],
+        NameReference {
+            name: "chair".to_string()
+        },
+        NameReference,
+        nref
+    );
+}
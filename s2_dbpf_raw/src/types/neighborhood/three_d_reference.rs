@@ -4,6 +4,265 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
+//! 3D ID referencing (3IDR): the list of every resource a sim, CAS part or object part is built
+//! from, by key.
+
+use std::collections::HashSet;
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId, DbpfKind};
+use crate::types::package::database_packed_file::{Dbpf, Key};
+use crate::types::util::hash::LOCAL_GROUP;
+use crate::types::util::parser_args::ParserArgs;
+use binrw::binrw;
+#[cfg(test)]
+use proptest::collection::vec;
+#[cfg(test)]
+use proptest::prelude::*;
+
 pub type TrIDR = ThreeDReference;
 
-pub struct ThreeDReference {}
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[brw(little, magic = 0xDEAD_BEEF_u32)]
+#[br(import_raw(_args: ParserArgs))]
+pub struct ThreeDReference {
+    /// 2 if the keys carry a resource id, 1 otherwise
+    pub version: u32,
+    #[br(temp)]
+    #[bw(calc = keys.len() as u32)]
+    num_keys: u32,
+    #[br(args { count: num_keys as usize, inner: (version == 2,) })]
+    pub keys: Vec<Key>,
+}
+
+impl Default for ThreeDReference {
+    fn default() -> Self {
+        Self {
+            version: 2,
+            keys: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+prop_compose! {
+    fn three_d_reference_mapper()(
+        version in 1_u32..3
+    )(
+        keys in vec(any_with::<Key>((version == 2,)), 0..5),
+        version in Just(version),
+    ) -> ThreeDReference {
+        ThreeDReference { version, keys }
+    }
+}
+
+#[cfg(test)]
+impl Arbitrary for ThreeDReference {
+    type Parameters = ();
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        three_d_reference_mapper().boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
+
+impl DbpfEntry for ThreeDReference {
+    fn id(&self) -> DbpfId {
+        DbpfId::ThreeDReference
+    }
+
+    fn name(&self) -> Option<String> {
+        None
+    }
+}
+
+/// An entry of a 3IDR, and the key it matched in the packages searched, if any.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ResolvedReference {
+    pub reference: Key,
+    pub found: Option<Key>,
+}
+
+/// What a 3IDR pulls in, following the 3IDRs it lists in turn.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ReferenceGraph {
+    /// Each 3IDR and a resource it lists, in the order they were reached
+    pub edges: Vec<(Key, Key)>,
+    /// Each 3IDR and an entry of it that isn't in any of the packages
+    pub missing: Vec<(Key, Key)>,
+}
+
+impl ThreeDReference {
+    /// Looks every entry up in `packages`, `from` being the key of this 3IDR.
+    #[must_use]
+    pub fn resolve(&self, packages: &[Dbpf], from: &Key) -> Vec<ResolvedReference> {
+        self.keys
+            .iter()
+            .map(|reference| ResolvedReference {
+                reference: *reference,
+                found: find(packages, Some(from), reference).map(|(key, _)| key),
+            })
+            .collect()
+    }
+
+    /// The graph of resources the 3IDR at `root` depends on, across `packages`.
+    #[must_use]
+    pub fn dependencies(packages: &[Dbpf], root: &Key) -> ReferenceGraph {
+        let mut graph = ReferenceGraph::default();
+        let mut visited = HashSet::new();
+        let mut pending = vec![*root];
+        while let Some(from) = pending.pop() {
+            if !visited.insert(from) {
+                continue;
+            }
+            let reference = match find(packages, None, &from) {
+                Some((_, DbpfKind::ThreeDReference(reference))) => reference,
+                _ => continue,
+            };
+            for resolved in reference.resolve(packages, &from) {
+                match resolved.found {
+                    Some(to) => {
+                        graph.edges.push((from, to));
+                        if to.kind == DbpfId::ThreeDReference {
+                            pending.push(to);
+                        }
+                    }
+                    None => graph.missing.push((from, resolved.reference)),
+                }
+            }
+        }
+        graph
+    }
+}
+
+/// Finds the resource `reference` points at in `packages`, searching them in order. The resource
+/// id only has to match if both keys have one.
+///
+/// `from` is the resource holding the reference. A group of `0xFFFFFFFF` stands for the group of
+/// the package `from` is in, so only that package is searched, for resources in the group of
+/// `from` or stored with the local group themselves. Without `from`, groups have to match
+/// exactly.
+#[must_use]
+pub fn find<'a>(
+    packages: &'a [Dbpf],
+    from: Option<&Key>,
+    reference: &Key,
+) -> Option<(Key, &'a DbpfKind)> {
+    let matches = |key: &Key| {
+        key.kind == reference.kind
+            && key.instance_id == reference.instance_id
+            && (key.resource_id.is_none()
+                || reference.resource_id.is_none()
+                || key.resource_id == reference.resource_id)
+    };
+    let find_in = |dbpf: &'a Dbpf, in_group: &dyn Fn(&Key) -> bool| {
+        dbpf.entries
+            .iter()
+            .find(|(key, _)| matches(key) && in_group(key))
+            .map(|(key, entry)| (*key, &entry.data))
+    };
+    match from {
+        Some(from) if reference.group_id == LOCAL_GROUP => {
+            let dbpf = packages
+                .iter()
+                .find(|dbpf| dbpf.entries.contains_key(from))?;
+            find_in(dbpf, &|key| {
+                key.group_id == from.group_id || key.group_id == LOCAL_GROUP
+            })
+        }
+        _ => packages
+            .iter()
+            .find_map(|dbpf| find_in(dbpf, &|key| key.group_id == reference.group_id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{dbpf_from, test_parsing};
+    use crate::types::name_reference::NameReference;
+    use crate::types::package::database_packed_file::GroupId;
+    use crate::types::package::database_packed_file::{InstanceId, ResourceId};
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+
+    test_parsing!(
+        [
+            0xEF, 0xBE, 0xAD, 0xDE, // magic
+            0x02, 0x00, 0x00, 0x00, // version
+            0x01, 0x00, 0x00, 0x00, // number of keys
+            0x46, 0x45, 0x52, 0x4E, // NREF
+            0x00, 0x00, 0x05, 0x1C, // group
+            0x01, 0x00, 0x00, 0x00, // instance
+            0x02, 0x00, 0x00, 0x00, // resource
+        ],
+        ThreeDReference {
+            version: 2,
+            keys: vec![Key {
+                kind: DbpfId::NameReference,
+                group_id: GroupId(0x1C05_0000),
+                instance_id: InstanceId(1),
+                resource_id: Some(ResourceId(2)),
+            }],
+        },
+        ThreeDReference,
+        three_d_reference
+    );
+
+    fn key(kind: DbpfId, group: u32, instance: u32) -> Key {
+        Key {
+            kind,
+            group_id: GroupId(group),
+            instance_id: InstanceId(instance),
+            resource_id: None,
+        }
+    }
+
+    fn name(name: &str) -> DbpfKind {
+        DbpfKind::NameReference(NameReference {
+            name: name.to_string(),
+        })
+    }
+
+    #[test]
+    fn dependencies() {
+        let root = key(DbpfId::ThreeDReference, 0x1C05_0000, 1);
+        let nested = key(DbpfId::ThreeDReference, 0x1C05_0000, 2);
+        let local = key(DbpfId::NameReference, 0x1C05_0000, 3);
+        let shared = key(DbpfId::NameReference, 0x1C05_0001, 4);
+        let absent = key(DbpfId::NameReference, 0x1C05_0000, 5);
+        // Only in another package, so not in the local group of the part
+        let elsewhere = key(DbpfId::NameReference, 0xFFFF_FFFF, 4);
+        let reference = |keys| DbpfKind::ThreeDReference(ThreeDReference { version: 1, keys });
+
+        let part = dbpf_from(vec![
+            (
+                root,
+                reference(vec![
+                    key(DbpfId::NameReference, 0xFFFF_FFFF, 3),
+                    nested,
+                    absent,
+                    elsewhere,
+                ]),
+            ),
+            (nested, reference(vec![shared, root])),
+            (local, name("local")),
+        ]);
+        let base = dbpf_from(vec![(shared, name("shared"))]);
+        let packages = [part, base];
+
+        let graph = ThreeDReference::dependencies(&packages, &root);
+
+        assert_eq!(
+            graph.edges,
+            vec![
+                (root, local),
+                (root, nested),
+                (nested, shared),
+                (nested, root)
+            ]
+        );
+        assert_eq!(graph.missing, vec![(root, absent), (root, elsewhere)]);
+    }
+}
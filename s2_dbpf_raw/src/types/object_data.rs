@@ -4,13 +4,27 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
+//! The catalog and simulation properties of an object, including the GUIDs other resources
+//! know it by.
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId};
+use crate::types::util::parser_args::ParserArgs;
+use binrw::helpers::until_eof;
 use binrw::{binrw, NullString};
+#[cfg(test)]
+use proptest::{array::uniform8, collection::vec, prelude::*};
+
+pub type OBJD = ObjectData;
 
 #[binrw]
-#[derive(PartialEq, Eq, Debug)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[brw(little)]
+#[br(import_raw(_args: ParserArgs))]
 pub struct ObjectData {
+    #[br(try_map(NullString::try_into))]
+    #[bw(map(| x: &String | NullString::from(x.clone())))]
     #[brw(pad_size_to = 64)]
-    pub filename: NullString,
+    pub filename: String,
     pub version: u32,
     pub initial_stack_size: u16,
     pub default_wall_adjacent_flags: u16,
@@ -104,8 +118,237 @@ pub struct ObjectData {
     pub three_d_object_type: u16,
     pub community_sort_flags: u16,
     pub dream_flags: u16,
-    /*
-    #[deku(pad_bytes_before = "12")]
-    filename_closer: String,
-     */
+    /// Fields newer versions add past the ones above, kept as is up to the end of the resource
+    #[br(parse_with = until_eof)]
+    pub rest: Vec<u8>,
+}
+
+impl DbpfEntry for ObjectData {
+    fn id(&self) -> DbpfId {
+        DbpfId::ObjectData
+    }
+
+    fn name(&self) -> Option<String> {
+        Some(self.filename.clone())
+    }
+}
+
+impl ObjectData {
+    /// The size of the fields this type knows about, the file name included.
+    pub const KNOWN_SIZE: u32 = 64 + 85 * 2 + 8 * 4;
+
+    /// Every field holding a GUID, the object's own first, then those of the objects it points
+    /// at. Zero means unset.
+    #[must_use]
+    pub fn guids(&self) -> [u32; 7] {
+        [
+            self.guid,
+            self.original_guid,
+            self.proxy_guid,
+            self.diagonal_selector_guid,
+            self.grid_aligned_selector_guid,
+            self.job_object_guid,
+            self.object_model_guid,
+        ]
+    }
+
+    /// The fields of [`ObjectData::guids`], in the same order.
+    pub fn guids_mut(&mut self) -> [&mut u32; 7] {
+        [
+            &mut self.guid,
+            &mut self.original_guid,
+            &mut self.proxy_guid,
+            &mut self.diagonal_selector_guid,
+            &mut self.grid_aligned_selector_guid,
+            &mut self.job_object_guid,
+            &mut self.object_model_guid,
+        ]
+    }
+}
+
+#[cfg(test)]
+impl ObjectData {
+    /// Every 16 bit field, in file order.
+    fn u16_fields_mut(&mut self) -> [&mut u16; 85] {
+        [
+            &mut self.initial_stack_size,
+            &mut self.default_wall_adjacent_flags,
+            &mut self.default_placement_flags,
+            &mut self.default_wall_placement_flags,
+            &mut self.default_allowed_height_flags,
+            &mut self.interaction_table_id,
+            &mut self.interaction_group,
+            &mut self.type_of_object,
+            &mut self.multi_tile_master_id,
+            &mut self.multi_tile_sub_index,
+            &mut self.use_default_placement_flags,
+            &mut self.look_at_score,
+            &mut self.item_is_unlockable,
+            &mut self.catalog_use_flags,
+            &mut self.price,
+            &mut self.body_strings_id,
+            &mut self.slot_id,
+            &mut self.object_ownership_flags,
+            &mut self.ignore_globalsim_in_cas_lot,
+            &mut self.cannot_move_out_with,
+            &mut self.hauntable,
+            &mut self.slot_group,
+            &mut self.aspiration_flags,
+            &mut self.memory_nice_or_bad,
+            &mut self.sale_price_different,
+            &mut self.initial_depreciation,
+            &mut self.daily_depreciation,
+            &mut self.self_depreciation,
+            &mut self.deprecation_limit,
+            &mut self.room_sort_flags,
+            &mut self.function_sort_flags,
+            &mut self.catalog_strings_id,
+            &mut self.is_global_sim_object,
+            &mut self.tooltip_name_type,
+            &mut self.template_version,
+            &mut self.niceness_multiplier,
+            &mut self.no_duplicate_on_placement,
+            &mut self.want_category,
+            &mut self.no_new_name_from_template,
+            &mut self.object_version,
+            &mut self.default_thumbnail_id,
+            &mut self.motive_effects_id,
+            &mut self.catalog_popup_id,
+            &mut self.ignore_current_model_index_in_icons,
+            &mut self.level_offset,
+            &mut self.shadow_type,
+            &mut self.num_attributes,
+            &mut self.num_object_arrays,
+            &mut self.unused,
+            &mut self.front_direction,
+            &mut self.unused_1,
+            &mut self.multitile_lead_object,
+            &mut self.expansion_flags,
+            &mut self.unused_2,
+            &mut self.chair_entry_flags,
+            &mut self.tile_width,
+            &mut self.inhibit_suit_copying,
+            &mut self.build_mode_type,
+            &mut self.build_mode_subsort,
+            &mut self.unused_3,
+            &mut self.unused_4,
+            &mut self.footprint_mask,
+            &mut self.unused_5,
+            &mut self.unused_6,
+            &mut self.unused_7,
+            &mut self.unused_8,
+            &mut self.hunger_rating,
+            &mut self.comfort_rating,
+            &mut self.hygiene_rating,
+            &mut self.bladder_rating,
+            &mut self.energy_rating,
+            &mut self.fun_rating,
+            &mut self.room_rating,
+            &mut self.skill_flags,
+            &mut self.num_type_attributes,
+            &mut self.misc_flags,
+            &mut self.unused_9,
+            &mut self.function_subsort,
+            &mut self.downtown_sort,
+            &mut self.keep_buying,
+            &mut self.vacation_sort,
+            &mut self.reset_lot_action,
+            &mut self.three_d_object_type,
+            &mut self.community_sort_flags,
+            &mut self.dream_flags,
+        ]
+    }
+}
+
+#[cfg(test)]
+impl Arbitrary for ObjectData {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    /// Too many fields for a derived strategy, so they're filled in from lists of values.
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        (
+            "[a-zA-Z0-9 ]{0,63}",
+            any::<u32>(),
+            uniform8(any::<u32>()),
+            vec(any::<u16>(), 85),
+            vec(any::<u8>(), 0..8),
+        )
+            .prop_map(|(filename, version, guids, fields, rest)| {
+                let mut objd = ObjectData {
+                    filename,
+                    version,
+                    rest,
+                    ..ObjectData::default()
+                };
+                for (field, value) in objd.guids_mut().into_iter().zip(guids) {
+                    *field = value;
+                }
+                for (field, value) in objd.u16_fields_mut().into_iter().zip(fields) {
+                    *field = value;
+                }
+                objd
+            })
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+
+    fn bytes() -> Vec<u8> {
+        let mut data = b"Chair".to_vec();
+        data.resize(64, 0);
+        data.extend_from_slice(&0x8Bu32.to_le_bytes());
+        data.resize(data.len() + 12 * 2, 0);
+        data.extend_from_slice(&0x1234_5678u32.to_le_bytes());
+        data.resize(ObjectData::KNOWN_SIZE as usize, 0);
+        data
+    }
+
+    test_parsing!(
+        bytes(),
+        ObjectData {
+            filename: "Chair".to_string(),
+            version: 0x8B,
+            guid: 0x1234_5678,
+            ..ObjectData::default()
+        },
+        ObjectData,
+        object_data
+    );
+
+    #[test]
+    fn newer_fields_run_to_the_end() {
+        let mut data = bytes();
+        data.extend_from_slice(&[1, 2, 3]);
+
+        let objd: ObjectData = Cursor::new(data).read_le().unwrap();
+
+        assert_eq!(objd.rest, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn newer_fields_stop_at_the_end_of_the_entry() {
+        use crate::constants::data_kinds::DbpfKind;
+        use crate::test_helpers::package_bytes;
+        use crate::types::package::database_packed_file::{Dbpf, GroupId, InstanceId, Key};
+
+        let mut data = bytes();
+        data.extend_from_slice(&[1, 2, 3]);
+        let key = Key::new(DbpfId::ObjectData, GroupId(1), InstanceId(1), None);
+        let next = Key::new(DbpfId::JpegImage, GroupId(1), InstanceId(2), None);
+        let package = package_bytes(&[(key, data), (next, vec![0xCC; 8])]);
+
+        let dbpf: Dbpf = Cursor::new(package).read_le().unwrap();
+
+        match &dbpf.entries[&key].data {
+            DbpfKind::ObjectData(objd) => assert_eq!(objd.rest, vec![1, 2, 3]),
+            other => panic!("{:?}", other),
+        }
+    }
 }
@@ -4,4 +4,307 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct ObjectFunction {}
+//! Table of the BHAVs the game calls into an object with, such as its init and main functions.
+//! Entries are positional, the position of an entry decides which function it is.
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId};
+use crate::types::package::database_packed_file::{GroupId, Key};
+use crate::types::simantic::behavior_function::BehaviorFunction;
+use crate::types::simantic::global_data::CallResolver;
+use crate::types::util::parser_args::ParserArgs;
+use binrw::{binrw, NullString};
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type OBJF = ObjectFunction;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(_args: ParserArgs))]
+pub struct ObjectFunction {
+    #[br(try_map(NullString::try_into))]
+    #[bw(map(| x: &String | NullString::from(x.clone())))]
+    #[brw(pad_size_to = 64)]
+    #[cfg_attr(test, strategy("[a-zA-Z0-9 ]{0,63}"))]
+    pub file_name: String,
+    pub unknown_1: u32,
+    pub unknown_2: u32,
+    #[brw(magic(b"fJBO"))]
+    #[br(temp)]
+    #[bw(calc = functions.len() as u32)]
+    num_functions: u32,
+    #[br(count(num_functions as usize))]
+    pub functions: Vec<FunctionEntry>,
+}
+
+impl DbpfEntry for ObjectFunction {
+    fn id(&self) -> DbpfId {
+        DbpfId::ObjectFunction
+    }
+
+    fn name(&self) -> Option<String> {
+        Some(self.file_name.clone())
+    }
+}
+
+impl ObjectFunction {
+    #[must_use]
+    pub fn get(&self, slot: FunctionSlot) -> Option<&FunctionEntry> {
+        self.functions.get(slot as usize)
+    }
+
+    /// Sets the entry for `slot`, growing the table with empty entries if it is too short.
+    pub fn set(&mut self, slot: FunctionSlot, entry: FunctionEntry) {
+        let index = slot as usize;
+        if self.functions.len() <= index {
+            self.functions.resize(index + 1, FunctionEntry::default());
+        }
+        self.functions[index] = entry;
+    }
+
+    /// Pairs every entry with its named slot, entries past the known slots are skipped.
+    pub fn iter(&self) -> impl Iterator<Item = (FunctionSlot, &FunctionEntry)> {
+        FunctionSlot::ALL.iter().copied().zip(self.functions.iter())
+    }
+
+    /// Looks up the action BHAV for `slot` of the object in `group`. Entries are called like
+    /// any other BHAV call, so they can point into the object's group or into its global or
+    /// semiglobal libraries.
+    #[must_use]
+    pub fn action<'a>(
+        &self,
+        slot: FunctionSlot,
+        resolver: &CallResolver<'a>,
+        group: GroupId,
+    ) -> Option<(&'a Key, &'a BehaviorFunction)> {
+        self.get(slot)
+            .and_then(|entry| resolver.resolve(group, entry.action))
+    }
+
+    /// Looks up the guard BHAV for `slot` of the object in `group`, see [`Self::action`].
+    #[must_use]
+    pub fn guard<'a>(
+        &self,
+        slot: FunctionSlot,
+        resolver: &CallResolver<'a>,
+        group: GroupId,
+    ) -> Option<(&'a Key, &'a BehaviorFunction)> {
+        self.get(slot)
+            .and_then(|entry| resolver.resolve(group, entry.guard))
+    }
+}
+
+/// A pair of BHAV instance ids, 0 if unset.
+#[binrw]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct FunctionEntry {
+    pub guard: u16,
+    pub action: u16,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[repr(usize)]
+pub enum FunctionSlot {
+    Init = 0,
+    Main,
+    Load,
+    Cleanup,
+    QueueSkipped,
+    AllowIntersection,
+    WallAdjacencyChanged,
+    RoomChanged,
+    DynamicMultiTileUpdate,
+    Placement,
+    Pickup,
+    UserPlacement,
+    UserPickup,
+    LevelInfoRequest,
+    ServingSurface,
+    Portal,
+    Gardening,
+    WashHands,
+    Prep,
+    Cook,
+    Surface,
+    Dispose,
+    Food,
+    PickupFromSlot,
+    WashDish,
+    EatingSurface,
+    Sit,
+    Stand,
+    Clean,
+    Repair,
+    UiEventHandler,
+    Deliver,
+    Reset,
+}
+
+impl FunctionSlot {
+    pub const ALL: [FunctionSlot; 33] = [
+        FunctionSlot::Init,
+        FunctionSlot::Main,
+        FunctionSlot::Load,
+        FunctionSlot::Cleanup,
+        FunctionSlot::QueueSkipped,
+        FunctionSlot::AllowIntersection,
+        FunctionSlot::WallAdjacencyChanged,
+        FunctionSlot::RoomChanged,
+        FunctionSlot::DynamicMultiTileUpdate,
+        FunctionSlot::Placement,
+        FunctionSlot::Pickup,
+        FunctionSlot::UserPlacement,
+        FunctionSlot::UserPickup,
+        FunctionSlot::LevelInfoRequest,
+        FunctionSlot::ServingSurface,
+        FunctionSlot::Portal,
+        FunctionSlot::Gardening,
+        FunctionSlot::WashHands,
+        FunctionSlot::Prep,
+        FunctionSlot::Cook,
+        FunctionSlot::Surface,
+        FunctionSlot::Dispose,
+        FunctionSlot::Food,
+        FunctionSlot::PickupFromSlot,
+        FunctionSlot::WashDish,
+        FunctionSlot::EatingSurface,
+        FunctionSlot::Sit,
+        FunctionSlot::Stand,
+        FunctionSlot::Clean,
+        FunctionSlot::Repair,
+        FunctionSlot::UiEventHandler,
+        FunctionSlot::Deliver,
+        FunctionSlot::Reset,
+    ];
+
+    #[must_use]
+    pub fn from_index(index: usize) -> Option<Self> {
+        Self::ALL.get(index).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{dbpf_from, test_parsing};
+    use crate::types::package::database_packed_file::InstanceId;
+    use crate::types::simantic::behavior_function::Signature;
+    use crate::types::simantic::global_data::GLOBAL_GROUP;
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+    use proptest::prelude::*;
+
+    test_parsing!(
+        [
+            0x54, 0x65, 0x73, 0x74, 0x46, 0x69, 0x6C, 0x65, //TestFile - in ascii bytes
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // first padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // second padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // third padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fourth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fifth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sixth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // seventh padding block
+            0x00, 0x00, 0x00, 0x00, // unknown 1
+            0x00, 0x00, 0x00, 0x00, // unknown 2
+            0x66, 0x4A, 0x42, 0x4F, // fJBO
+            0x02, 0x00, 0x00, 0x00, // number of functions
+            0x00, 0x00, 0x01, 0x10, // init - no guard, 0x1001 action
+            0x02, 0x10, 0x03, 0x10, // main - 0x1002 guard, 0x1003 action
+        ],
+        ObjectFunction {
+            file_name: "TestFile".to_string(),
+            unknown_1: 0,
+            unknown_2: 0,
+            functions: vec![
+                FunctionEntry {
+                    guard: 0,
+                    action: 0x1001
+                },
+                FunctionEntry {
+                    guard: 0x1002,
+                    action: 0x1003
+                },
+            ]
+        },
+        ObjectFunction,
+        objf
+    );
+
+    #[test]
+    fn set_grows_table() {
+        let mut objf = ObjectFunction {
+            file_name: String::new(),
+            unknown_1: 0,
+            unknown_2: 0,
+            functions: vec![],
+        };
+        let entry = FunctionEntry {
+            guard: 0,
+            action: 0x1000,
+        };
+        objf.set(FunctionSlot::Cleanup, entry);
+
+        assert_eq!(objf.functions.len(), 4);
+        assert_eq!(objf.get(FunctionSlot::Cleanup), Some(&entry));
+        assert_eq!(
+            objf.get(FunctionSlot::Init),
+            Some(&FunctionEntry::default())
+        );
+    }
+
+    #[test]
+    fn action_lookup() {
+        let group = GroupId(0x7F00_0001);
+        let bhav = |file_name: &str| BehaviorFunction {
+            file_name: file_name.to_string(),
+            signature: Signature::Seven,
+            tree_type: 0,
+            num_parameters: 0,
+            num_locals: 0,
+            flag: 0,
+            tree_version: 0,
+            instructions: vec![],
+        };
+        let init = Key::new(DbpfId::BehaviorFunction, group, InstanceId(0x1001), None);
+        let reset = Key::new(
+            DbpfId::BehaviorFunction,
+            GLOBAL_GROUP,
+            InstanceId(0x0105),
+            None,
+        );
+        let object = dbpf_from(vec![(init, bhav("Init").into())]);
+        let libraries = vec![dbpf_from(vec![(reset, bhav("Reset").into())])];
+        let resolver = CallResolver::new(&object, &libraries);
+        let objf = ObjectFunction {
+            file_name: String::new(),
+            unknown_1: 0,
+            unknown_2: 0,
+            functions: vec![
+                FunctionEntry {
+                    guard: 0,
+                    action: 0x1001,
+                },
+                FunctionEntry {
+                    guard: 0x0105,
+                    action: 0x1002,
+                },
+            ],
+        };
+
+        assert_eq!(
+            objf.action(FunctionSlot::Init, &resolver, group),
+            Some((&init, &bhav("Init")))
+        );
+        assert_eq!(objf.guard(FunctionSlot::Init, &resolver, group), None);
+        assert_eq!(
+            objf.guard(FunctionSlot::Main, &resolver, group),
+            Some((&reset, &bhav("Reset")))
+        );
+        assert_eq!(objf.action(FunctionSlot::Main, &resolver, group), None);
+        assert_eq!(objf.action(FunctionSlot::Load, &resolver, group), None);
+    }
+}
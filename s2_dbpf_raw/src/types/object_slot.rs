@@ -4,4 +4,493 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct ObjectSlot {}
+//! The slots of an object: where sims route to when using it, where other objects can be
+//! placed on it and where it can be targeted from. Each version appends fields to every slot.
+
+use std::cmp::Ordering;
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId};
+use crate::types::util::parser_args::ParserArgs;
+use binrw::{binrw, NullString};
+#[cfg(test)]
+use proptest::collection::vec;
+#[cfg(test)]
+use proptest::prelude::*;
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type SLOT = ObjectSlot;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(_args: ParserArgs))]
+pub struct ObjectSlot {
+    #[br(try_map(NullString::try_into))]
+    #[bw(map(| x: &String | NullString::from(x.clone())))]
+    #[brw(pad_size_to = 64)]
+    #[cfg_attr(test, strategy("[a-zA-Z0-9 ]{0,63}"))]
+    pub file_name: String,
+    // Always zero in every file seen so far
+    pub unknown: u32,
+    pub version: Version,
+    #[brw(magic(b"TOLS"))]
+    #[br(temp)]
+    #[bw(calc = slots.len() as u32)]
+    num_slots: u32,
+    #[br(args { count: num_slots as usize, inner: (version,) })]
+    #[cfg_attr(test, strategy(vec(any_with::< Slot > ((# version,)), (0..10))))]
+    pub slots: Vec<Slot>,
+}
+
+impl DbpfEntry for ObjectSlot {
+    fn id(&self) -> DbpfId {
+        DbpfId::ObjectSlot
+    }
+
+    fn name(&self) -> Option<String> {
+        Some(self.file_name.clone())
+    }
+}
+
+impl ObjectSlot {
+    #[must_use]
+    pub fn new(file_name: String, version: Version) -> Self {
+        Self {
+            file_name,
+            unknown: 0,
+            version,
+            slots: vec![],
+        }
+    }
+
+    /// Appends a slot with every field this table's version stores, returning it for editing.
+    pub fn add_slot(&mut self, kind: SlotKind) -> &mut Slot {
+        let index = self.slots.len();
+        self.slots.push(Slot::new(kind, self.version));
+        &mut self.slots[index]
+    }
+
+    pub fn slots_of(&self, kind: SlotKind) -> impl Iterator<Item = &Slot> {
+        self.slots.iter().filter(move |slot| slot.kind == kind)
+    }
+
+    pub fn routing_slots(&self) -> impl Iterator<Item = &Slot> {
+        self.slots_of(SlotKind::Routing)
+    }
+
+    pub fn container_slots(&self) -> impl Iterator<Item = &Slot> {
+        self.slots_of(SlotKind::Container)
+    }
+
+    pub fn target_slots(&self) -> impl Iterator<Item = &Slot> {
+        self.slots_of(SlotKind::Target)
+    }
+}
+
+/// SLOT format version. Versions this crate doesn't name are kept as [`Version::Other`] and
+/// read with the fields of every known version below them.
+#[binrw]
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub enum Version {
+    #[brw(magic(0x04_u32))]
+    Four,
+    #[brw(magic(0x05_u32))]
+    Five,
+    #[brw(magic(0x06_u32))]
+    Six,
+    #[brw(magic(0x07_u32))]
+    Seven,
+    #[brw(magic(0x08_u32))]
+    Eight,
+    #[brw(magic(0x09_u32))]
+    Nine,
+    #[brw(magic(0x0A_u32))]
+    Ten,
+    Other(#[cfg_attr(test, strategy(0x0B_u32..))] u32),
+}
+
+impl Version {
+    #[must_use]
+    pub fn value(self) -> u32 {
+        match self {
+            Version::Four => 0x04,
+            Version::Five => 0x05,
+            Version::Six => 0x06,
+            Version::Seven => 0x07,
+            Version::Eight => 0x08,
+            Version::Nine => 0x09,
+            Version::Ten => 0x0A,
+            Version::Other(value) => value,
+        }
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.value().partial_cmp(&other.value())
+    }
+}
+
+impl Default for Version {
+    fn default() -> Self {
+        Version::Ten
+    }
+}
+
+#[binrw]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub enum SlotKind {
+    /// Where another object can be placed
+    #[brw(magic(0_u16))]
+    Container,
+    #[brw(magic(1_u16))]
+    Location,
+    /// Where a sim stands or sits to use the object
+    #[brw(magic(3_u16))]
+    Routing,
+    /// Where a sim can target the object from
+    #[brw(magic(4_u16))]
+    Target,
+    Other(#[cfg_attr(test, strategy(5_u16..))] u16),
+}
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq)]
+#[brw(little)]
+#[br(import(version: Version))]
+pub struct Slot {
+    pub kind: SlotKind,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    /// Height a standing sim's hands reach into the slot at
+    pub standing: i32,
+    /// Height a sitting sim's hands reach into the slot at
+    pub sitting: i32,
+    /// Height a sim on the ground reaches into the slot at
+    pub ground: i32,
+    /// Routing flags, see the `FLAG_` constants
+    pub flags: i32,
+    /// Index of the slot a sim snaps to after routing here, -1 for none
+    pub snap_target_slot: i32,
+    #[br(if(version >= Version::Six))]
+    pub proximity: Option<Proximity>,
+    #[br(if(version >= Version::Seven))]
+    pub gradient: Option<f32>,
+    #[br(if(version >= Version::Eight))]
+    pub height: Option<i32>,
+    #[br(if(version >= Version::Nine))]
+    #[bw(map = |x: &Option<Facing>| x.map(i32::from))]
+    #[br(map = |x: Option<i32>| x.map(Facing::from))]
+    pub facing: Option<Facing>,
+    #[br(if(version >= Version::Ten))]
+    pub resolution: Option<i32>,
+}
+
+impl Slot {
+    pub const FLAG_NORTH: i32 = 0x0001;
+    pub const FLAG_NORTH_EAST: i32 = 0x0002;
+    pub const FLAG_EAST: i32 = 0x0004;
+    pub const FLAG_SOUTH_EAST: i32 = 0x0008;
+    pub const FLAG_SOUTH: i32 = 0x0010;
+    pub const FLAG_SOUTH_WEST: i32 = 0x0020;
+    pub const FLAG_WEST: i32 = 0x0040;
+    pub const FLAG_NORTH_WEST: i32 = 0x0080;
+    pub const FLAG_ALLOW_ANY_ROTATION: i32 = 0x0100;
+    pub const FLAG_ABSOLUTE: i32 = 0x0200;
+    pub const FLAG_FACING_AWAY_FROM_OBJECT: i32 = 0x0400;
+    pub const FLAG_IGNORE_ROOMS: i32 = 0x0800;
+    pub const FLAG_SNAP_TO_DIRECTION: i32 = 0x1000;
+    pub const FLAG_RANDOM_SCORING: i32 = 0x2000;
+    pub const FLAG_ALLOW_FAILURE_TREES: i32 = 0x4000;
+    pub const FLAG_ALLOW_DIFFERENT_ALTITUDES: i32 = 0x8000;
+    pub const FLAG_USE_AVERAGE_OBJECT_LOCATION: i32 = 0x1_0000;
+
+    /// A slot at the origin with every field present that `version` stores.
+    #[must_use]
+    pub fn new(kind: SlotKind, version: Version) -> Self {
+        Self {
+            kind,
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+            standing: 0,
+            sitting: 0,
+            ground: 0,
+            flags: 0,
+            snap_target_slot: -1,
+            proximity: (version >= Version::Six).then(Proximity::default),
+            gradient: (version >= Version::Seven).then(|| 0.0),
+            height: (version >= Version::Eight).then(|| 0),
+            facing: (version >= Version::Nine).then(|| Facing::Anywhere),
+            resolution: (version >= Version::Ten).then(|| 0),
+        }
+    }
+
+    #[must_use]
+    pub fn offset(&self) -> (f32, f32, f32) {
+        (self.x, self.y, self.z)
+    }
+
+    pub fn set_offset(&mut self, x: f32, y: f32, z: f32) {
+        self.x = x;
+        self.y = y;
+        self.z = z;
+    }
+
+    #[must_use]
+    pub fn has_flag(&self, flag: i32) -> bool {
+        self.flags & flag == flag
+    }
+
+    pub fn set_flag(&mut self, flag: i32, value: bool) {
+        if value {
+            self.flags |= flag;
+        } else {
+            self.flags &= !flag;
+        }
+    }
+
+    /// The slot a sim snaps to after routing here.
+    #[must_use]
+    pub fn snap_target<'a>(&self, table: &'a ObjectSlot) -> Option<&'a Slot> {
+        usize::try_from(self.snap_target_slot)
+            .ok()
+            .and_then(|i| table.slots.get(i))
+    }
+}
+
+/// How close a sim routes to the slot, in sixteenths of a tile.
+#[binrw]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct Proximity {
+    pub min: i32,
+    pub max: i32,
+    pub optimal: i32,
+    pub max_size: i32,
+    pub unknown: i32,
+}
+
+/// The direction a sim faces once in the slot.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Facing {
+    Anywhere,
+    TowardsObject,
+    AwayFromObject,
+    /// One of the eight compass directions, 0 is north going clockwise. The game doesn't use
+    /// anything past 7 but larger values are kept as they are.
+    Direction(u8),
+    /// Any value not covered by the other variants
+    Other(i32),
+}
+
+impl From<i32> for Facing {
+    fn from(value: i32) -> Self {
+        match value {
+            -3 => Facing::Anywhere,
+            -2 => Facing::TowardsObject,
+            -1 => Facing::AwayFromObject,
+            _ => match u8::try_from(value) {
+                Ok(direction) => Facing::Direction(direction),
+                Err(_) => Facing::Other(value),
+            },
+        }
+    }
+}
+
+impl From<Facing> for i32 {
+    fn from(value: Facing) -> Self {
+        match value {
+            Facing::Anywhere => -3,
+            Facing::TowardsObject => -2,
+            Facing::AwayFromObject => -1,
+            Facing::Direction(direction) => i32::from(direction),
+            Facing::Other(other) => other,
+        }
+    }
+}
+
+#[cfg(test)]
+prop_compose! {
+    fn slot_mapper(
+        version: Version
+    )(
+        kind in any::<SlotKind>(),
+        x in -100.0_f32..100.0,
+        y in -100.0_f32..100.0,
+        z in -100.0_f32..100.0,
+        standing in any::<i32>(),
+        sitting in any::<i32>(),
+        ground in any::<i32>(),
+        flags in any::<i32>(),
+        snap_target_slot in any::<i32>(),
+        proximity in any::<Proximity>(),
+        gradient in -100.0_f32..100.0,
+        height in any::<i32>(),
+        facing in any::<i32>(),
+        resolution in any::<i32>(),
+    ) -> Slot {
+        Slot {
+            kind,
+            x,
+            y,
+            z,
+            standing,
+            sitting,
+            ground,
+            flags,
+            snap_target_slot,
+            proximity: (version >= Version::Six).then(|| proximity),
+            gradient: (version >= Version::Seven).then(|| gradient),
+            height: (version >= Version::Eight).then(|| height),
+            facing: (version >= Version::Nine).then(|| Facing::from(facing)),
+            resolution: (version >= Version::Ten).then(|| resolution),
+        }
+    }
+}
+
+#[cfg(test)]
+impl Arbitrary for Slot {
+    type Parameters = (Version,);
+
+    fn arbitrary_with(args: Self::Parameters) -> Self::Strategy {
+        slot_mapper(args.0).boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+
+    test_parsing!(
+        [
+            0x54, 0x65, 0x73, 0x74, 0x46, 0x69, 0x6C, 0x65, //TestFile - in ascii bytes
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // first padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // second padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // third padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fourth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fifth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sixth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // seventh padding block
+            0x00, 0x00, 0x00, 0x00, // unknown
+            0x09, 0x00, 0x00, 0x00, // version
+            0x54, 0x4F, 0x4C, 0x53, // TOLS
+            0x01, 0x00, 0x00, 0x00, // number of slots
+            0x03, 0x00, // kind
+            0x00, 0x00, 0x80, 0x3F, // x
+            0x00, 0x00, 0x00, 0x00, // y
+            0x00, 0x00, 0x00, 0xBF, // z
+            0x05, 0x00, 0x00, 0x00, // standing
+            0x06, 0x00, 0x00, 0x00, // sitting
+            0x00, 0x00, 0x00, 0x00, // ground
+            0x01, 0x01, 0x00, 0x00, // flags
+            0xFF, 0xFF, 0xFF, 0xFF, // snap target slot
+            0x00, 0x00, 0x00, 0x00, // min proximity
+            0x10, 0x00, 0x00, 0x00, // max proximity
+            0x08, 0x00, 0x00, 0x00, // optimal proximity
+            0x00, 0x00, 0x00, 0x00, // max size
+            0x00, 0x00, 0x00, 0x00, // unknown
+            0x00, 0x00, 0x00, 0x00, // gradient
+            0x00, 0x00, 0x00, 0x00, // height
+            0xFE, 0xFF, 0xFF, 0xFF, // facing
+        ],
+        ObjectSlot {
+            file_name: "TestFile".to_string(),
+            unknown: 0,
+            version: Version::Nine,
+            slots: vec![Slot {
+                kind: SlotKind::Routing,
+                x: 1.0,
+                y: 0.0,
+                z: -0.5,
+                standing: 5,
+                sitting: 6,
+                ground: 0,
+                flags: Slot::FLAG_NORTH | Slot::FLAG_ALLOW_ANY_ROTATION,
+                snap_target_slot: -1,
+                proximity: Some(Proximity {
+                    min: 0,
+                    max: 16,
+                    optimal: 8,
+                    max_size: 0,
+                    unknown: 0,
+                }),
+                gradient: Some(0.0),
+                height: Some(0),
+                facing: Some(Facing::TowardsObject),
+                resolution: None,
+            }]
+        },
+        ObjectSlot,
+        slot
+    );
+
+    #[test]
+    fn authored_slots_round_trip() {
+        let mut table = ObjectSlot::new("Chair".to_string(), Version::Ten);
+        let routing = table.add_slot(SlotKind::Routing);
+        routing.set_offset(0.0, -0.6, 0.0);
+        routing.set_flag(Slot::FLAG_SOUTH, true);
+        routing.facing = Some(Facing::Direction(4));
+        table.add_slot(SlotKind::Container);
+
+        assert_eq!(table.routing_slots().count(), 1);
+        assert_eq!(table.container_slots().count(), 1);
+        assert_eq!(table.target_slots().count(), 0);
+
+        let mut cursor = Cursor::new(vec![]);
+        cursor.write_le(&table).unwrap();
+        cursor.set_position(0);
+        let out: ObjectSlot = cursor.read_le().unwrap();
+
+        assert_eq!(out, table);
+        assert!(out.slots[0].has_flag(Slot::FLAG_SOUTH));
+        assert_eq!(out.slots[0].resolution, Some(0));
+    }
+
+    #[test]
+    fn facing_round_trip() {
+        for facing in [
+            Facing::Anywhere,
+            Facing::TowardsObject,
+            Facing::AwayFromObject,
+            Facing::Direction(0),
+            Facing::Direction(7),
+            Facing::Direction(8),
+            Facing::Direction(u8::MAX),
+            Facing::Other(0x100),
+            Facing::Other(-4),
+        ] {
+            assert_eq!(Facing::from(i32::from(facing)), facing);
+        }
+    }
+
+    #[test]
+    fn unknown_version_and_kind() {
+        let mut table = ObjectSlot::new(String::new(), Version::Other(3));
+        table.add_slot(SlotKind::Other(2));
+
+        assert!(table.version < Version::Four);
+        assert_eq!(table.slots[0].proximity, None);
+
+        let mut cursor = Cursor::new(vec![]);
+        cursor.write_le(&table).unwrap();
+        cursor.set_position(0);
+        let out: ObjectSlot = cursor.read_le().unwrap();
+
+        assert_eq!(out, table);
+    }
+}
@@ -41,7 +41,6 @@ impl BinRead for Dbpf {
         _args: Self::Args,
     ) -> BinResult<Self> {
         let header = Header::read(reader)?;
-        let store_pos = reader.stream_position()?;
         reader.seek(SeekFrom::Start(u64::from(header.index_position.0)))?;
         let has_resource = header.has_resource_id();
         let index_table =
@@ -65,23 +64,32 @@ impl BinRead for Dbpf {
         } else {
             None
         };
-        reader.seek(SeekFrom::Start(store_pos))?;
 
         let mut entries_table = HashMap::new();
         for (key, entry) in index_table.table {
-            let parser_args = ParserArgs {
-                header,
-                index_entry: entry,
-            };
-            let new_kind = if let Some(ref compression_table) = compression_table {
-                let decompressed_size = compression_table.table[&key].decompressed_size.0;
+            // Every entry is parsed from a buffer of its own, so resources that run to the end of
+            // their data stop at the end of the entry rather than the end of the package
+            reader.seek(SeekFrom::Start(u64::from(entry.location.0)))?;
+            let mut data = vec![0u8; entry.size.0 as usize];
+            reader.read_exact(&mut data)?;
+            let compressed = compression_table
+                .as_ref()
+                .and_then(|compression_table| compression_table.table.get(&key));
+            let mut buffer = if let Some(compressed) = compressed {
+                let decompressed_size = compressed.decompressed_size.0;
                 let mut decomp_buffer = Cursor::new(vec![0u8; decompressed_size as usize]);
-                decompress(reader, &mut decomp_buffer).expect("Decompression failed");
+                decompress(&mut Cursor::new(data), &mut decomp_buffer)
+                    .expect("Decompression failed");
                 decomp_buffer.set_position(0);
-                DbpfKind::parse(&mut decomp_buffer, key.kind, options, parser_args)?
+                decomp_buffer
             } else {
-                DbpfKind::parse(reader, key.kind, options, parser_args)?
+                Cursor::new(data)
             };
+            let parser_args = ParserArgs {
+                header,
+                index_entry: entry,
+            };
+            let new_kind = DbpfKind::parse(&mut buffer, key.kind, options, parser_args)?;
             let new_entry = Entry {
                 compressed: false,
                 data: new_kind,
@@ -96,6 +104,24 @@ impl BinRead for Dbpf {
     }
 }
 
+impl Dbpf {
+    /// Finds an entry by type, group and instance, ignoring the resource id.
+    #[must_use]
+    pub fn find(
+        &self,
+        kind: DbpfId,
+        group_id: GroupId,
+        instance_id: InstanceId,
+    ) -> Option<(&Key, &DbpfKind)> {
+        self.entries
+            .iter()
+            .find(|(key, _)| {
+                key.kind == kind && key.group_id == group_id && key.instance_id == instance_id
+            })
+            .map(|(key, entry)| (key, &entry.data))
+    }
+}
+
 /*
 impl BinWrite for Dbpf {
     type Args = ();
@@ -154,3 +180,28 @@ pub struct Entry {
     pub compressed: bool,
     pub data: DbpfKind,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::package_bytes;
+    use binrw::BinReaderExt;
+
+    #[test]
+    fn entries_stop_at_their_size() {
+        let first = Key::new(DbpfId::JpegImage, GroupId(1), InstanceId(1), None);
+        let second = Key::new(DbpfId::JpegImage, GroupId(1), InstanceId(2), None);
+        let jpeg = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x01, 0x02];
+        let png = b"\x89PNG\x0D\x0A\x1A\x0A".to_vec();
+        let bytes = package_bytes(&[(first, jpeg.clone()), (second, png.clone())]);
+
+        let dbpf: Dbpf = Cursor::new(bytes).read_le().unwrap();
+
+        for (key, data) in [(first, jpeg), (second, png)] {
+            match &dbpf.entries[&key].data {
+                DbpfKind::JpegImage(image) => assert_eq!(image.data, data),
+                other => panic!("{:?}", other),
+            }
+        }
+    }
+}
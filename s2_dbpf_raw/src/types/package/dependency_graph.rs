@@ -0,0 +1,577 @@
+////////////////////////////////////////////////////////////////////////////////
+// This Source Code Form is subject to the terms of the Mozilla Public         /
+// License, v. 2.0. If a copy of the MPL was not distributed with this         /
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
+////////////////////////////////////////////////////////////////////////////////
+
+//! Which resources refer to which, across any number of packages, to tell what can be deleted
+//! without breaking anything.
+
+use std::collections::HashSet;
+
+use crate::constants::data_kinds::{DbpfId, DbpfKind};
+use crate::types::neighborhood::three_d_reference::find;
+use crate::types::object_data::ObjectData;
+use crate::types::package::database_packed_file::{Dbpf, InstanceId, Key};
+use crate::types::simantic::behavior_function::Instruction;
+use crate::types::simantic::global_data::CallResolver;
+use crate::types::util::hash::{name_key, with_suffix};
+use crate::types::util::resource_collection::ResourceLink;
+
+/// The STR# in an object's group listing the CRES names its models are built from.
+pub const MODEL_NAMES_INSTANCE: InstanceId = InstanceId(0x85);
+
+/// Operands of primitives that can read a BCON entry: the opcode, then the offsets of the owner
+/// byte and of the data word it applies to.
+const CONSTANT_OPERANDS: [(u16, usize, usize); 2] = [
+    // Expression, left hand side
+    (0x0002, 6, 0),
+    // Expression, right hand side
+    (0x0002, 7, 2),
+];
+
+/// The owner of operands whose data names a BCON entry: the BCON in the high 9 bits and the
+/// entry in the low 7.
+const TUNING_OWNER: u8 = 0x1A;
+
+/// References between the resources of a set of packages, `(from, to)` meaning `from` refers
+/// to `to`. Only references to resources found in the packages become edges.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DependencyGraph {
+    /// Every resource in the packages
+    pub resources: Vec<Key>,
+    pub edges: Vec<(Key, Key)>,
+}
+
+impl DependencyGraph {
+    /// Walks every resource in `packages`. BHAV calls are resolved from the package holding
+    /// the caller, falling back to the rest of `packages`.
+    #[must_use]
+    pub fn build(packages: &[Dbpf]) -> Self {
+        let mut builder = Builder {
+            packages,
+            seen: HashSet::new(),
+            graph: DependencyGraph::default(),
+        };
+        let mut resources = HashSet::new();
+        for dbpf in packages {
+            for (key, entry) in &dbpf.entries {
+                if resources.insert(*key) {
+                    builder.graph.resources.push(*key);
+                }
+                builder.visit(dbpf, *key, &entry.data);
+            }
+        }
+        builder.graph
+    }
+
+    /// The resources `key` refers to.
+    #[must_use]
+    pub fn dependencies(&self, key: &Key) -> Vec<Key> {
+        self.edges
+            .iter()
+            .filter(|(from, _)| from == key)
+            .map(|(_, to)| *to)
+            .collect()
+    }
+
+    /// The resources referring to `key`, `key` is safe to delete if there are none.
+    #[must_use]
+    pub fn dependents(&self, key: &Key) -> Vec<Key> {
+        self.edges
+            .iter()
+            .filter(|(_, to)| to == key)
+            .map(|(from, _)| *from)
+            .collect()
+    }
+
+    /// Resources nothing refers to. Besides orphans this includes roots such as OBJDs and
+    /// GZPSs, which the game finds on its own.
+    #[must_use]
+    pub fn unreferenced(&self) -> Vec<Key> {
+        let referenced: HashSet<&Key> = self.edges.iter().map(|(_, to)| to).collect();
+        self.resources
+            .iter()
+            .filter(|key| !referenced.contains(key))
+            .copied()
+            .collect()
+    }
+
+    /// `roots` and every resource reached by following references from them.
+    #[must_use]
+    pub fn reachable(&self, roots: &[Key]) -> HashSet<Key> {
+        let mut reached = HashSet::new();
+        let mut pending = roots.to_vec();
+        while let Some(key) = pending.pop() {
+            if reached.insert(key) {
+                pending.extend(self.dependencies(&key));
+            }
+        }
+        reached
+    }
+
+    /// Resources that can't be reached by following references from any of `roots`.
+    #[must_use]
+    pub fn unreachable(&self, roots: &[Key]) -> Vec<Key> {
+        let reached = self.reachable(roots);
+        self.resources
+            .iter()
+            .filter(|key| !reached.contains(key))
+            .copied()
+            .collect()
+    }
+}
+
+struct Builder<'a> {
+    packages: &'a [Dbpf],
+    seen: HashSet<(Key, Key)>,
+    graph: DependencyGraph,
+}
+
+impl Builder<'_> {
+    fn visit(&mut self, dbpf: &Dbpf, key: Key, data: &DbpfKind) {
+        match data {
+            DbpfKind::ObjectData(objd) => self.object_data(key, objd),
+            DbpfKind::ObjectFunction(objf) => {
+                for entry in &objf.functions {
+                    self.call(dbpf, key, entry.action);
+                    self.call(dbpf, key, entry.guard);
+                }
+            }
+            DbpfKind::PieMenuFunctions(ttab) => {
+                for interaction in &ttab.interactions {
+                    self.call(dbpf, key, interaction.action);
+                    self.call(dbpf, key, interaction.guard);
+                }
+                // The menu strings share the table's instance
+                self.reference(
+                    key,
+                    &Key {
+                        kind: DbpfId::PieMenuSettings,
+                        resource_id: None,
+                        ..key
+                    },
+                );
+            }
+            DbpfKind::BehaviorFunction(bhav) => {
+                for instruction in &bhav.instructions {
+                    self.call(dbpf, key, instruction.opcode);
+                    self.constants(dbpf, key, instruction);
+                }
+            }
+            DbpfKind::TextLists(strings) if key.instance_id == MODEL_NAMES_INSTANCE => {
+                for item in &strings.items {
+                    self.name(key, DbpfId::CreationResource, &item.value, "_cres");
+                }
+            }
+            DbpfKind::PropertySet(_) => self.reference(
+                key,
+                &Key {
+                    kind: DbpfId::ThreeDReference,
+                    resource_id: None,
+                    ..key
+                },
+            ),
+            DbpfKind::ThreeDReference(reference) => {
+                for target in &reference.keys {
+                    self.reference(key, target);
+                }
+            }
+            DbpfKind::CreationResource(cres) => self.links(key, &cres.links),
+            DbpfKind::Shape(shpe) => {
+                self.links(key, &shpe.links);
+                if let Some(shape) = shpe.shape() {
+                    for item in &shape.items {
+                        self.name(key, DbpfId::GeometricNode, &item.file_name, "_gmnd");
+                    }
+                    for part in &shape.parts {
+                        self.name(key, DbpfId::TexturedMaterial, &part.material, "_txmt");
+                    }
+                }
+            }
+            DbpfKind::GeometricNode(gmnd) => self.links(key, &gmnd.links),
+            DbpfKind::TexturedMaterial(txmt) => {
+                self.links(key, &txmt.links);
+                if let Some(material) = txmt.material() {
+                    for name in material.texture_names() {
+                        self.name(key, DbpfId::TextureResource, name, "_txtr");
+                    }
+                }
+            }
+            DbpfKind::Light(lght) => self.links(key, &lght.links),
+            _ => {}
+        }
+    }
+
+    /// The function table, interactions, strings and slots of an object live in its group,
+    /// numbered by the ids in the OBJD, along with its model names at [`MODEL_NAMES_INSTANCE`].
+    fn object_data(&mut self, from: Key, objd: &ObjectData) {
+        let in_group = |kind: DbpfId| -> Vec<Key> {
+            self.packages
+                .iter()
+                .flat_map(|dbpf| dbpf.entries.keys())
+                .filter(|key| key.kind == kind && key.group_id == from.group_id)
+                .copied()
+                .collect()
+        };
+        let mut targets = in_group(DbpfId::ObjectFunction);
+        targets.extend(in_group(DbpfId::GlobalData));
+        for target in targets {
+            self.edge(from, target);
+        }
+        for (kind, id) in [
+            (DbpfId::PieMenuFunctions, objd.interaction_table_id),
+            (DbpfId::TextLists, objd.body_strings_id),
+            (DbpfId::CatalogDescription, objd.catalog_strings_id),
+            (DbpfId::ObjectSlot, objd.slot_id),
+        ] {
+            if id != 0 {
+                self.reference(
+                    from,
+                    &Key {
+                        kind,
+                        group_id: from.group_id,
+                        instance_id: InstanceId(u32::from(id)),
+                        resource_id: None,
+                    },
+                );
+            }
+        }
+        self.reference(
+            from,
+            &Key {
+                kind: DbpfId::TextLists,
+                group_id: from.group_id,
+                instance_id: MODEL_NAMES_INSTANCE,
+                resource_id: None,
+            },
+        );
+    }
+
+    fn call(&mut self, dbpf: &Dbpf, from: Key, opcode: u16) {
+        let target = CallResolver::new(dbpf, self.packages)
+            .resolve(from.group_id, opcode)
+            .map(|(key, _)| *key);
+        if let Some(target) = target {
+            self.edge(from, target);
+        }
+    }
+
+    /// The BCONs an instruction reads entries of. Like calls, the BCON's instance picks the
+    /// group it lives in: the BHAV's own, the semiglobal one or the global one.
+    fn constants(&mut self, dbpf: &Dbpf, from: Key, instruction: &Instruction) {
+        for (_, owner, data) in CONSTANT_OPERANDS
+            .iter()
+            .filter(|(opcode, _, _)| *opcode == instruction.opcode)
+        {
+            if instruction.operands.get(*owner) != Some(&TUNING_OWNER) {
+                continue;
+            }
+            let data = match instruction.operands.get(*data..*data + 2) {
+                Some(data) => u16::from_le_bytes([data[0], data[1]]),
+                None => continue,
+            };
+            let instance = match data >> 7 {
+                table @ 0..=63 => 0x1000 + table,
+                table @ 64..=127 => 0x2000 + table - 64,
+                table @ 128..=191 => 0x0100 + table - 128,
+                _ => continue,
+            };
+            let group = match CallResolver::new(dbpf, self.packages)
+                .target_group(from.group_id, instance)
+            {
+                Some(group) => group,
+                None => continue,
+            };
+            self.reference(
+                from,
+                &Key {
+                    kind: DbpfId::BehaviorConstant,
+                    group_id: group,
+                    instance_id: InstanceId(u32::from(instance)),
+                    resource_id: None,
+                },
+            );
+        }
+    }
+
+    fn links(&mut self, from: Key, links: &[ResourceLink]) {
+        for link in links {
+            self.reference(from, &link.key());
+        }
+    }
+
+    /// Follows a reference by file name, adding `suffix` if the name leaves it out.
+    fn name(&mut self, from: Key, kind: DbpfId, name: &str, suffix: &str) {
+        if name.is_empty() {
+            return;
+        }
+        self.reference(from, &name_key(kind, &with_suffix(name, suffix)));
+    }
+
+    fn reference(&mut self, from: Key, reference: &Key) {
+        if let Some((target, _)) = find(self.packages, Some(&from), reference) {
+            self.edge(from, target);
+        }
+    }
+
+    fn edge(&mut self, from: Key, to: Key) {
+        if from != to && self.seen.insert((from, to)) {
+            self.graph.edges.push((from, to));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::dbpf_from;
+    use crate::types::object_function::{FunctionEntry, ObjectFunction};
+    use crate::types::package::database_packed_file::GroupId;
+    use crate::types::pie_menu_functions::{Interaction, PieMenuFunctions, Version};
+    use crate::types::pie_menu_settings::PieMenuSettings;
+    use crate::types::scenegraph::creation_resource::CreationResource;
+    use crate::types::scenegraph::textured_material::{
+        MaterialDefinition, MaterialProperty, TexturedMaterial,
+    };
+    use crate::types::simantic::behavior_constant::BehaviorConstants;
+    use crate::types::simantic::behavior_function::{
+        BehaviorFunction, GoTo, Instruction, Signature,
+    };
+    use crate::types::simantic::global_data::GLOBAL_GROUP;
+    use crate::types::text_lists::{Format, LanguageId, TextItem, TextList};
+    use crate::types::texture_resource::TextureResource;
+    use crate::types::util::hash::{instance_hash, name_key};
+    use crate::types::util::resource_collection::{BlockHeader, ResourceCollection, SgResource};
+
+    const GROUP: GroupId = GroupId(0x7F00_0001);
+
+    fn key(kind: DbpfId, group_id: GroupId, instance_id: u32) -> Key {
+        Key {
+            kind,
+            group_id,
+            instance_id: InstanceId(instance_id),
+            resource_id: None,
+        }
+    }
+
+    fn bhav(calls: &[u16]) -> DbpfKind {
+        DbpfKind::BehaviorFunction(BehaviorFunction {
+            file_name: String::new(),
+            signature: Signature::Seven,
+            tree_type: 0,
+            num_parameters: 0,
+            num_locals: 0,
+            flag: 0,
+            tree_version: 0,
+            instructions: calls
+                .iter()
+                .map(|opcode| Instruction {
+                    opcode: *opcode,
+                    goto_true: GoTo::True,
+                    goto_false: GoTo::False,
+                    node_version: Some(false),
+                    operands: vec![0; 16],
+                    cache_flags: None,
+                })
+                .collect(),
+        })
+    }
+
+    fn bcon() -> DbpfKind {
+        DbpfKind::BehaviorConstant(BehaviorConstants {
+            file_name: String::new(),
+            flags: 0,
+            constants: vec![0; 4],
+        })
+    }
+
+    fn strings(values: &[&str]) -> TextList {
+        TextList {
+            file_name: String::new(),
+            format: Format::Localized,
+            items: values
+                .iter()
+                .map(|value| TextItem {
+                    language: LanguageId::ENGLISH_US,
+                    value: (*value).to_string(),
+                    description: String::new(),
+                })
+                .collect(),
+            unknown: None,
+        }
+    }
+
+    fn set(keys: &[Key]) -> HashSet<Key> {
+        keys.iter().copied().collect()
+    }
+
+    #[test]
+    fn object_graph() {
+        let objd = key(DbpfId::ObjectData, GROUP, 0x41A7);
+        let functions = key(DbpfId::ObjectFunction, GROUP, 0x41A7);
+        let init = key(DbpfId::BehaviorFunction, GROUP, 0x1000);
+        let helper = key(DbpfId::BehaviorFunction, GROUP, 0x1001);
+        let orphan = key(DbpfId::BehaviorFunction, GROUP, 0x1002);
+        let global = key(DbpfId::BehaviorFunction, GLOBAL_GROUP, 0x0100);
+
+        let object = dbpf_from(vec![
+            (objd, DbpfKind::ObjectData(ObjectData::default())),
+            (
+                functions,
+                DbpfKind::ObjectFunction(ObjectFunction {
+                    file_name: String::new(),
+                    unknown_1: 0,
+                    unknown_2: 0,
+                    functions: vec![FunctionEntry {
+                        guard: 0,
+                        action: 0x1000,
+                    }],
+                }),
+            ),
+            (init, bhav(&[0x1001, 0x0100, 0x0002])),
+            (helper, bhav(&[])),
+            (orphan, bhav(&[0x1001])),
+        ]);
+        let globals = dbpf_from(vec![(global, bhav(&[]))]);
+
+        let graph = DependencyGraph::build(&[object, globals]);
+
+        assert_eq!(graph.dependencies(&objd), vec![functions]);
+        assert_eq!(graph.dependencies(&functions), vec![init]);
+        assert_eq!(graph.dependencies(&init), vec![helper, global]);
+        assert_eq!(set(&graph.dependents(&helper)), set(&[init, orphan]));
+        assert_eq!(set(&graph.unreferenced()), set(&[objd, orphan]));
+        assert_eq!(graph.unreachable(&[objd]), vec![orphan]);
+    }
+
+    #[test]
+    fn material_textures() {
+        let txmt = key(
+            DbpfId::TexturedMaterial,
+            GroupId(0x1C05_0000),
+            instance_hash("chair_seat_txmt").0,
+        );
+        let txtr = key(
+            DbpfId::TextureResource,
+            GroupId(0x1C05_0000),
+            instance_hash("chair_seat_base_txtr").0,
+        );
+        let material = TexturedMaterial {
+            collection: ResourceCollection::new(vec![MaterialDefinition {
+                block: BlockHeader::of::<MaterialDefinition>(11),
+                resource: SgResource::new("chair_seat_txmt".to_string()),
+                description: "chair_seat".to_string(),
+                shader: "StandardMaterial".to_string(),
+                properties: vec![MaterialProperty {
+                    name: "stdMatBaseTextureName".to_string(),
+                    value: "##0x1c050000!chair_seat_base".to_string(),
+                }],
+                textures: vec![],
+            }
+            .into()]),
+        };
+        let package = dbpf_from(vec![
+            (txmt, DbpfKind::TexturedMaterial(material)),
+            (
+                txtr,
+                DbpfKind::TextureResource(TextureResource {
+                    collection: ResourceCollection::default(),
+                }),
+            ),
+        ]);
+
+        let graph = DependencyGraph::build(&[package]);
+
+        assert_eq!(graph.dependents(&txtr), vec![txmt]);
+        assert_eq!(graph.unreferenced(), vec![txmt]);
+    }
+
+    #[test]
+    fn pie_menu_strings() {
+        let objd = key(DbpfId::ObjectData, GROUP, 0x41A7);
+        let menu = key(DbpfId::PieMenuFunctions, GROUP, 0x80);
+        let menu_strings = key(DbpfId::PieMenuSettings, GROUP, 0x80);
+        let other_menu_strings = key(DbpfId::PieMenuSettings, GROUP, 0x81);
+
+        let mut table = PieMenuFunctions::new(String::new(), Version::SeventyFour);
+        table
+            .interactions
+            .push(Interaction::new(Version::SeventyFour));
+        let settings = || {
+            DbpfKind::PieMenuSettings(PieMenuSettings {
+                strings: strings(&["Sit"]),
+            })
+        };
+        let package = dbpf_from(vec![
+            (
+                objd,
+                DbpfKind::ObjectData(ObjectData {
+                    interaction_table_id: 0x80,
+                    ..ObjectData::default()
+                }),
+            ),
+            (menu, DbpfKind::PieMenuFunctions(table)),
+            (menu_strings, settings()),
+            (other_menu_strings, settings()),
+        ]);
+
+        let graph = DependencyGraph::build(&[package]);
+
+        assert_eq!(graph.dependents(&menu_strings), vec![menu]);
+        assert_eq!(set(&graph.unreferenced()), set(&[objd, other_menu_strings]));
+    }
+
+    #[test]
+    fn behavior_constants() {
+        let reader = key(DbpfId::BehaviorFunction, GROUP, 0x1000);
+        let local = key(DbpfId::BehaviorConstant, GROUP, 0x1000);
+        let unread = key(DbpfId::BehaviorConstant, GROUP, 0x1001);
+        let global = key(DbpfId::BehaviorConstant, GLOBAL_GROUP, 0x0100);
+
+        let mut function = bhav(&[0x0002, 0x0002]);
+        if let DbpfKind::BehaviorFunction(bhav) = &mut function {
+            // Entry 3 of the local BCON 0x1000 against entry 1 of the global BCON 0x0100
+            bhav.instructions[0].operands[..8]
+                .copy_from_slice(&[0x03, 0x00, 0x01, 0x40, 0x00, 0x00, 0x1A, 0x1A]);
+            // Literals, which would name BCON 0x1001 if they were read as tuning data
+            bhav.instructions[1].operands[..8]
+                .copy_from_slice(&[0x80, 0x00, 0x80, 0x00, 0x00, 0x00, 0x07, 0x07]);
+        }
+        let object = dbpf_from(vec![(reader, function), (local, bcon()), (unread, bcon())]);
+        let globals = dbpf_from(vec![(global, bcon())]);
+
+        let graph = DependencyGraph::build(&[object, globals]);
+
+        assert_eq!(set(&graph.dependencies(&reader)), set(&[local, global]));
+        assert_eq!(set(&graph.unreferenced()), set(&[reader, unread]));
+    }
+
+    #[test]
+    fn model_names() {
+        let objd = key(DbpfId::ObjectData, GROUP, 0x41A7);
+        let models = key(DbpfId::TextLists, GROUP, 0x85);
+        let cres = name_key(DbpfId::CreationResource, "##0x1c050000!chair_cres");
+        let other_cres = name_key(DbpfId::CreationResource, "##0x1c050000!table_cres");
+
+        let empty_cres = || {
+            DbpfKind::CreationResource(CreationResource {
+                collection: ResourceCollection::default(),
+            })
+        };
+        let package = dbpf_from(vec![
+            (objd, DbpfKind::ObjectData(ObjectData::default())),
+            (
+                models,
+                DbpfKind::TextLists(strings(&["##0x1c050000!chair", ""])),
+            ),
+            (cres, empty_cres()),
+            (other_cres, empty_cres()),
+        ]);
+
+        let graph = DependencyGraph::build(&[package]);
+
+        assert_eq!(graph.dependencies(&models), vec![cres]);
+        assert_eq!(set(&graph.unreferenced()), set(&[objd, other_cres]));
+    }
+}
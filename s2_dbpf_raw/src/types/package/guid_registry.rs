@@ -0,0 +1,281 @@
+////////////////////////////////////////////////////////////////////////////////
+// This Source Code Form is subject to the terms of the Mozilla Public         /
+// License, v. 2.0. If a copy of the MPL was not distributed with this         /
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
+////////////////////////////////////////////////////////////////////////////////
+
+//! Keeps track of the GUIDs objects are known by, hands out new ones that collide with none of
+//! them, and moves a cloned object over to its new GUIDs.
+
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasher, Hasher};
+
+use crate::constants::data_kinds::DbpfKind;
+use crate::types::object_data::ObjectData;
+use crate::types::package::database_packed_file::{Dbpf, Key};
+use crate::types::simantic::behavior_function::BehaviorFunction;
+
+/// The primitives with a GUID operand, and the offset of the GUID in their operands.
+const GUID_OPERANDS: [(u16, usize); 3] = [
+    // Set to Next, when looking for objects of a type
+    (0x001F, 0),
+    // Test Object Type
+    (0x0020, 0),
+    // Create New Object Instance
+    (0x002A, 0),
+];
+
+/// The GUIDs in use across a set of packages.
+#[derive(Debug, Clone, Default)]
+pub struct GuidRegistry {
+    /// The OBJD each object GUID belongs to
+    pub objects: HashMap<u32, Key>,
+    /// Every GUID seen in an OBJD field or handed out by [`GuidRegistry::generate`]
+    pub reserved: HashSet<u32>,
+    random: RandomState,
+    draws: u64,
+}
+
+impl GuidRegistry {
+    /// Indexes the GUIDs of every OBJD in `packages`. When two objects share a GUID the first
+    /// one found keeps it.
+    #[must_use]
+    pub fn scan(packages: &[Dbpf]) -> Self {
+        let mut registry = Self::default();
+        for dbpf in packages {
+            for (key, entry) in &dbpf.entries {
+                if let DbpfKind::ObjectData(objd) = &entry.data {
+                    registry.insert(*key, objd);
+                }
+            }
+        }
+        registry
+    }
+
+    /// Adds the GUIDs of the OBJD at `key`.
+    pub fn insert(&mut self, key: Key, objd: &ObjectData) {
+        if objd.guid != 0 {
+            self.objects.entry(objd.guid).or_insert(key);
+        }
+        self.reserved
+            .extend(objd.guids().iter().copied().filter(|guid| *guid != 0));
+    }
+
+    /// The OBJD of the object known by `guid`.
+    #[must_use]
+    pub fn object(&self, guid: u32) -> Option<&Key> {
+        self.objects.get(&guid)
+    }
+
+    #[must_use]
+    pub fn contains(&self, guid: u32) -> bool {
+        self.reserved.contains(&guid)
+    }
+
+    /// A random GUID no indexed object uses, reserved so it won't be handed out again. Zero and
+    /// `0xFFFFFFFF` are never returned, the game reads both as no object.
+    pub fn generate(&mut self) -> u32 {
+        loop {
+            let mut hasher = self.random.build_hasher();
+            hasher.write_u64(self.draws);
+            self.draws += 1;
+            let guid = hasher.finish() as u32;
+            if guid != 0 && guid != u32::MAX && self.reserved.insert(guid) {
+                return guid;
+            }
+        }
+    }
+
+    /// Gives every GUID in `guids` a newly generated one, for moving a clone off the GUIDs of
+    /// the object it was copied from.
+    pub fn remap(&mut self, guids: impl IntoIterator<Item = u32>) -> GuidMap {
+        let mut map = GuidMap::default();
+        for guid in guids {
+            if guid != 0 && !map.0.contains_key(&guid) {
+                let new = self.generate();
+                map.0.insert(guid, new);
+            }
+        }
+        map
+    }
+}
+
+/// Old GUIDs to the new ones replacing them.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GuidMap(pub HashMap<u32, u32>);
+
+impl GuidMap {
+    #[must_use]
+    pub fn get(&self, guid: u32) -> Option<u32> {
+        self.0.get(&guid).copied()
+    }
+
+    /// Replaces the mapped GUIDs in the GUID fields of `objd`, returning how many changed.
+    pub fn rewrite_object_data(&self, objd: &mut ObjectData) -> usize {
+        let mut changed = 0;
+        for field in objd.guids_mut() {
+            if let Some(new) = self.get(*field) {
+                *field = new;
+                changed += 1;
+            }
+        }
+        changed
+    }
+
+    /// Replaces mapped GUIDs in the GUID operands of the primitives that take one, returning
+    /// how many changed. Other operands are left alone, even if they happen to match a GUID.
+    pub fn rewrite_behavior(&self, bhav: &mut BehaviorFunction) -> usize {
+        let mut changed = 0;
+        for instruction in &mut bhav.instructions {
+            let offset = match GUID_OPERANDS
+                .iter()
+                .find(|(opcode, _)| *opcode == instruction.opcode)
+            {
+                Some((_, offset)) => *offset,
+                None => continue,
+            };
+            let field = match instruction.operands.get_mut(offset..offset + 4) {
+                Some(field) => field,
+                None => continue,
+            };
+            let mut bytes = [0; 4];
+            bytes.copy_from_slice(field);
+            if let Some(new) = self.get(u32::from_le_bytes(bytes)) {
+                field.copy_from_slice(&new.to_le_bytes());
+                changed += 1;
+            }
+        }
+        changed
+    }
+
+    /// Rewrites every OBJD and BHAV in `dbpf`, returning how many GUIDs changed.
+    pub fn rewrite(&self, dbpf: &mut Dbpf) -> usize {
+        dbpf.entries
+            .values_mut()
+            .map(|entry| match &mut entry.data {
+                DbpfKind::ObjectData(objd) => self.rewrite_object_data(objd),
+                DbpfKind::BehaviorFunction(bhav) => self.rewrite_behavior(bhav),
+                _ => 0,
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::data_kinds::DbpfId;
+    use crate::test_helpers::dbpf_from;
+    use crate::types::package::database_packed_file::{GroupId, InstanceId};
+    use crate::types::simantic::behavior_function::{GoTo, Instruction, Signature};
+
+    fn objd_key(instance_id: u32) -> Key {
+        Key {
+            kind: DbpfId::ObjectData,
+            group_id: GroupId(0x7F00_0001),
+            instance_id: InstanceId(instance_id),
+            resource_id: None,
+        }
+    }
+
+    fn objd(guid: u32, diagonal_selector_guid: u32) -> ObjectData {
+        ObjectData {
+            guid,
+            original_guid: guid,
+            diagonal_selector_guid,
+            ..ObjectData::default()
+        }
+    }
+
+    #[test]
+    fn scan_and_generate() {
+        let chair = objd_key(0x41A7);
+        let mut registry = GuidRegistry::scan(&[dbpf_from(vec![(
+            chair,
+            DbpfKind::ObjectData(objd(0x1234_5678, 0x1234_5679)),
+        )])]);
+
+        assert_eq!(registry.object(0x1234_5678), Some(&chair));
+        assert_eq!(registry.object(0x1234_5679), None);
+        assert!(registry.contains(0x1234_5679));
+
+        let generated: HashSet<u32> = (0..1000).map(|_| registry.generate()).collect();
+        assert_eq!(generated.len(), 1000);
+        assert!(!generated.contains(&0x1234_5678));
+        assert!(!generated.contains(&0x1234_5679));
+        assert!(!generated.contains(&0));
+    }
+
+    #[test]
+    fn rewrite_clone() {
+        let mut registry = GuidRegistry::default();
+        let map = registry.remap([0x1234_5678, 0x1234_5679]);
+        let guid = map.get(0x1234_5678).unwrap();
+        let selector = map.get(0x1234_5679).unwrap();
+
+        let instruction = |opcode: u16, offset: usize| {
+            let mut operands = vec![0; 16];
+            operands[offset..offset + 4].copy_from_slice(&0x1234_5679_u32.to_le_bytes());
+            Instruction {
+                opcode,
+                goto_true: GoTo::True,
+                goto_false: GoTo::False,
+                node_version: Some(false),
+                operands,
+                cache_flags: None,
+            }
+        };
+        let bhav = BehaviorFunction {
+            file_name: "Create Selector".to_string(),
+            signature: Signature::Seven,
+            tree_type: 0,
+            num_parameters: 0,
+            num_locals: 0,
+            flag: 0,
+            tree_version: 0,
+            instructions: vec![
+                // Create New Object Instance
+                instruction(0x002A, 0),
+                // The same value in operands that aren't GUIDs
+                instruction(0x002A, 4),
+                instruction(0x0002, 0),
+            ],
+        };
+        let bhav_key = Key {
+            kind: DbpfId::BehaviorFunction,
+            ..objd_key(0x1000)
+        };
+        let mut package = dbpf_from(vec![
+            (
+                objd_key(0x41A7),
+                DbpfKind::ObjectData(objd(0x1234_5678, 0x1234_5679)),
+            ),
+            (bhav_key, DbpfKind::BehaviorFunction(bhav)),
+        ]);
+
+        assert_eq!(map.rewrite(&mut package), 4);
+        match &package.entries[&objd_key(0x41A7)].data {
+            DbpfKind::ObjectData(objd) => {
+                assert_eq!(objd.guid, guid);
+                assert_eq!(objd.original_guid, guid);
+                assert_eq!(objd.diagonal_selector_guid, selector);
+            }
+            _ => unreachable!(),
+        }
+        match &package.entries[&bhav_key].data {
+            DbpfKind::BehaviorFunction(bhav) => {
+                assert_eq!(bhav.instructions[0].operands[0..4], selector.to_le_bytes());
+                assert_eq!(
+                    bhav.instructions[1].operands[4..8],
+                    0x1234_5679_u32.to_le_bytes()
+                );
+                assert_eq!(
+                    bhav.instructions[2].operands[0..4],
+                    0x1234_5679_u32.to_le_bytes()
+                );
+            }
+            _ => unreachable!(),
+        }
+    }
+}
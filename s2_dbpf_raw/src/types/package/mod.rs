@@ -5,6 +5,10 @@
 ////////////////////////////////////////////////////////////////////////////////
 
 pub mod database_packed_file;
+pub mod dependency_graph;
 pub mod directory;
+pub mod guid_registry;
 pub mod header;
 pub mod index_table;
+pub mod object_clone;
+pub mod recolor;
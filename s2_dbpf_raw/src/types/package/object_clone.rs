@@ -0,0 +1,488 @@
+////////////////////////////////////////////////////////////////////////////////
+// This Source Code Form is subject to the terms of the Mozilla Public         /
+// License, v. 2.0. If a copy of the MPL was not distributed with this         /
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
+////////////////////////////////////////////////////////////////////////////////
+
+//! Copies an object out of a package into a package of its own, under a new group and new
+//! GUIDs, as the starting point for a custom object.
+
+use std::collections::HashMap;
+use std::slice;
+
+use crate::constants::data_kinds::{DbpfId, DbpfKind};
+use crate::types::neighborhood::three_d_reference::find;
+use crate::types::package::database_packed_file::{Dbpf, GroupId, Key};
+use crate::types::package::dependency_graph::{DependencyGraph, MODEL_NAMES_INSTANCE};
+use crate::types::package::guid_registry::{GuidMap, GuidRegistry};
+use crate::types::scenegraph::creation_resource::ResourceNodeKind;
+use crate::types::util::hash::{name_key, split_group, with_suffix};
+use crate::types::util::resource_collection::{ObjectGraphNode, ResourceLink};
+
+/// An object copied into a new package.
+#[derive(Debug, Clone)]
+pub struct ObjectClone {
+    pub dbpf: Dbpf,
+    /// The key every copied resource had in the source, mapped to its key in the clone
+    pub keys: HashMap<Key, Key>,
+    /// The GUIDs of the source object, mapped to the clone's
+    pub guids: GuidMap,
+}
+
+impl ObjectClone {
+    /// Everything the object whose OBJD is at `objd` is made of: every resource in its group,
+    /// which holds its OBJDs, OBJF, TTAB, TTAs, STR#s, CTSS, BHAVs, BCONs, SLOT and GLOB, and
+    /// the scenegraph of the models its model names STR# lists.
+    #[must_use]
+    pub fn related(source: &Dbpf, objd: &Key) -> Vec<Key> {
+        let mut related: Vec<Key> = source
+            .entries
+            .keys()
+            .filter(|key| key.group_id == objd.group_id)
+            .copied()
+            .collect();
+
+        let packages = slice::from_ref(source);
+        let models: Vec<Key> =
+            match source.find(DbpfId::TextLists, objd.group_id, MODEL_NAMES_INSTANCE) {
+                Some((strings, DbpfKind::TextLists(names))) => names
+                    .items
+                    .iter()
+                    .filter(|item| !item.value.is_empty())
+                    .filter_map(|item| {
+                        let name = with_suffix(&item.value, "_cres");
+                        find(
+                            packages,
+                            Some(strings),
+                            &name_key(DbpfId::CreationResource, &name),
+                        )
+                        .map(|(key, _)| key)
+                    })
+                    .collect(),
+                _ => vec![],
+            };
+        if !models.is_empty() {
+            let graph = DependencyGraph::build(packages);
+            for key in graph.reachable(&models) {
+                if !related.contains(&key) {
+                    related.push(key);
+                }
+            }
+        }
+        related
+    }
+
+    /// Copies the object whose OBJD is at `objd` out of `source`, `None` if there is no OBJD
+    /// there.
+    ///
+    /// Every copied resource moves to `group`, usually the [`group_hash`] of the new package's
+    /// name. Calls and ids inside the object are relative to its group, so they keep working
+    /// as they are. The GUIDs of the object's OBJDs are replaced with ones `registry` generates,
+    /// wherever they appear in the OBJDs and BHAVs, and the new OBJDs are added to `registry`.
+    /// The copied scenegraph gets the `##0x…!` prefix of `group` in its names, and the links,
+    /// names and model names STR# entries pointing at it are updated to match, so the clone
+    /// has models of its own. References to resources that weren't copied are left as they are.
+    ///
+    /// [`group_hash`]: crate::types::util::hash::group_hash
+    #[must_use]
+    pub fn create(
+        source: &Dbpf,
+        objd: &Key,
+        group: GroupId,
+        registry: &mut GuidRegistry,
+    ) -> Option<Self> {
+        match source.entries.get(objd) {
+            Some(entry) if matches!(entry.data, DbpfKind::ObjectData(_)) => {}
+            _ => return None,
+        }
+
+        let mut keys = HashMap::new();
+        let mut dbpf = Dbpf {
+            header: source.header,
+            entries: HashMap::new(),
+        };
+        for key in Self::related(source, objd) {
+            let new = Key {
+                group_id: group,
+                ..key
+            };
+            keys.insert(key, new);
+            dbpf.entries.insert(new, source.entries[&key].clone());
+        }
+
+        let regroup = Regroup {
+            source: slice::from_ref(source),
+            keys: &keys,
+            group,
+        };
+        for (key, new) in &keys {
+            if let Some(entry) = dbpf.entries.get_mut(new) {
+                regroup.resource(key, &mut entry.data);
+            }
+        }
+
+        let guids = registry.remap(dbpf.entries.values().filter_map(|entry| match &entry.data {
+            DbpfKind::ObjectData(objd) => Some(objd.guid),
+            _ => None,
+        }));
+        guids.rewrite(&mut dbpf);
+        for (key, entry) in &dbpf.entries {
+            if let DbpfKind::ObjectData(objd) = &entry.data {
+                registry.insert(*key, objd);
+            }
+        }
+
+        Some(Self { dbpf, keys, guids })
+    }
+}
+
+/// Moves the names and links of copied scenegraph resources into the clone's group.
+struct Regroup<'a> {
+    source: &'a [Dbpf],
+    keys: &'a HashMap<Key, Key>,
+    group: GroupId,
+}
+
+impl Regroup<'_> {
+    /// Updates the resource that was at `key` in the source.
+    fn resource(&self, key: &Key, data: &mut DbpfKind) {
+        match data {
+            DbpfKind::TextLists(strings) if key.instance_id == MODEL_NAMES_INSTANCE => {
+                for item in &mut strings.items {
+                    self.name(&mut item.value, DbpfId::CreationResource, "_cres");
+                }
+            }
+            DbpfKind::CreationResource(cres) => {
+                self.links(key, &mut cres.links);
+                if let Some(node) = cres.resource_node_mut() {
+                    match &mut node.node {
+                        ResourceNodeKind::Tree { resource, tree } => {
+                            self.own(&mut resource.file_name);
+                            self.graph(&mut tree.graph);
+                        }
+                        ResourceNodeKind::Graph { graph } => self.graph(graph),
+                    }
+                }
+            }
+            DbpfKind::Shape(shpe) => {
+                self.links(key, &mut shpe.links);
+                if let Some(shape) = shpe.shape_mut() {
+                    self.own(&mut shape.resource.file_name);
+                    self.graph(&mut shape.graph);
+                    for item in &mut shape.items {
+                        self.name(&mut item.file_name, DbpfId::GeometricNode, "_gmnd");
+                    }
+                    for part in &mut shape.parts {
+                        self.name(&mut part.material, DbpfId::TexturedMaterial, "_txmt");
+                    }
+                }
+            }
+            DbpfKind::GeometricNode(gmnd) => {
+                self.links(key, &mut gmnd.links);
+                if let Some(node) = gmnd.geometry_node_mut() {
+                    self.own(&mut node.resource.file_name);
+                    self.graph(&mut node.graph);
+                }
+            }
+            DbpfKind::GeometricDataContainer(gmdc) => {
+                self.links(key, &mut gmdc.links);
+                if let Some(geometry) = gmdc.geometry_mut() {
+                    self.own(&mut geometry.resource.file_name);
+                }
+            }
+            DbpfKind::TexturedMaterial(txmt) => {
+                self.links(key, &mut txmt.links);
+                if let Some(material) = txmt.material_mut() {
+                    self.own(&mut material.resource.file_name);
+                    let textures: Vec<String> = material
+                        .texture_names()
+                        .into_iter()
+                        .map(ToString::to_string)
+                        .collect();
+                    for texture in textures {
+                        let mut new_texture = texture.clone();
+                        self.name(&mut new_texture, DbpfId::TextureResource, "_txtr");
+                        material.retarget_texture(&texture, &new_texture);
+                    }
+                }
+            }
+            DbpfKind::TextureResource(txtr) => {
+                self.links(key, &mut txtr.links);
+                if let Some(image) = txtr.image_data_mut() {
+                    self.own(&mut image.resource.file_name);
+                    if let Some(name) = &mut image.file_name_repeat {
+                        self.own(name);
+                    }
+                }
+            }
+            DbpfKind::Light(lght) => {
+                self.links(key, &mut lght.links);
+                for light in lght.lights_mut() {
+                    self.own(&mut light.resource.file_name);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Puts a copied resource's own name under the clone's group prefix, if it has a prefix.
+    fn own(&self, name: &mut String) {
+        if let (Some(_), rest) = split_group(name) {
+            *name = format!("##0x{:08x}!{}", self.group.0, rest);
+        }
+    }
+
+    fn graph(&self, graph: &mut ObjectGraphNode) {
+        if let Some(name) = &mut graph.file_name {
+            self.own(name);
+        }
+    }
+
+    /// Follows a reference by name into the clone's group, if the resource it names was copied.
+    fn name(&self, name: &mut String, kind: DbpfId, suffix: &str) {
+        let reference = name_key(kind, &with_suffix(name, suffix));
+        if let Some((target, _)) = find(self.source, None, &reference) {
+            if self.keys.contains_key(&target) {
+                self.own(name);
+            }
+        }
+    }
+
+    fn links(&self, from: &Key, links: &mut [ResourceLink]) {
+        for link in links {
+            if let Some((target, _)) = find(self.source, Some(from), &link.key()) {
+                if self.keys.contains_key(&target) {
+                    link.group_id = self.group;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::dbpf_from;
+    use crate::types::object_data::ObjectData;
+    use crate::types::package::database_packed_file::InstanceId;
+    use crate::types::scenegraph::creation_resource::{CreationResource, ResourceNode};
+    use crate::types::scenegraph::shape::{Shape, ShapeData, ShapePart};
+    use crate::types::scenegraph::textured_material::{
+        MaterialDefinition, MaterialProperty, TexturedMaterial,
+    };
+    use crate::types::text_lists::{Format, LanguageId, TextItem, TextList};
+    use crate::types::texture_resource::TextureResource;
+    use crate::types::util::hash::group_hash;
+    use crate::types::util::resource_collection::{BlockHeader, ResourceCollection, SgResource};
+
+    fn key(kind: DbpfId, group_id: u32, instance_id: u32) -> Key {
+        Key {
+            kind,
+            group_id: GroupId(group_id),
+            instance_id: InstanceId(instance_id),
+            resource_id: None,
+        }
+    }
+
+    fn link(key: &Key) -> ResourceLink {
+        ResourceLink {
+            group_id: key.group_id,
+            instance_id: key.instance_id,
+            resource_id: key.resource_id,
+            kind: key.kind,
+        }
+    }
+
+    fn cres(name: &str, links: Vec<ResourceLink>) -> DbpfKind {
+        DbpfKind::CreationResource(CreationResource {
+            collection: ResourceCollection {
+                has_resource_ids: true,
+                links,
+                ..ResourceCollection::new(vec![ResourceNode {
+                    block: BlockHeader::of::<ResourceNode>(7),
+                    node: ResourceNodeKind::Graph {
+                        graph: ObjectGraphNode::new(name.to_string()),
+                    },
+                    children: vec![],
+                    unknown1: 0,
+                    unknown2: 0,
+                }
+                .into()])
+            },
+        })
+    }
+
+    #[test]
+    fn clone_chair() {
+        let objd = key(DbpfId::ObjectData, 0x7F00_0001, 0x41A7);
+        let models = key(DbpfId::TextLists, 0x7F00_0001, 0x85);
+        let cres_key = name_key(DbpfId::CreationResource, "##0x1c050000!chair_cres");
+        let shpe_key = name_key(DbpfId::Shape, "##0x1c050000!chair_shpe");
+        let txmt_key = name_key(DbpfId::TexturedMaterial, "##0x1c050000!chair_seat_txmt");
+        let txtr_key = name_key(DbpfId::TextureResource, "##0x1c050000!chair_seat_base_txtr");
+        let other_cres = name_key(DbpfId::CreationResource, "##0x1c050000!table_cres");
+        let other_objd = key(DbpfId::ObjectData, 0x7F00_0002, 0x41A7);
+
+        let chair = ObjectData {
+            guid: 0x1234_5678,
+            original_guid: 0x1234_5678,
+            proxy_guid: 0x0BAD_F00D,
+            ..ObjectData::default()
+        };
+        let shpe = Shape {
+            collection: ResourceCollection::new(vec![ShapeData {
+                block: BlockHeader::of::<ShapeData>(8),
+                resource: SgResource::new("##0x1c050000!chair_shpe".to_string()),
+                referent: BlockHeader::new(ShapeData::REFERENT_NODE, 0, 1),
+                graph: ObjectGraphNode::new("##0x1c050000!chair_shpe".to_string()),
+                lod_levels: vec![0],
+                items: vec![],
+                parts: vec![ShapePart {
+                    subset: "seat".to_string(),
+                    material: "##0x1c050000!chair_seat".to_string(),
+                    unknown: [0; 9],
+                }],
+            }
+            .into()]),
+        };
+        let txmt = TexturedMaterial {
+            collection: ResourceCollection::new(vec![MaterialDefinition {
+                block: BlockHeader::of::<MaterialDefinition>(11),
+                resource: SgResource::new("##0x1c050000!chair_seat_txmt".to_string()),
+                description: "chair_seat".to_string(),
+                shader: "StandardMaterial".to_string(),
+                properties: vec![
+                    MaterialProperty {
+                        name: "stdMatBaseTextureName".to_string(),
+                        value: "##0x1c050000!chair_seat_base".to_string(),
+                    },
+                    // Not in the package, so the clone keeps pointing at the original
+                    MaterialProperty {
+                        name: "stdMatNormalMapTextureName".to_string(),
+                        value: "##0x1c050000!shared_bump".to_string(),
+                    },
+                ],
+                textures: vec![],
+            }
+            .into()]),
+        };
+        let source = dbpf_from(vec![
+            (objd, DbpfKind::ObjectData(chair)),
+            (
+                models,
+                DbpfKind::TextLists(TextList {
+                    file_name: "Model - Names".to_string(),
+                    format: Format::Localized,
+                    items: vec![TextItem {
+                        language: LanguageId::ENGLISH_US,
+                        value: "##0x1c050000!chair".to_string(),
+                        description: String::new(),
+                    }],
+                    unknown: None,
+                }),
+            ),
+            (
+                cres_key,
+                cres("##0x1c050000!chair_cres", vec![link(&shpe_key)]),
+            ),
+            (shpe_key, DbpfKind::Shape(shpe)),
+            (txmt_key, DbpfKind::TexturedMaterial(txmt)),
+            (
+                txtr_key,
+                DbpfKind::TextureResource(TextureResource {
+                    collection: ResourceCollection::default(),
+                }),
+            ),
+            (other_cres, cres("##0x1c050000!table_cres", vec![])),
+            (
+                other_objd,
+                DbpfKind::ObjectData(ObjectData {
+                    guid: 0x1234_5679,
+                    ..ObjectData::default()
+                }),
+            ),
+        ]);
+        let mut registry = GuidRegistry::scan(slice::from_ref(&source));
+
+        let group = group_hash("MyChair");
+        let clone = ObjectClone::create(&source, &objd, group, &mut registry).unwrap();
+
+        let new_objd = Key {
+            group_id: group,
+            ..objd
+        };
+        let new_models = Key {
+            group_id: group,
+            ..models
+        };
+        let prefix = format!("##0x{:08x}!", group.0);
+        let new_cres = name_key(DbpfId::CreationResource, &format!("{}chair_cres", prefix));
+        assert_eq!(clone.dbpf.entries.len(), 6);
+        assert_eq!(clone.keys[&objd], new_objd);
+        assert_eq!(clone.keys[&models], new_models);
+        assert_eq!(clone.keys[&cres_key], new_cres);
+        assert!(!clone.keys.contains_key(&other_cres));
+
+        match &clone.dbpf.entries[&new_models].data {
+            DbpfKind::TextLists(names) => {
+                assert_eq!(names.items[0].value, format!("{}chair", prefix));
+            }
+            _ => unreachable!(),
+        }
+        match &clone.dbpf.entries[&new_cres].data {
+            DbpfKind::CreationResource(cres) => {
+                assert_eq!(cres.links[0].key(), clone.keys[&shpe_key]);
+                assert_eq!(
+                    cres.resource_node().unwrap().file_name(),
+                    Some(format!("{}chair_cres", prefix).as_str())
+                );
+            }
+            _ => unreachable!(),
+        }
+        match &clone.dbpf.entries[&clone.keys[&shpe_key]].data {
+            DbpfKind::Shape(shpe) => {
+                assert_eq!(
+                    shpe.shape().unwrap().parts[0].material,
+                    format!("{}chair_seat", prefix)
+                );
+            }
+            _ => unreachable!(),
+        }
+        match &clone.dbpf.entries[&clone.keys[&txmt_key]].data {
+            DbpfKind::TexturedMaterial(txmt) => {
+                let material = txmt.material().unwrap();
+                assert_eq!(
+                    material.resource.file_name,
+                    format!("{}chair_seat_txmt", prefix)
+                );
+                assert_eq!(
+                    material.texture_names(),
+                    vec![
+                        format!("{}chair_seat_base", prefix).as_str(),
+                        "##0x1c050000!shared_bump"
+                    ]
+                );
+            }
+            _ => unreachable!(),
+        }
+        // The renamed scenegraph is reachable from the clone's own model names
+        let graph = DependencyGraph::build(slice::from_ref(&clone.dbpf));
+        assert!(graph
+            .reachable(&[new_models])
+            .contains(&clone.keys[&txtr_key]));
+
+        let guid = clone.guids.get(0x1234_5678).unwrap();
+        match &clone.dbpf.entries[&new_objd].data {
+            DbpfKind::ObjectData(objd) => {
+                assert_eq!(objd.guid, guid);
+                assert_eq!(objd.original_guid, guid);
+                assert_eq!(objd.proxy_guid, 0x0BAD_F00D);
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(registry.object(guid), Some(&new_objd));
+        assert_eq!(registry.object(0x1234_5678), Some(&objd));
+
+        assert!(ObjectClone::create(&source, &models, group, &mut registry).is_none());
+    }
+}
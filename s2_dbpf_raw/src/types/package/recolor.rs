@@ -0,0 +1,516 @@
+////////////////////////////////////////////////////////////////////////////////
+// This Source Code Form is subject to the terms of the Mozilla Public         /
+// License, v. 2.0. If a copy of the MPL was not distributed with this         /
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
+////////////////////////////////////////////////////////////////////////////////
+
+//! Recolors of objects and CAS parts: a copy of a material and its textures under new names,
+//! with new images swapped in, and the MMAT or GZPS pointing the original mesh at the copy.
+
+use std::fs;
+use std::path::Path;
+
+use crate::constants::data_kinds::{DbpfId, DbpfKind};
+use crate::error::DbpfResult;
+use crate::types::neighborhood::three_d_reference::find;
+use crate::types::package::database_packed_file::{Dbpf, Entry, Key};
+use crate::types::scenegraph::textured_material::TexturedMaterial;
+use crate::types::texture_resource::TextureResource;
+use crate::types::util::hash::{
+    instance_hash, name_key, resource_hash, split_group, with_suffix, LOCAL_GROUP,
+};
+use crate::types::util::image::RgbaImage;
+use crate::types::util::texture::TextureFormat;
+
+/// Builds recolors named after `name`. Every copied resource keeps its original name with
+/// `name` and an underscore put in front, inside any group prefix, so `##0x1c050000!chair_seat`
+/// becomes `##0x1c050000!red_chair_seat` for the name `red`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecolorBuilder {
+    name: String,
+    images: Vec<(String, RgbaImage)>,
+}
+
+/// The resources making up a recolor, ready to be added to a package.
+#[derive(Debug, Clone, Default)]
+pub struct Recolor {
+    pub entries: Vec<(Key, DbpfKind)>,
+}
+
+impl Recolor {
+    pub fn insert_into(self, dbpf: &mut Dbpf) {
+        for (key, data) in self.entries {
+            dbpf.entries.insert(
+                key,
+                Entry {
+                    compressed: false,
+                    data,
+                },
+            );
+        }
+    }
+}
+
+impl RecolorBuilder {
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            images: vec![],
+        }
+    }
+
+    /// A builder with an image for every `.png` file in `folder`, each swapped in for the
+    /// texture the file is named after, so `chair_seat_base.png` replaces
+    /// `##0x1c050000!chair_seat_base_txtr`. Other files are ignored.
+    ///
+    /// # Errors
+    /// Returns [`Error::IoError`] if the folder or a file can't be read, and
+    /// [`Error::ImageError`] if a file isn't a valid PNG.
+    ///
+    /// [`Error::IoError`]: crate::error::Error::IoError
+    /// [`Error::ImageError`]: crate::error::Error::ImageError
+    pub fn from_folder(name: &str, folder: &Path) -> DbpfResult<Self> {
+        let mut paths = fs::read_dir(folder)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        paths.sort();
+        let mut builder = Self::new(name);
+        for path in paths {
+            let is_png = path
+                .extension()
+                .map_or(false, |extension| extension.eq_ignore_ascii_case("png"));
+            let texture = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(texture) if is_png && path.is_file() => texture.to_string(),
+                _ => continue,
+            };
+            let image = RgbaImage::decode(&fs::read(&path)?)?;
+            builder = builder.image(&texture, image);
+        }
+        Ok(builder)
+    }
+
+    /// Swaps `image` in for the texture called `texture`. The group prefix and `_txtr` suffix
+    /// can be left out, and case doesn't matter.
+    #[must_use]
+    pub fn image(mut self, texture: &str, image: RgbaImage) -> Self {
+        self.images.push((texture_stem(texture), image));
+        self
+    }
+
+    /// Recolors an object from one of its MMATs, found across `packages`. The new MMAT is no
+    /// longer the default material but keeps the model, subset and family of the original, so
+    /// the game lists it with the object's other recolors.
+    #[must_use]
+    pub fn object(&self, packages: &[Dbpf], mmat: &Key) -> Option<Recolor> {
+        let (mmat_key, mmat) = match find(packages, None, mmat)? {
+            (key, DbpfKind::MaterialOverride(mmat)) => (key, mmat),
+            _ => return None,
+        };
+        let material_name = mmat.material_definition()?;
+        let (txmt_key, txmt) = match find(
+            packages,
+            Some(&mmat_key),
+            &name_key(
+                DbpfId::TexturedMaterial,
+                &with_suffix(material_name, "_txmt"),
+            ),
+        )? {
+            (key, DbpfKind::TexturedMaterial(txmt)) => (key, txmt),
+            _ => return None,
+        };
+
+        let mut recolor = Recolor::default();
+        let new_material = self.rename(material_name);
+        self.material(
+            packages,
+            &txmt_key,
+            txmt,
+            &with_suffix(&new_material, "_txmt"),
+            &mut recolor,
+        );
+
+        let mut new_mmat = mmat.clone();
+        let new_name = self.rename(mmat.name().unwrap_or(material_name));
+        new_mmat.set_name(new_name.clone());
+        new_mmat.set_material_definition(new_material);
+        new_mmat.set_default_material(false);
+        recolor.entries.push((
+            renamed_key(&mmat_key, &new_name),
+            DbpfKind::MaterialOverride(new_mmat),
+        ));
+        Some(recolor)
+    }
+
+    /// Recolors a CAS part from its GZPS, found across `packages` along with the 3IDR next to
+    /// it. Every TXMT the 3IDR lists is copied, the meshes it lists stay as they are, and so do
+    /// TXMTs without a material definition, which have no name to copy them under.
+    #[must_use]
+    pub fn part(&self, packages: &[Dbpf], gzps: &Key) -> Option<Recolor> {
+        let (gzps_key, gzps) = match find(packages, None, gzps)? {
+            (key, DbpfKind::PropertySet(gzps)) => (key, gzps),
+            _ => return None,
+        };
+        let reference = Key {
+            kind: DbpfId::ThreeDReference,
+            resource_id: None,
+            ..gzps_key
+        };
+        let (reference_key, references) = match find(packages, None, &reference)? {
+            (key, DbpfKind::ThreeDReference(references)) => (key, references),
+            _ => return None,
+        };
+
+        let mut recolor = Recolor::default();
+        let mut new_references = references.clone();
+        for key in &mut new_references.keys {
+            if key.kind != DbpfId::TexturedMaterial {
+                continue;
+            }
+            if let Some((txmt_key, DbpfKind::TexturedMaterial(txmt))) =
+                find(packages, Some(&reference_key), key)
+            {
+                let name = match txmt.material() {
+                    Some(material) => material.resource.file_name.clone(),
+                    None => continue,
+                };
+                let new_key = self.material(
+                    packages,
+                    &txmt_key,
+                    txmt,
+                    &with_suffix(&self.rename(&name), "_txmt"),
+                    &mut recolor,
+                );
+                *key = Key {
+                    resource_id: key.resource_id.and(new_key.resource_id),
+                    ..new_key
+                };
+            }
+        }
+
+        let mut new_gzps = gzps.clone();
+        let new_name = self.rename(gzps.name().unwrap_or_default());
+        new_gzps.set_name(new_name.clone());
+        let new_gzps_key = renamed_key(&gzps_key, &new_name);
+        recolor.entries.push((
+            Key {
+                kind: DbpfId::ThreeDReference,
+                ..new_gzps_key
+            },
+            DbpfKind::ThreeDReference(new_references),
+        ));
+        recolor
+            .entries
+            .push((new_gzps_key, DbpfKind::PropertySet(new_gzps)));
+        Some(recolor)
+    }
+
+    /// Copies the TXMT at `key` under `new_name` along with its textures, returning the new
+    /// key. Textures that can't be found are only created if there's an image for them,
+    /// otherwise the copy keeps using the original.
+    fn material(
+        &self,
+        packages: &[Dbpf],
+        key: &Key,
+        txmt: &TexturedMaterial,
+        new_name: &str,
+        recolor: &mut Recolor,
+    ) -> Key {
+        let mut new_txmt = txmt.clone();
+        let new_key = renamed_key(key, new_name);
+        if let Some(material) = new_txmt.material_mut() {
+            material.resource.file_name = new_name.to_string();
+            if !material.description.is_empty() {
+                material.description = self.rename(&material.description);
+            }
+            let textures: Vec<String> = material
+                .texture_names()
+                .into_iter()
+                .map(ToString::to_string)
+                .collect();
+            for texture in textures {
+                let new_texture = self.rename(&texture);
+                let new_file_name = with_suffix(&new_texture, "_txtr");
+                let image = self.image_for(&texture);
+                let original = find(
+                    packages,
+                    Some(key),
+                    &name_key(DbpfId::TextureResource, &with_suffix(&texture, "_txtr")),
+                );
+                let (texture_key, new_txtr) = match (original, image) {
+                    (Some((texture_key, DbpfKind::TextureResource(txtr))), _) => {
+                        let mut new_txtr = txtr.clone();
+                        if let Some(data) = new_txtr.image_data_mut() {
+                            data.resource.file_name.clone_from(&new_file_name);
+                            if data.file_name_repeat.is_some() {
+                                data.file_name_repeat = Some(new_file_name.clone());
+                            }
+                            if let Some(image) = image {
+                                data.set_rgba(image);
+                            }
+                        }
+                        (renamed_key(&texture_key, &new_file_name), new_txtr)
+                    }
+                    (_, Some(image)) => {
+                        let mut texture_key = name_key(DbpfId::TextureResource, &new_file_name);
+                        if texture_key.group_id == LOCAL_GROUP {
+                            texture_key.group_id = key.group_id;
+                        }
+                        let format = if image.pixels().all(|pixel| pixel[3] == 0xFF) {
+                            TextureFormat::Dxt1
+                        } else {
+                            TextureFormat::Dxt5
+                        };
+                        (
+                            texture_key,
+                            TextureResource::from_rgba(new_file_name, image, format),
+                        )
+                    }
+                    _ => continue,
+                };
+                material.retarget_texture(&texture, &new_texture);
+                recolor
+                    .entries
+                    .push((texture_key, DbpfKind::TextureResource(new_txtr)));
+            }
+        }
+        recolor
+            .entries
+            .push((new_key, DbpfKind::TexturedMaterial(new_txmt)));
+        new_key
+    }
+
+    fn rename(&self, name: &str) -> String {
+        match split_group(name) {
+            (Some(group), rest) => format!("##0x{:08x}!{}_{}", group.0, self.name, rest),
+            (None, rest) => format!("{}_{}", self.name, rest),
+        }
+    }
+
+    fn image_for(&self, texture: &str) -> Option<&RgbaImage> {
+        let stem = texture_stem(texture);
+        self.images
+            .iter()
+            .find(|(name, _)| *name == stem)
+            .map(|(_, image)| image)
+    }
+}
+
+/// A texture name without its group prefix and `_txtr` suffix, lowercased.
+fn texture_stem(name: &str) -> String {
+    let name = split_group(name).1.to_lowercase();
+    match name.strip_suffix("_txtr") {
+        Some(stem) => stem.to_string(),
+        None => name,
+    }
+}
+
+/// `key` with the instance and resource id of `name`, keeping its type and group.
+fn renamed_key(key: &Key, name: &str) -> Key {
+    Key {
+        instance_id: instance_hash(name),
+        resource_id: key.resource_id.map(|_| resource_hash(name)),
+        ..*key
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::dbpf_from;
+    use crate::types::neighborhood::three_d_reference::ThreeDReference;
+    use crate::types::package::database_packed_file::InstanceId;
+    use crate::types::package::dependency_graph::DependencyGraph;
+    use crate::types::scenegraph::material_override::MaterialOverride;
+    use crate::types::scenegraph::property_set::PropertySet;
+    use crate::types::scenegraph::textured_material::{MaterialDefinition, MaterialProperty};
+    use crate::types::util::cpf::{Cpf, CpfValue};
+    use crate::types::util::image::ImageFileFormat;
+    use crate::types::util::resource_collection::{BlockHeader, ResourceCollection, SgResource};
+
+    fn filled(pixel: [u8; 4]) -> RgbaImage {
+        let mut image = RgbaImage::new(4, 4);
+        for y in 0..4 {
+            for x in 0..4 {
+                image.set_pixel(x, y, pixel);
+            }
+        }
+        image
+    }
+
+    fn material(name: &str, texture: &str) -> TexturedMaterial {
+        TexturedMaterial {
+            collection: ResourceCollection::new(vec![MaterialDefinition {
+                block: BlockHeader::of::<MaterialDefinition>(11),
+                resource: SgResource::new(name.to_string()),
+                description: String::new(),
+                shader: "StandardMaterial".to_string(),
+                properties: vec![MaterialProperty {
+                    name: "stdMatBaseTextureName".to_string(),
+                    value: texture.to_string(),
+                }],
+                textures: vec![],
+            }
+            .into()]),
+        }
+    }
+
+    fn source() -> (Key, Key, Key, Dbpf) {
+        let txmt = name_key(DbpfId::TexturedMaterial, "##0x1c050000!chair_seat_txmt");
+        let txtr = name_key(DbpfId::TextureResource, "##0x1c050000!chair_seat_base_txtr");
+        let mmat = Key {
+            kind: DbpfId::MaterialOverride,
+            ..name_key(DbpfId::MaterialOverride, "##0x1c050000!chair_seat_mmat")
+        };
+        let mut cpf = Cpf::default();
+        cpf.set_name("##0x1c050000!chair_seat_mmat".to_string());
+        cpf.set_family("fb5d9e39-8b5e-4e9c-9f3b-46c2a8b5c1d0".to_string());
+        cpf.set("defaultMaterial", CpfValue::Bool(true));
+        let mut override_ = MaterialOverride { cpf };
+        override_.set_model_name("chair_cres".to_string());
+        override_.set_subset_name("seat".to_string());
+        override_.set_material_definition("##0x1c050000!chair_seat".to_string());
+
+        let package = dbpf_from(vec![
+            (mmat, DbpfKind::MaterialOverride(override_)),
+            (
+                txmt,
+                DbpfKind::TexturedMaterial(material(
+                    "##0x1c050000!chair_seat_txmt",
+                    "##0x1c050000!chair_seat_base",
+                )),
+            ),
+            (
+                txtr,
+                DbpfKind::TextureResource(TextureResource::from_rgba(
+                    "##0x1c050000!chair_seat_base_txtr".to_string(),
+                    &filled([0, 0, 255, 255]),
+                    TextureFormat::Raw32,
+                )),
+            ),
+        ]);
+        (mmat, txmt, txtr, package)
+    }
+
+    #[test]
+    fn recolor_object() {
+        let (mmat, _, _, mut package) = source();
+        let recolor = RecolorBuilder::new("red")
+            .image("Chair_Seat_Base_txtr", filled([255, 0, 0, 255]))
+            .object(&[package.clone()], &mmat)
+            .unwrap();
+        assert_eq!(recolor.entries.len(), 3);
+        recolor.insert_into(&mut package);
+
+        let new_mmat = renamed_key(&mmat, "##0x1c050000!red_chair_seat_mmat");
+        let new_txmt = name_key(DbpfId::TexturedMaterial, "##0x1c050000!red_chair_seat_txmt");
+        let new_txtr = name_key(
+            DbpfId::TextureResource,
+            "##0x1c050000!red_chair_seat_base_txtr",
+        );
+        match &package.entries[&new_mmat].data {
+            DbpfKind::MaterialOverride(mmat) => {
+                assert_eq!(
+                    mmat.material_definition(),
+                    Some("##0x1c050000!red_chair_seat")
+                );
+                assert_eq!(mmat.model_name(), Some("chair_cres"));
+                assert_eq!(mmat.default_material(), Some(false));
+                assert_eq!(mmat.family(), Some("fb5d9e39-8b5e-4e9c-9f3b-46c2a8b5c1d0"));
+            }
+            _ => unreachable!(),
+        }
+        match &package.entries[&new_txtr].data {
+            DbpfKind::TextureResource(txtr) => {
+                assert_eq!(txtr.to_rgba(None).unwrap().pixel(1, 1), [255, 0, 0, 255]);
+            }
+            _ => unreachable!(),
+        }
+
+        let graph = DependencyGraph::build(&[package]);
+        assert_eq!(graph.dependencies(&new_txmt), vec![new_txtr]);
+    }
+
+    #[test]
+    fn recolor_part() {
+        let (_, txmt, _, package) = source();
+        let cres = name_key(DbpfId::CreationResource, "##0x1c050000!afbody_cres");
+        let gzps = Key {
+            kind: DbpfId::PropertySet,
+            group_id: txmt.group_id,
+            instance_id: instance_hash("afbody_blue"),
+            resource_id: None,
+        };
+        let mut cpf = Cpf::default();
+        cpf.set_name("afbody_blue".to_string());
+        // A TXMT without a material definition stays as it is
+        let blank = Key {
+            instance_id: InstanceId(1),
+            ..txmt
+        };
+        let part = dbpf_from(vec![
+            (gzps, DbpfKind::PropertySet(PropertySet { cpf })),
+            (
+                blank,
+                DbpfKind::TexturedMaterial(TexturedMaterial {
+                    collection: ResourceCollection::default(),
+                }),
+            ),
+            (
+                Key {
+                    kind: DbpfId::ThreeDReference,
+                    ..gzps
+                },
+                DbpfKind::ThreeDReference(ThreeDReference {
+                    keys: vec![cres, txmt, blank],
+                    ..ThreeDReference::default()
+                }),
+            ),
+        ]);
+
+        let recolor = RecolorBuilder::new("red")
+            .part(&[part, package], &gzps)
+            .unwrap();
+
+        let new_gzps = renamed_key(&gzps, "red_afbody_blue");
+        let new_txmt = name_key(DbpfId::TexturedMaterial, "##0x1c050000!red_chair_seat_txmt");
+        let references = recolor
+            .entries
+            .iter()
+            .find_map(|(key, data)| match data {
+                DbpfKind::ThreeDReference(references) => Some((key, references)),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(references.0.instance_id, new_gzps.instance_id);
+        assert_eq!(references.1.keys, vec![cres, new_txmt, blank]);
+        assert_eq!(recolor.entries.len(), 4);
+        assert!(recolor.entries.iter().any(|(key, data)| {
+            *key == new_gzps
+                && matches!(data, DbpfKind::PropertySet(gzps) if gzps.name() == Some("red_afbody_blue"))
+        }));
+    }
+
+    #[test]
+    fn images_from_folder() {
+        let folder = std::env::temp_dir().join(format!("s2_dbpf_recolor_{}", std::process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        let red = filled([255, 0, 0, 255]);
+        fs::write(
+            folder.join("Chair_Seat_Base.PNG"),
+            red.encode(ImageFileFormat::Png).unwrap(),
+        )
+        .unwrap();
+        fs::write(folder.join("notes.txt"), "not an image").unwrap();
+
+        let builder = RecolorBuilder::from_folder("red", &folder);
+        fs::remove_dir_all(&folder).unwrap();
+
+        let builder = builder.unwrap();
+        assert_eq!(builder.images, vec![("chair_seat_base".to_string(), red)]);
+        assert_eq!(
+            builder.image_for("##0x1c050000!chair_seat_base_txtr"),
+            builder.images.first().map(|(_, image)| image)
+        );
+    }
+}
@@ -4,4 +4,813 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct PieMenuFunctions {}
+//! The interaction table of an object. Every interaction points at an action and a guard BHAV,
+//! and at the string in the TTAs with the same instance that is shown in the pie menu.
+
+use std::io::{Read, Seek, Write};
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId, DbpfKind};
+use crate::types::package::database_packed_file::{Dbpf, Key};
+use crate::types::pie_menu_settings::PieMenuSettings;
+use crate::types::text_lists::LanguageId;
+use crate::types::util::parser_args::ParserArgs;
+use binrw::helpers::until_eof;
+use binrw::{binrw, BinRead, BinResult, BinWrite, NullString, ReadOptions, VecArgs, WriteOptions};
+#[cfg(test)]
+use proptest::collection::vec;
+#[cfg(test)]
+use proptest::prelude::*;
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type TTAB = PieMenuFunctions;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(_args: ParserArgs))]
+pub struct PieMenuFunctions {
+    #[br(try_map(NullString::try_into))]
+    #[bw(map(| x: &String | NullString::from(x.clone())))]
+    #[brw(pad_size_to = 64)]
+    #[cfg_attr(test, strategy("[a-zA-Z0-9 ]{0,63}"))]
+    pub file_name: String,
+    #[br(temp)]
+    #[bw(calc = unknown.as_ref().map_or(interactions.len() as u16, |unknown| unknown.count))]
+    num_interactions: u16,
+    pub version: Version,
+    #[br(if(version.is_known()), args { count: num_interactions as usize, inner: (version,) })]
+    #[bw(assert(
+        version.is_known() || interactions.is_empty(),
+        "interactions can't be written in unknown TTAB version {:?}",
+        version
+    ))]
+    #[bw(args_raw = (* version,))]
+    #[cfg_attr(test, strategy(vec(any_with::< Interaction > ((# version,)), (0..10))))]
+    pub interactions: Vec<Interaction>,
+    /// The interactions of a version whose layout isn't known, kept as they were read
+    #[br(if(!version.is_known()), args(num_interactions))]
+    #[cfg_attr(test, strategy(Just(None)))]
+    pub unknown: Option<UnknownInteractions>,
+}
+
+impl DbpfEntry for PieMenuFunctions {
+    fn id(&self) -> DbpfId {
+        DbpfId::PieMenuFunctions
+    }
+
+    fn name(&self) -> Option<String> {
+        Some(self.file_name.clone())
+    }
+}
+
+impl PieMenuFunctions {
+    #[must_use]
+    pub fn new(file_name: String, version: Version) -> Self {
+        Self {
+            file_name,
+            version,
+            interactions: vec![],
+            unknown: None,
+        }
+    }
+
+    /// Appends a new interaction with the fields this table's version needs, returning its index.
+    pub fn add_interaction(&mut self, action: u16, guard: u16, string_index: u32) -> usize {
+        let mut interaction = Interaction::new(self.version);
+        interaction.action = action;
+        interaction.guard = guard;
+        interaction.string_index = string_index;
+        self.interactions.push(interaction);
+        self.interactions.len() - 1
+    }
+
+    pub fn remove_interaction(&mut self, index: usize) -> Interaction {
+        self.interactions.remove(index)
+    }
+
+    /// Pairs every interaction with its pie menu text from `settings` in `language`.
+    #[must_use]
+    pub fn menu<'a>(
+        &'a self,
+        settings: Option<&'a PieMenuSettings>,
+        language: LanguageId,
+    ) -> Vec<MenuEntry<'a>> {
+        self.interactions
+            .iter()
+            .enumerate()
+            .map(|(index, interaction)| MenuEntry {
+                index,
+                interaction,
+                text: settings.and_then(|settings| {
+                    settings.text(interaction.string_index as usize, language)
+                }),
+            })
+            .collect()
+    }
+
+    /// Lists the interactions of the TTAB stored under `key` along with their menu text, read
+    /// from the TTAs sharing its group and instance.
+    #[must_use]
+    pub fn menu_for<'a>(
+        key: &Key,
+        dbpf: &'a Dbpf,
+        language: LanguageId,
+    ) -> Option<Vec<MenuEntry<'a>>> {
+        let functions = match dbpf.find(DbpfId::PieMenuFunctions, key.group_id, key.instance_id) {
+            Some((_, DbpfKind::PieMenuFunctions(functions))) => functions,
+            _ => return None,
+        };
+        let settings = match dbpf.find(DbpfId::PieMenuSettings, key.group_id, key.instance_id) {
+            Some((_, DbpfKind::PieMenuSettings(settings))) => Some(settings),
+            _ => None,
+        };
+        Some(functions.menu(settings, language))
+    }
+}
+
+/// An interaction joined with the text it shows in the pie menu.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MenuEntry<'a> {
+    /// Position of the interaction within the TTAB
+    pub index: usize,
+    pub interaction: &'a Interaction,
+    /// `None` when the TTAs is missing or has no string at the interaction's index
+    pub text: Option<&'a str>,
+}
+
+/// The interaction table of a TTAB whose version isn't one of [`Version`]'s known ones.
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[brw(little)]
+#[br(import(count: u16))]
+pub struct UnknownInteractions {
+    #[br(calc = count)]
+    #[bw(ignore)]
+    pub count: u16,
+    /// The rest of the resource
+    #[br(parse_with = until_eof)]
+    pub data: Vec<u8>,
+}
+
+/// TTAB format version. Everything below 0x44 uses the older layout shared with The Sims 1,
+/// which stores a single motive group and its length up front. Other versions are kept as
+/// [`UnknownInteractions`].
+#[binrw]
+#[derive(Debug, PartialOrd, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub enum Version {
+    #[brw(magic(0x07_u16))]
+    Seven,
+    #[brw(magic(0x08_u16))]
+    Eight,
+    #[brw(magic(0x44_u16))]
+    SixtyEight,
+    #[brw(magic(0x45_u16))]
+    SixtyNine,
+    #[brw(magic(0x46_u16))]
+    Seventy,
+    #[brw(magic(0x47_u16))]
+    SeventyOne,
+    #[brw(magic(0x48_u16))]
+    SeventyTwo,
+    #[brw(magic(0x49_u16))]
+    SeventyThree,
+    #[brw(magic(0x4A_u16))]
+    SeventyFour,
+    #[brw(magic(0x4B_u16))]
+    SeventyFive,
+    #[brw(magic(0x4C_u16))]
+    SeventySix,
+    #[brw(magic(0x4D_u16))]
+    SeventySeven,
+    #[brw(magic(0x4E_u16))]
+    SeventyEight,
+    #[brw(magic(0x54_u16))]
+    EightyFour,
+    #[cfg_attr(test, weight(0))]
+    Other(u16),
+}
+
+impl Version {
+    /// Whether the interaction layout of this version is known.
+    #[must_use]
+    pub fn is_known(self) -> bool {
+        !matches!(self, Version::Other(_))
+    }
+}
+
+impl Default for Version {
+    fn default() -> Self {
+        Version::EightyFour
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interaction {
+    /// Instance of the BHAV run when the interaction is chosen
+    pub action: u16,
+    /// Instance of the BHAV deciding whether the interaction shows up, 0 for none
+    pub guard: u16,
+    pub flags: u16,
+    pub flags_2: u16,
+    /// Index of the pie menu text in the matching TTAs
+    pub string_index: u32,
+    /// 0 uses `attenuation_value`, 1 through 4 are none, low, medium and high
+    pub attenuation_code: u32,
+    pub attenuation_value: f32,
+    pub autonomy_threshold: u32,
+    /// Index of the interaction this one joins, -1 for none
+    pub joining_index: i32,
+    pub ui_display_type: Option<u16>,
+    pub facial_animation: Option<u32>,
+    pub memory_iterative_multiplier: Option<f32>,
+    pub object_type: Option<u32>,
+    pub model_table_id: Option<u32>,
+    /// Motive advertisements for sims, one group per age
+    pub motives: Vec<Vec<MotiveAdvertisement>>,
+    pub animal_motives: Option<Vec<Vec<AnimalMotiveAdvertisement>>>,
+}
+
+impl Interaction {
+    pub const FLAG_VISITORS: u16 = 0x0001;
+    pub const FLAG_JOINABLE: u16 = 0x0002;
+    pub const FLAG_RUN_IMMEDIATELY: u16 = 0x0004;
+    pub const FLAG_ALLOW_CONSECUTIVE: u16 = 0x0008;
+    pub const FLAG_DEBUG: u16 = 0x0080;
+    pub const FLAG_AUTO_FIRST_SELECT: u16 = 0x0100;
+
+    /// An empty interaction with every field present that `version` stores.
+    #[must_use]
+    pub fn new(version: Version) -> Self {
+        Self {
+            action: 0,
+            guard: 0,
+            flags: 0,
+            flags_2: 0,
+            string_index: 0,
+            attenuation_code: 0,
+            attenuation_value: 0.0,
+            autonomy_threshold: 0,
+            joining_index: -1,
+            ui_display_type: (version >= Version::SixtyNine).then(|| 0),
+            facial_animation: (version >= Version::Seventy).then(|| 0),
+            memory_iterative_multiplier: (version >= Version::SeventyFour).then(|| 0.0),
+            object_type: (version >= Version::SeventyFour).then(|| 0),
+            model_table_id: (version >= Version::SeventyFour).then(|| 0),
+            motives: if version < Version::SixtyEight {
+                vec![vec![]]
+            } else {
+                vec![]
+            },
+            animal_motives: (version >= Version::EightyFour).then(Vec::new),
+        }
+    }
+
+    #[must_use]
+    pub fn has_flag(&self, flag: u16) -> bool {
+        self.flags & flag == flag
+    }
+
+    pub fn set_flag(&mut self, flag: u16, value: bool) {
+        if value {
+            self.flags |= flag;
+        } else {
+            self.flags &= !flag;
+        }
+    }
+
+    #[must_use]
+    pub fn is_joinable(&self) -> bool {
+        self.has_flag(Self::FLAG_JOINABLE)
+    }
+}
+
+impl BinRead for Interaction {
+    type Args = (Version,);
+
+    fn read_options<R: Read + Seek>(
+        reader: &mut R,
+        options: &ReadOptions,
+        args: Self::Args,
+    ) -> BinResult<Self> {
+        let version = args.0;
+        let action = u16::read_options(reader, options, ())?;
+        let guard = u16::read_options(reader, options, ())?;
+        let legacy_count = if version < Version::SixtyEight {
+            Some(u32::read_options(reader, options, ())?)
+        } else {
+            None
+        };
+        let flags = u16::read_options(reader, options, ())?;
+        let flags_2 = u16::read_options(reader, options, ())?;
+        let string_index = u32::read_options(reader, options, ())?;
+        let attenuation_code = u32::read_options(reader, options, ())?;
+        let attenuation_value = f32::read_options(reader, options, ())?;
+        let autonomy_threshold = u32::read_options(reader, options, ())?;
+        let joining_index = i32::read_options(reader, options, ())?;
+        let ui_display_type = if version >= Version::SixtyNine {
+            Some(u16::read_options(reader, options, ())?)
+        } else {
+            None
+        };
+        let facial_animation = if version >= Version::Seventy {
+            Some(u32::read_options(reader, options, ())?)
+        } else {
+            None
+        };
+        let (memory_iterative_multiplier, object_type, model_table_id) =
+            if version >= Version::SeventyFour {
+                (
+                    Some(f32::read_options(reader, options, ())?),
+                    Some(u32::read_options(reader, options, ())?),
+                    Some(u32::read_options(reader, options, ())?),
+                )
+            } else {
+                (None, None, None)
+            };
+        let motives = if let Some(count) = legacy_count {
+            vec![read_group(reader, options, count)?]
+        } else {
+            let groups = u32::read_options(reader, options, ())?;
+            let mut motives = Vec::with_capacity(groups as usize);
+            for _ in 0..groups {
+                let count = u32::read_options(reader, options, ())?;
+                motives.push(read_group(reader, options, count)?);
+            }
+            motives
+        };
+        let animal_motives = if version >= Version::EightyFour {
+            let groups = u32::read_options(reader, options, ())?;
+            let mut animal_motives = Vec::with_capacity(groups as usize);
+            for _ in 0..groups {
+                let count = u32::read_options(reader, options, ())?;
+                animal_motives.push(read_group(reader, options, count)?);
+            }
+            Some(animal_motives)
+        } else {
+            None
+        };
+
+        Ok(Interaction {
+            action,
+            guard,
+            flags,
+            flags_2,
+            string_index,
+            attenuation_code,
+            attenuation_value,
+            autonomy_threshold,
+            joining_index,
+            ui_display_type,
+            facial_animation,
+            memory_iterative_multiplier,
+            object_type,
+            model_table_id,
+            motives,
+            animal_motives,
+        })
+    }
+}
+
+impl BinWrite for Interaction {
+    type Args = (Version,);
+
+    fn write_options<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+        args: Self::Args,
+    ) -> BinResult<()> {
+        let legacy = args.0 < Version::SixtyEight;
+        u16::write_options(&self.action, writer, options, ())?;
+        u16::write_options(&self.guard, writer, options, ())?;
+        if legacy {
+            if self.motives.len() > 1 {
+                return Err(binrw::Error::AssertFail {
+                    pos: writer.stream_position()?,
+                    message: format!(
+                        "TTAB version {:?} stores a single motive group, not {}",
+                        args.0,
+                        self.motives.len()
+                    ),
+                });
+            }
+            let count = self.motives.first().map_or(0, Vec::len) as u32;
+            u32::write_options(&count, writer, options, ())?;
+        }
+        u16::write_options(&self.flags, writer, options, ())?;
+        u16::write_options(&self.flags_2, writer, options, ())?;
+        u32::write_options(&self.string_index, writer, options, ())?;
+        u32::write_options(&self.attenuation_code, writer, options, ())?;
+        f32::write_options(&self.attenuation_value, writer, options, ())?;
+        u32::write_options(&self.autonomy_threshold, writer, options, ())?;
+        i32::write_options(&self.joining_index, writer, options, ())?;
+        <Option<u16>>::write_options(&self.ui_display_type, writer, options, ())?;
+        <Option<u32>>::write_options(&self.facial_animation, writer, options, ())?;
+        <Option<f32>>::write_options(&self.memory_iterative_multiplier, writer, options, ())?;
+        <Option<u32>>::write_options(&self.object_type, writer, options, ())?;
+        <Option<u32>>::write_options(&self.model_table_id, writer, options, ())?;
+        if legacy {
+            if let Some(group) = self.motives.first() {
+                group.write_options(writer, options, ())?;
+            }
+        } else {
+            write_groups(&self.motives, writer, *options)?;
+        }
+        if let Some(animal_motives) = &self.animal_motives {
+            write_groups(animal_motives, writer, *options)?;
+        }
+        Ok(())
+    }
+}
+
+fn read_group<R: Read + Seek, T: BinRead<Args = ()>>(
+    reader: &mut R,
+    options: &ReadOptions,
+    count: u32,
+) -> BinResult<Vec<T>> {
+    <Vec<T>>::read_options(
+        reader,
+        options,
+        VecArgs {
+            count: count as usize,
+            inner: (),
+        },
+    )
+}
+
+fn write_groups<W: Write + Seek, T: BinWrite<Args = ()> + 'static>(
+    groups: &[Vec<T>],
+    writer: &mut W,
+    options: WriteOptions,
+) -> BinResult<()> {
+    u32::write_options(&(groups.len() as u32), writer, &options, ())?;
+    for group in groups {
+        u32::write_options(&(group.len() as u32), writer, &options, ())?;
+        group.write_options(writer, &options, ())?;
+    }
+    Ok(())
+}
+
+#[binrw]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct MotiveAdvertisement {
+    pub min: i16,
+    pub delta: i16,
+    pub personality: i16,
+}
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct AnimalMotiveAdvertisement {
+    #[br(temp)]
+    #[bw(calc = values.len() as u32)]
+    count: u32,
+    #[br(count(count as usize))]
+    #[cfg_attr(test, strategy(vec(any::< i16 > (), (0..4))))]
+    pub values: Vec<i16>,
+}
+
+#[cfg(test)]
+prop_compose! {
+    fn interaction_mapper(
+        version: Version
+    )(
+        action in any::<u16>(),
+        guard in any::<u16>(),
+        flags in any::<u16>(),
+        flags_2 in any::<u16>(),
+        string_index in any::<u32>(),
+        attenuation_code in any::<u32>(),
+        attenuation_value in -1000.0_f32..1000.0,
+        autonomy_threshold in any::<u32>(),
+        joining_index in any::<i32>(),
+        ui_display_type in any::<u16>(),
+        facial_animation in any::<u32>(),
+        memory_iterative_multiplier in -1000.0_f32..1000.0,
+        object_type in any::<u32>(),
+        model_table_id in any::<u32>(),
+        legacy_motives in vec(any::<MotiveAdvertisement>(), 0..8),
+        motives in vec(vec(any::<MotiveAdvertisement>(), 0..8), 0..4),
+        animal_motives in vec(vec(any::<AnimalMotiveAdvertisement>(), 0..4), 0..4),
+    ) -> Interaction {
+        Interaction {
+            action,
+            guard,
+            flags,
+            flags_2,
+            string_index,
+            attenuation_code,
+            attenuation_value,
+            autonomy_threshold,
+            joining_index,
+            ui_display_type: (version >= Version::SixtyNine).then(|| ui_display_type),
+            facial_animation: (version >= Version::Seventy).then(|| facial_animation),
+            memory_iterative_multiplier: (version >= Version::SeventyFour)
+                .then(|| memory_iterative_multiplier),
+            object_type: (version >= Version::SeventyFour).then(|| object_type),
+            model_table_id: (version >= Version::SeventyFour).then(|| model_table_id),
+            motives: if version < Version::SixtyEight {
+                vec![legacy_motives]
+            } else {
+                motives
+            },
+            animal_motives: (version >= Version::EightyFour).then(|| animal_motives),
+        }
+    }
+}
+
+#[cfg(test)]
+impl Arbitrary for Interaction {
+    type Parameters = (Version,);
+
+    fn arbitrary_with(args: Self::Parameters) -> Self::Strategy {
+        interaction_mapper(args.0).boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{test_parsing, test_parsing_bhav_ins};
+    use crate::types::package::database_packed_file::{Entry, GroupId, InstanceId};
+    use crate::types::package::header::Header;
+    use crate::types::text_lists::{Format, TextItem, TextList};
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+    use std::collections::HashMap;
+    use test_strategy::proptest;
+
+    test_parsing_bhav_ins!(
+        [
+            0x00, 0x10, // action
+            0x01, 0x10, // guard
+            0x01, 0x00, 0x00, 0x00, // motive count
+            0x02, 0x00, // flags
+            0x00, 0x00, // flags 2
+            0x03, 0x00, 0x00, 0x00, // string index
+            0x00, 0x00, 0x00, 0x00, // attenuation code
+            0x00, 0x00, 0x80, 0x3F, // attenuation value
+            0x32, 0x00, 0x00, 0x00, // autonomy threshold
+            0xFF, 0xFF, 0xFF, 0xFF, // joining index
+            0x00, 0x00, 0x0A, 0x00, 0x00, 0x00, // motive
+        ],
+        Interaction {
+            action: 0x1000,
+            guard: 0x1001,
+            flags: Interaction::FLAG_JOINABLE,
+            flags_2: 0,
+            string_index: 3,
+            attenuation_code: 0,
+            attenuation_value: 1.0,
+            autonomy_threshold: 50,
+            joining_index: -1,
+            ui_display_type: None,
+            facial_animation: None,
+            memory_iterative_multiplier: None,
+            object_type: None,
+            model_table_id: None,
+            motives: vec![vec![MotiveAdvertisement {
+                min: 0,
+                delta: 10,
+                personality: 0
+            }]],
+            animal_motives: None,
+        },
+        Interaction,
+        ttab_interaction_legacy,
+        (Version::Eight,)
+    );
+
+    test_parsing_bhav_ins!(
+        [
+            0x00, 0x10, // action
+            0x00, 0x00, // guard
+            0x00, 0x00, // flags
+            0x00, 0x00, // flags 2
+            0x00, 0x00, 0x00, 0x00, // string index
+            0x02, 0x00, 0x00, 0x00, // attenuation code
+            0x00, 0x00, 0x00, 0x00, // attenuation value
+            0x00, 0x00, 0x00, 0x00, // autonomy threshold
+            0xFF, 0xFF, 0xFF, 0xFF, // joining index
+            0x01, 0x00, // ui display type
+            0x00, 0x00, 0x00, 0x00, // facial animation
+            0x00, 0x00, 0x00, 0x00, // memory iterative multiplier
+            0x00, 0x00, 0x00, 0x00, // object type
+            0x00, 0x00, 0x00, 0x00, // model table id
+            0x01, 0x00, 0x00, 0x00, // motive groups
+            0x01, 0x00, 0x00, 0x00, // motives in group
+            0x00, 0x00, 0x0A, 0x00, 0x01, 0x00, // motive
+            0x01, 0x00, 0x00, 0x00, // animal motive groups
+            0x01, 0x00, 0x00, 0x00, // animal motives in group
+            0x02, 0x00, 0x00, 0x00, // animal motive values
+            0x05, 0x00, 0x06, 0x00, // values
+        ],
+        Interaction {
+            action: 0x1000,
+            guard: 0,
+            flags: 0,
+            flags_2: 0,
+            string_index: 0,
+            attenuation_code: 2,
+            attenuation_value: 0.0,
+            autonomy_threshold: 0,
+            joining_index: -1,
+            ui_display_type: Some(1),
+            facial_animation: Some(0),
+            memory_iterative_multiplier: Some(0.0),
+            object_type: Some(0),
+            model_table_id: Some(0),
+            motives: vec![vec![MotiveAdvertisement {
+                min: 0,
+                delta: 10,
+                personality: 1
+            }]],
+            animal_motives: Some(vec![vec![AnimalMotiveAdvertisement { values: vec![5, 6] }]]),
+        },
+        Interaction,
+        ttab_interaction_84,
+        (Version::EightyFour,)
+    );
+
+    test_parsing!(
+        [
+            0x54, 0x65, 0x73, 0x74, 0x46, 0x69, 0x6C, 0x65, //TestFile - in ascii bytes
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // first padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // second padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // third padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fourth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fifth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sixth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // seventh padding block
+            0x01, 0x00, // number of interactions
+            0x44, 0x00, // version
+            0x00, 0x10, // action
+            0x00, 0x00, // guard
+            0x00, 0x00, // flags
+            0x00, 0x00, // flags 2
+            0x00, 0x00, 0x00, 0x00, // string index
+            0x00, 0x00, 0x00, 0x00, // attenuation code
+            0x00, 0x00, 0x00, 0x00, // attenuation value
+            0x00, 0x00, 0x00, 0x00, // autonomy threshold
+            0xFF, 0xFF, 0xFF, 0xFF, // joining index
+            0x00, 0x00, 0x00, 0x00, // motive groups
+        ],
+        PieMenuFunctions {
+            file_name: "TestFile".to_string(),
+            version: Version::SixtyEight,
+            interactions: vec![Interaction {
+                action: 0x1000,
+                ..Interaction::new(Version::SixtyEight)
+            }],
+            unknown: None,
+        },
+        PieMenuFunctions,
+        ttab
+    );
+
+    test_parsing!(
+        [
+            0x54, 0x65, 0x73, 0x74, 0x46, 0x69, 0x6C, 0x65, //TestFile - in ascii bytes
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // first padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // second padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // third padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fourth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fifth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sixth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // seventh padding block
+            0x02, 0x00, // number of interactions
+            0x55, 0x00, // version
+            0x00, 0x10, 0x01, 0x10, // interaction data
+        ],
+        PieMenuFunctions {
+            file_name: "TestFile".to_string(),
+            version: Version::Other(0x55),
+            interactions: vec![],
+            unknown: Some(UnknownInteractions {
+                count: 2,
+                data: vec![0x00, 0x10, 0x01, 0x10],
+            }),
+        },
+        PieMenuFunctions,
+        ttab_unknown_version
+    );
+
+    #[test]
+    fn unknown_version_stops_at_the_end_of_the_entry() {
+        use crate::test_helpers::package_bytes;
+        use crate::types::package::database_packed_file::{Dbpf, Key};
+
+        let ttab = PieMenuFunctions {
+            file_name: "TestFile".to_string(),
+            version: Version::Other(0x55),
+            interactions: vec![],
+            unknown: Some(UnknownInteractions {
+                count: 2,
+                data: vec![0x00, 0x10, 0x01, 0x10],
+            }),
+        };
+        let mut cursor = Cursor::new(vec![]);
+        cursor.write_le(&ttab).unwrap();
+        let key = Key::new(DbpfId::PieMenuFunctions, GroupId(1), InstanceId(1), None);
+        let next = Key::new(DbpfId::JpegImage, GroupId(1), InstanceId(2), None);
+        let package = package_bytes(&[(key, cursor.into_inner()), (next, vec![0xCC; 8])]);
+
+        let dbpf: Dbpf = Cursor::new(package).read_le().unwrap();
+
+        match &dbpf.entries[&key].data {
+            DbpfKind::PieMenuFunctions(read) => assert_eq!(read, &ttab),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn legacy_write_keeps_one_motive_group() {
+        let mut ttab = PieMenuFunctions::new(String::new(), Version::Eight);
+        let index = ttab.add_interaction(0x1000, 0, 0);
+        ttab.interactions[index].motives.push(vec![]);
+
+        let mut cursor = Cursor::new(vec![]);
+        assert!(cursor.write_le(&ttab).is_err());
+    }
+
+    #[test]
+    fn new_interaction_matches_version() {
+        let mut ttab = PieMenuFunctions::new(String::new(), Version::SeventyFour);
+        let index = ttab.add_interaction(0x1000, 0x1001, 2);
+        let interaction = &ttab.interactions[index];
+
+        assert_eq!(interaction.facial_animation, Some(0));
+        assert_eq!(interaction.model_table_id, Some(0));
+        assert_eq!(interaction.animal_motives, None);
+
+        let mut cursor = Cursor::new(vec![]);
+        cursor.write_le(&ttab).unwrap();
+        cursor.set_position(0);
+        let out: PieMenuFunctions = cursor.read_le().unwrap();
+
+        assert_eq!(out, ttab);
+    }
+
+    #[test]
+    fn menu_joins_text() {
+        let group = GroupId(0x7F00_0001);
+        let instance = InstanceId(0x80);
+        let mut ttab = PieMenuFunctions::new(String::new(), Version::EightyFour);
+        ttab.add_interaction(0x1000, 0, 0);
+        ttab.add_interaction(0x1001, 0, 1);
+        ttab.add_interaction(0x1002, 0, 5);
+        let settings = PieMenuSettings {
+            strings: TextList {
+                file_name: String::new(),
+                format: Format::Localized,
+                items: vec![
+                    TextItem {
+                        language: LanguageId::ENGLISH_US,
+                        value: "Sit".to_string(),
+                        description: String::new(),
+                    },
+                    TextItem {
+                        language: LanguageId::ENGLISH_US,
+                        value: "Stand".to_string(),
+                        description: String::new(),
+                    },
+                ],
+                unknown: None,
+            },
+        };
+        let mut entries = HashMap::new();
+        for data in [
+            DbpfKind::PieMenuFunctions(ttab.clone()),
+            DbpfKind::PieMenuSettings(settings),
+        ] {
+            entries.insert(
+                Key::new(data.id(), group, instance, None),
+                Entry {
+                    compressed: false,
+                    data,
+                },
+            );
+        }
+        let dbpf = Dbpf {
+            header: Header::default(),
+            entries,
+        };
+        let key = Key::new(DbpfId::PieMenuFunctions, group, instance, None);
+
+        let menu = PieMenuFunctions::menu_for(&key, &dbpf, LanguageId::FRENCH).unwrap();
+        let text: Vec<_> = menu.iter().map(|entry| entry.text).collect();
+
+        assert_eq!(text, vec![Some("Sit"), Some("Stand"), None]);
+        assert_eq!(menu[1].interaction, &ttab.interactions[1]);
+    }
+}
@@ -4,4 +4,76 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct PieMenuSettings {}
+//! The pie menu text for a TTAB, stored as a STR# under the same group and instance.
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId};
+use crate::types::text_lists::TextList;
+use crate::types::util::parser_args::ParserArgs;
+use binrw::binrw;
+use derive_more::{Deref, DerefMut};
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type TTAs = PieMenuSettings;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq, Deref, DerefMut)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(args: ParserArgs))]
+pub struct PieMenuSettings {
+    #[br(args_raw = args)]
+    pub strings: TextList,
+}
+
+impl DbpfEntry for PieMenuSettings {
+    fn id(&self) -> DbpfId {
+        DbpfId::PieMenuSettings
+    }
+
+    fn name(&self) -> Option<String> {
+        Some(self.strings.file_name.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use crate::types::text_lists::{Format, LanguageId, TextItem};
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+    use proptest::prelude::*;
+
+    test_parsing!(
+        [
+            0x54, 0x65, 0x73, 0x74, 0x46, 0x69, 0x6C, 0x65, //TestFile - in ascii bytes
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // first padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // second padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // third padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fourth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fifth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sixth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // seventh padding block
+            0xFD, 0xFF, // format
+            0x01, 0x00, // number of items
+            0x01, // language
+            0x53, 0x69, 0x74, 0x00, // "Sit"
+            0x00, // description
+        ],
+        PieMenuSettings {
+            strings: TextList {
+                file_name: "TestFile".to_string(),
+                format: Format::Localized,
+                items: vec![TextItem {
+                    language: LanguageId::ENGLISH_US,
+                    value: "Sit".to_string(),
+                    description: String::new(),
+                }],
+                unknown: None,
+            }
+        },
+        PieMenuSettings,
+        ttas
+    );
+}
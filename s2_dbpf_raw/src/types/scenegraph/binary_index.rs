@@ -4,4 +4,110 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct BinaryIndex {}
+//! Binary indexes (BINX), placing a catalog item in the collection it's listed in and linking its
+//! icon, strings and object.
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId};
+use crate::types::util::cpf::Cpf;
+use crate::types::util::parser_args::ParserArgs;
+use binrw::binrw;
+use derive_more::{Deref, DerefMut};
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type BINX = BinaryIndex;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Default, Deref, DerefMut)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(args: ParserArgs))]
+pub struct BinaryIndex {
+    #[br(args_raw = args)]
+    pub cpf: Cpf,
+}
+
+impl DbpfEntry for BinaryIndex {
+    fn id(&self) -> DbpfId {
+        DbpfId::BinaryIndex
+    }
+
+    fn name(&self) -> Option<String> {
+        self.cpf.name().map(ToString::to_string)
+    }
+}
+
+impl BinaryIndex {
+    /// The index of the icon in the package's links, pointing at an image.
+    #[must_use]
+    pub fn icon_index(&self) -> Option<u32> {
+        self.cpf.uint("iconidx")
+    }
+
+    /// The index of the catalog strings in the package's links.
+    #[must_use]
+    pub fn string_set_index(&self) -> Option<u32> {
+        self.cpf.uint("stringsetidx")
+    }
+
+    /// The index of the collection the item is listed in.
+    #[must_use]
+    pub fn bin_index(&self) -> Option<u32> {
+        self.cpf.uint("binidx")
+    }
+
+    /// The index of the object the item is built from in the package's links.
+    #[must_use]
+    pub fn object_index(&self) -> Option<u32> {
+        self.cpf.uint("objectidx")
+    }
+
+    /// The position of the item within its collection.
+    #[must_use]
+    pub fn sort_index(&self) -> Option<i32> {
+        self.cpf.get("sortindex")?.as_int()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use crate::types::util::cpf::{CpfFormat, CpfProperty, CpfValue};
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+    use proptest::prelude::*;
+
+    test_parsing!(
+        [
+            0xE0, 0x50, 0xE7, 0xCB, // magic
+            0x02, 0x00, // version
+            0x02, 0x00, 0x00, 0x00, // number of properties
+            0xF7, 0xE4, 0x61, 0xEB, // uint
+            0x07, 0x00, 0x00, 0x00, // name length
+            0x69, 0x63, 0x6F, 0x6E, 0x69, 0x64, 0x78, // iconidx
+            0x01, 0x00, 0x00, 0x00, // value
+            0x12, 0x47, 0x26, 0x0C, // int
+            0x09, 0x00, 0x00, 0x00, // name length
+            0x73, 0x6F, 0x72, 0x74, 0x69, 0x6E, 0x64, 0x65, 0x78, // sortindex
+            0xFF, 0xFF, 0xFF, 0xFF, // value
+        ],
+        BinaryIndex {
+            cpf: Cpf {
+                format: CpfFormat::Binary { version: 2 },
+                properties: vec![
+                    CpfProperty {
+                        name: "iconidx".to_string(),
+                        value: CpfValue::Uint(1),
+                    },
+                    CpfProperty {
+                        name: "sortindex".to_string(),
+                        value: CpfValue::Int(-1),
+                    },
+                ],
+            },
+        },
+        BinaryIndex,
+        binx
+    );
+}
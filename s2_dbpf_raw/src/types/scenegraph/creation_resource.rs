@@ -4,4 +4,576 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct CreationResource {}
+//! Creation resources (CRES), the root of an object's scenegraph: a resource node over a tree of
+//! transform nodes, which double as the joints meshes are skinned to. The shapes and lights
+//! hanging off the tree are linked from the collection.
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId};
+use crate::types::util::parser_args::ParserArgs;
+use crate::types::util::resource_collection::{
+    BlockHeader, CompositionTreeNode, ObjectGraphNode, RcolBlock, ResourceCollection, SgResource,
+};
+use crate::types::util::strings::{
+    read_seven_bit_string, read_seven_bit_strings, write_seven_bit_string, write_seven_bit_strings,
+};
+use binrw::binrw;
+use derive_more::{Deref, DerefMut};
+#[cfg(test)]
+use proptest::collection::vec;
+#[cfg(test)]
+use proptest::prelude::*;
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type CRES = CreationResource;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Deref, DerefMut)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(args: ParserArgs))]
+pub struct CreationResource {
+    #[br(args_raw = args)]
+    pub collection: ResourceCollection,
+}
+
+impl DbpfEntry for CreationResource {
+    fn id(&self) -> DbpfId {
+        DbpfId::CreationResource
+    }
+
+    fn name(&self) -> Option<String> {
+        self.resource_node()
+            .and_then(ResourceNode::file_name)
+            .map(ToString::to_string)
+    }
+}
+
+impl CreationResource {
+    #[must_use]
+    pub fn resource_node(&self) -> Option<&ResourceNode> {
+        self.block()
+    }
+
+    pub fn resource_node_mut(&mut self) -> Option<&mut ResourceNode> {
+        self.block_mut()
+    }
+
+    /// The transform nodes in the order they're stored, which is the order child references
+    /// index into.
+    pub fn transform_nodes(&self) -> impl Iterator<Item = &TransformNode> {
+        self.blocks_of()
+    }
+
+    /// The tree of transform nodes as a list of bones, each pointing at its parent. Child
+    /// references index into the collection's blocks.
+    #[must_use]
+    pub fn skeleton(&self) -> Vec<Bone> {
+        let nodes: Vec<(usize, &TransformNode)> = self
+            .blocks
+            .iter()
+            .enumerate()
+            .filter_map(|(index, block)| TransformNode::from_block(block).map(|node| (index, node)))
+            .collect();
+        let bone_of = |block: usize| nodes.iter().position(|(index, _)| *index == block);
+        let mut bones: Vec<Bone> = nodes
+            .iter()
+            .map(|(_, node)| Bone {
+                name: node.file_name().unwrap_or_default().to_string(),
+                parent: None,
+                translation: node.translation,
+                rotation: node.rotation,
+                joint: node.joint(),
+            })
+            .collect();
+        for (parent, (_, node)) in nodes.iter().enumerate() {
+            for child in &node.children {
+                if let Some(child) = bone_of(child.index as usize) {
+                    bones[child].parent = Some(parent);
+                }
+            }
+        }
+        bones
+    }
+}
+
+/// A transform node of the skeleton, with its rest pose relative to its parent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bone {
+    pub name: String,
+    /// Index of the parent bone, `None` for roots
+    pub parent: Option<usize>,
+    pub translation: [f32; 3],
+    /// A quaternion, x y z w
+    pub rotation: [f32; 4],
+    /// The joint index meshes refer to, if the node is a joint
+    pub joint: Option<u32>,
+}
+
+/// The `cResourceNode` block, the root of the scenegraph.
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct ResourceNode {
+    #[cfg_attr(test, strategy(Just(BlockHeader::of::<ResourceNode>(7))))]
+    pub block: BlockHeader,
+    pub node: ResourceNodeKind,
+    #[br(temp)]
+    #[bw(calc = children.len() as u8)]
+    num_children: u8,
+    #[br(count(num_children as usize))]
+    #[cfg_attr(test, strategy(vec(any::<ChildReference>(), 0..4)))]
+    pub children: Vec<ChildReference>,
+    pub unknown1: u32,
+    pub unknown2: u32,
+}
+
+impl ResourceNode {
+    /// The name of the resource, if the node has one.
+    #[must_use]
+    pub fn file_name(&self) -> Option<&str> {
+        match &self.node {
+            ResourceNodeKind::Tree { resource, .. } => Some(&resource.file_name),
+            ResourceNodeKind::Graph { graph } => graph.file_name.as_deref(),
+        }
+    }
+}
+
+/// Resource nodes are either a named composition tree, or a bare graph node.
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub enum ResourceNodeKind {
+    #[brw(magic = 1u8)]
+    Tree {
+        resource: SgResource,
+        tree: CompositionTreeNode,
+    },
+    #[brw(magic = 0u8)]
+    Graph { graph: ObjectGraphNode },
+}
+
+/// The `cTransformNode` block: a node of the object's tree with its position relative to its
+/// parent, which is a joint if `joint` is set.
+#[binrw]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct TransformNode {
+    #[cfg_attr(test, strategy(Just(BlockHeader::of::<TransformNode>(7))))]
+    pub block: BlockHeader,
+    pub tree: CompositionTreeNode,
+    #[br(temp)]
+    #[bw(calc = children.len() as u32)]
+    num_children: u32,
+    #[br(count(num_children as usize))]
+    #[cfg_attr(test, strategy(vec(any::<ChildReference>(), 0..4)))]
+    pub children: Vec<ChildReference>,
+    #[cfg_attr(test, strategy(proptest::array::uniform3(-100.0_f32..100.0)))]
+    pub translation: [f32; 3],
+    /// A quaternion, x y z w
+    #[cfg_attr(test, strategy(proptest::array::uniform4(-1.0_f32..1.0)))]
+    pub rotation: [f32; 4],
+    /// The joint index meshes refer to, or [`TransformNode::NOT_A_JOINT`]
+    pub joint: u32,
+}
+
+impl TransformNode {
+    pub const NOT_A_JOINT: u32 = 0x7FFF_FFFF;
+
+    /// The name of the node, which for joints is the bone name.
+    #[must_use]
+    pub fn file_name(&self) -> Option<&str> {
+        self.tree.graph.file_name.as_deref()
+    }
+
+    #[must_use]
+    pub fn joint(&self) -> Option<u32> {
+        (self.joint != Self::NOT_A_JOINT).then(|| self.joint)
+    }
+}
+
+/// A child of a node, by index into the collection's blocks.
+#[binrw]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct ChildReference {
+    pub unknown: u16,
+    pub index: u32,
+}
+
+/// The `cRenderableNode` and `cBoundedNode` blocks the reference nodes embed, neither has
+/// fields of its own, followed by the `cTransformNode` placing the node in the tree.
+#[binrw]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct RenderableNode {
+    #[cfg_attr(test, strategy(Just(BlockHeader::new(RenderableNode::NAME, 0, 5))))]
+    pub renderable: BlockHeader,
+    #[cfg_attr(test, strategy(Just(BlockHeader::new(RenderableNode::BOUNDED, 0, 5))))]
+    pub bounded: BlockHeader,
+    pub transform: TransformNode,
+}
+
+impl RenderableNode {
+    pub const NAME: &'static str = "cRenderableNode";
+    pub const BOUNDED: &'static str = "cBoundedNode";
+}
+
+/// The `cShapeRefNode` block, a node of the tree drawing the SHPEs linked from the collection.
+#[binrw]
+#[derive(Debug, Clone, PartialEq)]
+#[brw(little)]
+pub struct ShapeRefNode {
+    pub block: BlockHeader,
+    pub node: RenderableNode,
+    pub unknown1: i16,
+    pub unknown2: i32,
+    /// Usually `Practical`
+    #[br(parse_with = read_seven_bit_string)]
+    #[bw(write_with = write_seven_bit_string)]
+    pub category: String,
+    pub unknown3: i32,
+    pub unknown4: u8,
+    #[br(temp)]
+    #[bw(calc = shapes.len() as u32)]
+    num_shapes: u32,
+    #[br(count(num_shapes as usize))]
+    pub shapes: Vec<ShapeReference>,
+    pub unknown5: i32,
+    #[br(temp)]
+    #[bw(calc = blends.len() as u32)]
+    num_blends: u32,
+    #[br(count(num_blends as usize))]
+    pub blends: Vec<i32>,
+    /// The name of every blend, one per entry of `blends`
+    #[br(parse_with = read_seven_bit_strings, args(num_blends))]
+    #[bw(write_with = write_seven_bit_strings)]
+    pub blend_names: Vec<String>,
+    #[br(temp)]
+    #[bw(calc = data.len() as u32)]
+    data_size: u32,
+    #[br(count(data_size as usize))]
+    pub data: Vec<u8>,
+    pub unknown6: i32,
+}
+
+#[cfg(test)]
+prop_compose! {
+    fn shape_ref_node_mapper()(
+        node in any::<RenderableNode>(),
+        unknown in any::<(i16, i32, i32, u8, i32, i32)>(),
+        category in "[a-zA-Z]{0,16}",
+        shapes in vec(any::<ShapeReference>(), 0..4),
+        blends in vec((any::<i32>(), "[a-zA-Z_]{0,16}"), 0..4),
+        data in vec(any::<u8>(), 0..16),
+    ) -> ShapeRefNode {
+        let (unknown1, unknown2, unknown3, unknown4, unknown5, unknown6) = unknown;
+        let (blends, blend_names) = blends.into_iter().unzip();
+        ShapeRefNode {
+            block: BlockHeader::of::<ShapeRefNode>(21),
+            node,
+            unknown1,
+            unknown2,
+            category,
+            unknown3,
+            unknown4,
+            shapes,
+            unknown5,
+            blends,
+            blend_names,
+            data,
+            unknown6,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Arbitrary for ShapeRefNode {
+    type Parameters = ();
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        shape_ref_node_mapper().boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
+
+/// A SHPE drawn by a shape reference node, by index into the collection's links.
+#[binrw]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct ShapeReference {
+    pub unknown: i16,
+    pub index: i32,
+}
+
+/// The `cLightRefNode` block, a node of the tree holding the LGHT linked from the collection.
+#[binrw]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct LightRefNode {
+    #[cfg_attr(test, strategy(Just(BlockHeader::of::<LightRefNode>(10))))]
+    pub block: BlockHeader,
+    pub node: RenderableNode,
+    pub unknown1: i16,
+    #[br(temp)]
+    #[bw(calc = names.len() as u32)]
+    num_names: u32,
+    #[br(parse_with = read_seven_bit_strings, args(num_names))]
+    #[bw(write_with = write_seven_bit_strings)]
+    #[cfg_attr(test, strategy(vec("[a-zA-Z_]{0,16}", 0..4)))]
+    pub names: Vec<String>,
+    pub unknown2: [u8; 13],
+}
+
+/// The `cViewerRefNode` block, a camera placed in the tree.
+#[binrw]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct ViewerRefNode {
+    #[cfg_attr(test, strategy(Just(BlockHeader::of::<ViewerRefNode>(1))))]
+    pub block: BlockHeader,
+    /// The embedded `cViewerRefNodeBase` block, which has no fields of its own
+    #[cfg_attr(test, strategy(Just(BlockHeader::new(ViewerRefNode::BASE, 0, 5))))]
+    pub base: BlockHeader,
+    pub node: RenderableNode,
+    pub unknown1: i16,
+    #[br(temp)]
+    #[bw(calc = names.len() as u32)]
+    num_names: u32,
+    #[br(parse_with = read_seven_bit_strings, args(num_names))]
+    #[bw(write_with = write_seven_bit_strings)]
+    #[cfg_attr(test, strategy(vec("[a-zA-Z_]{0,16}", 0..4)))]
+    pub names: Vec<String>,
+    #[br(count(ViewerRefNode::UNKNOWN_SIZE))]
+    #[bw(assert(unknown2.len() == ViewerRefNode::UNKNOWN_SIZE))]
+    #[cfg_attr(test, strategy(vec(any::<u8>(), ViewerRefNode::UNKNOWN_SIZE)))]
+    pub unknown2: Vec<u8>,
+}
+
+impl ViewerRefNode {
+    pub const BASE: &'static str = "cViewerRefNodeBase";
+    pub const UNKNOWN_SIZE: usize = 0xA0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+
+    test_parsing!(
+        [
+            0x0D, // name length
+            0x63, 0x52, 0x65, 0x73, 0x6F, 0x75, 0x72, 0x63, 0x65, 0x4E, 0x6F, 0x64,
+            0x65, // cResourceNode
+            0x33, 0xC9, 0x19, 0xE5, // block id
+            0x07, 0x00, 0x00, 0x00, // block version
+            0x00, // graph node
+            0x10, // name length
+            0x63, 0x4F, 0x62, 0x6A, 0x65, 0x63, 0x74, 0x47, 0x72, 0x61, 0x70, 0x68, 0x4E, 0x6F,
+            0x64, 0x65, // cObjectGraphNode
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x04, 0x00, 0x00, 0x00, // block version
+            0x00, 0x00, 0x00, 0x00, // number of extensions
+            0x01, 0x52, // R
+            0x01, // number of children
+            0x00, 0x00, // unknown
+            0x01, 0x00, 0x00, 0x00, // index
+            0x02, 0x00, 0x00, 0x00, // unknown 1
+            0x03, 0x00, 0x00, 0x00, // unknown 2
+        ],
+        ResourceNode {
+            block: BlockHeader::of::<ResourceNode>(7),
+            node: ResourceNodeKind::Graph {
+                graph: ObjectGraphNode::new("R".to_string()),
+            },
+            children: vec![ChildReference {
+                unknown: 0,
+                index: 1,
+            }],
+            unknown1: 2,
+            unknown2: 3,
+        },
+        ResourceNode,
+        cresourcenode
+    );
+
+    test_parsing!(
+        [
+            0x0E, // name length
+            0x63, 0x54, 0x72, 0x61, 0x6E, 0x73, 0x66, 0x6F, 0x72, 0x6D, 0x4E, 0x6F, 0x64,
+            0x65, // cTransformNode
+            0x62, 0x64, 0x24, 0x65, // block id
+            0x07, 0x00, 0x00, 0x00, // block version
+            0x14, // name length
+            0x63, 0x43, 0x6F, 0x6D, 0x70, 0x6F, 0x73, 0x69, 0x74, 0x69, 0x6F, 0x6E, 0x54, 0x72,
+            0x65, 0x65, 0x4E, 0x6F, 0x64, 0x65, // cCompositionTreeNode
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x0B, 0x00, 0x00, 0x00, // block version
+            0x10, // name length
+            0x63, 0x4F, 0x62, 0x6A, 0x65, 0x63, 0x74, 0x47, 0x72, 0x61, 0x70, 0x68, 0x4E, 0x6F,
+            0x64, 0x65, // cObjectGraphNode
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x04, 0x00, 0x00, 0x00, // block version
+            0x00, 0x00, 0x00, 0x00, // number of extensions
+            0x04, 0x72, 0x6F, 0x6F, 0x74, // root
+            0x00, 0x00, 0x00, 0x00, // number of children
+            0x00, 0x00, 0x80, 0x3F, // x
+            0x00, 0x00, 0x00, 0x00, // y
+            0x00, 0x00, 0x00, 0x00, // z
+            0x00, 0x00, 0x00, 0x00, // rotation x
+            0x00, 0x00, 0x00, 0x00, // rotation y
+            0x00, 0x00, 0x00, 0x00, // rotation z
+            0x00, 0x00, 0x80, 0x3F, // rotation w
+            0x00, 0x00, 0x00, 0x00, // joint
+        ],
+        TransformNode {
+            block: BlockHeader::of::<TransformNode>(7),
+            tree: CompositionTreeNode::new("root".to_string()),
+            children: vec![],
+            translation: [1.0, 0.0, 0.0],
+            rotation: [0.0, 0.0, 0.0, 1.0],
+            joint: 0,
+        },
+        TransformNode,
+        ctransformnode
+    );
+
+    test_parsing!(
+        [
+            0x0D, // name length
+            0x63, 0x4C, 0x69, 0x67, 0x68, 0x74, 0x52, 0x65, 0x66, 0x4E, 0x6F, 0x64,
+            0x65, // cLightRefNode
+            0x18, 0x20, 0x3D, 0x25, // block id
+            0x0A, 0x00, 0x00, 0x00, // block version
+            0x0F, // name length
+            0x63, 0x52, 0x65, 0x6E, 0x64, 0x65, 0x72, 0x61, 0x62, 0x6C, 0x65, 0x4E, 0x6F, 0x64,
+            0x65, // cRenderableNode
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x05, 0x00, 0x00, 0x00, // block version
+            0x0C, // name length
+            0x63, 0x42, 0x6F, 0x75, 0x6E, 0x64, 0x65, 0x64, 0x4E, 0x6F, 0x64,
+            0x65, // cBoundedNode
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x05, 0x00, 0x00, 0x00, // block version
+            0x0E, // name length
+            0x63, 0x54, 0x72, 0x61, 0x6E, 0x73, 0x66, 0x6F, 0x72, 0x6D, 0x4E, 0x6F, 0x64,
+            0x65, // cTransformNode
+            0x62, 0x64, 0x24, 0x65, // block id
+            0x07, 0x00, 0x00, 0x00, // block version
+            0x14, // name length
+            0x63, 0x43, 0x6F, 0x6D, 0x70, 0x6F, 0x73, 0x69, 0x74, 0x69, 0x6F, 0x6E, 0x54, 0x72,
+            0x65, 0x65, 0x4E, 0x6F, 0x64, 0x65, // cCompositionTreeNode
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x0B, 0x00, 0x00, 0x00, // block version
+            0x10, // name length
+            0x63, 0x4F, 0x62, 0x6A, 0x65, 0x63, 0x74, 0x47, 0x72, 0x61, 0x70, 0x68, 0x4E, 0x6F,
+            0x64, 0x65, // cObjectGraphNode
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x04, 0x00, 0x00, 0x00, // block version
+            0x00, 0x00, 0x00, 0x00, // number of extensions
+            0x05, 0x6C, 0x69, 0x67, 0x68, 0x74, // light
+            0x00, 0x00, 0x00, 0x00, // number of children
+            0x00, 0x00, 0x00, 0x00, // x
+            0x00, 0x00, 0x00, 0x00, // y
+            0x00, 0x00, 0x00, 0x00, // z
+            0x00, 0x00, 0x00, 0x00, // rotation x
+            0x00, 0x00, 0x00, 0x00, // rotation y
+            0x00, 0x00, 0x00, 0x00, // rotation z
+            0x00, 0x00, 0x80, 0x3F, // rotation w
+            0xFF, 0xFF, 0xFF, 0x7F, // joint
+            0x00, 0x00, // unknown 1
+            0x01, 0x00, 0x00, 0x00, // number of names
+            0x01, 0x4C, // L
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x01, // unknown 2
+        ],
+        LightRefNode {
+            block: BlockHeader::of::<LightRefNode>(10),
+            node: RenderableNode {
+                renderable: BlockHeader::new(RenderableNode::NAME, 0, 5),
+                bounded: BlockHeader::new(RenderableNode::BOUNDED, 0, 5),
+                transform: TransformNode {
+                    block: BlockHeader::of::<TransformNode>(7),
+                    tree: CompositionTreeNode::new("light".to_string()),
+                    children: vec![],
+                    translation: [0.0, 0.0, 0.0],
+                    rotation: [0.0, 0.0, 0.0, 1.0],
+                    joint: TransformNode::NOT_A_JOINT,
+                },
+            },
+            unknown1: 0,
+            names: vec!["L".to_string()],
+            unknown2: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+        },
+        LightRefNode,
+        clightrefnode
+    );
+
+    fn transform_node(name: &str, children: Vec<u32>, joint: u32) -> TransformNode {
+        TransformNode {
+            block: BlockHeader::of::<TransformNode>(7),
+            tree: CompositionTreeNode::new(name.to_string()),
+            children: children
+                .into_iter()
+                .map(|index| ChildReference { unknown: 0, index })
+                .collect(),
+            translation: [0.0, 1.0, 0.0],
+            rotation: [0.0, 0.0, 0.0, 1.0],
+            joint,
+        }
+    }
+
+    #[test]
+    fn skeleton() {
+        let cres = CreationResource {
+            collection: ResourceCollection::new(vec![
+                ResourceNode {
+                    block: BlockHeader::of::<ResourceNode>(7),
+                    node: ResourceNodeKind::Graph {
+                        graph: ObjectGraphNode::new("sim_cres".to_string()),
+                    },
+                    children: vec![ChildReference {
+                        unknown: 0,
+                        index: 1,
+                    }],
+                    unknown1: 0,
+                    unknown2: 0,
+                }
+                .into(),
+                transform_node("auskel", vec![2], TransformNode::NOT_A_JOINT).into(),
+                transform_node("root_trans", vec![], 0).into(),
+            ]),
+        };
+
+        assert_eq!(
+            cres.skeleton(),
+            vec![
+                Bone {
+                    name: "auskel".to_string(),
+                    parent: None,
+                    translation: [0.0, 1.0, 0.0],
+                    rotation: [0.0, 0.0, 0.0, 1.0],
+                    joint: None,
+                },
+                Bone {
+                    name: "root_trans".to_string(),
+                    parent: Some(0),
+                    translation: [0.0, 1.0, 0.0],
+                    rotation: [0.0, 0.0, 0.0, 1.0],
+                    joint: Some(0),
+                },
+            ]
+        );
+    }
+}
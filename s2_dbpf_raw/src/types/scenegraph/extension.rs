@@ -0,0 +1,249 @@
+////////////////////////////////////////////////////////////////////////////////
+// This Source Code Form is subject to the terms of the Mozilla Public         /
+// License, v. 2.0. If a copy of the MPL was not distributed with this         /
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
+////////////////////////////////////////////////////////////////////////////////
+
+//! The `cDataListExtension` block, a named tree of loosely typed values scenegraph nodes carry
+//! settings in.
+
+use crate::types::util::resource_collection::BlockHeader;
+use crate::types::util::strings::{read_seven_bit_string, write_seven_bit_string};
+use binrw::binrw;
+#[cfg(test)]
+use proptest::collection::vec;
+#[cfg(test)]
+use proptest::prelude::*;
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct DataListExtension {
+    #[cfg_attr(test, strategy(Just(BlockHeader::of::<DataListExtension>(1))))]
+    pub block: BlockHeader,
+    /// The embedded `cExtension` block
+    #[cfg_attr(
+        test,
+        strategy(Just(BlockHeader::new(DataListExtension::EXTENSION, 0, 3)))
+    )]
+    pub extension: BlockHeader,
+    pub value: ExtensionItem,
+}
+
+impl DataListExtension {
+    pub const EXTENSION: &'static str = "cExtension";
+
+    #[must_use]
+    pub fn new(value: ExtensionItem) -> Self {
+        Self {
+            block: BlockHeader::of::<Self>(1),
+            extension: BlockHeader::new(Self::EXTENSION, 0, 3),
+            value,
+        }
+    }
+
+    /// The item called `name` in the top level array.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&ExtensionItem> {
+        match &self.value {
+            ExtensionItem::Array { items, .. } => items.iter().find(|item| item.name() == name),
+            _ => None,
+        }
+    }
+}
+
+/// The `cBoneDataExtension` block, extra rest pose data attached to a joint.
+#[binrw]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct BoneDataExtension {
+    #[cfg_attr(test, strategy(Just(BlockHeader::of::<BoneDataExtension>(5))))]
+    pub block: BlockHeader,
+    /// The embedded `cExtension` block
+    #[cfg_attr(
+        test,
+        strategy(Just(BlockHeader::new(DataListExtension::EXTENSION, 0, 3)))
+    )]
+    pub extension: BlockHeader,
+    pub value: ExtensionItem,
+    pub unknown1: i32,
+    pub unknown2: i32,
+    #[cfg_attr(test, strategy(-100.0_f32..100.0))]
+    pub unknown3: f32,
+    /// A quaternion, x y z w
+    #[cfg_attr(test, strategy(proptest::array::uniform4(-1.0_f32..1.0)))]
+    pub rotation: [f32; 4],
+}
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub enum ExtensionItem {
+    #[brw(magic = 2u8)]
+    Int {
+        #[br(parse_with = read_seven_bit_string)]
+        #[bw(write_with = write_seven_bit_string)]
+        #[cfg_attr(test, strategy("[a-zA-Z]{0,16}"))]
+        name: String,
+        value: i32,
+    },
+    #[brw(magic = 3u8)]
+    Float {
+        #[br(parse_with = read_seven_bit_string)]
+        #[bw(write_with = write_seven_bit_string)]
+        #[cfg_attr(test, strategy("[a-zA-Z]{0,16}"))]
+        name: String,
+        #[cfg_attr(test, strategy(-100.0_f32..100.0))]
+        value: f32,
+    },
+    #[brw(magic = 5u8)]
+    Translation {
+        #[br(parse_with = read_seven_bit_string)]
+        #[bw(write_with = write_seven_bit_string)]
+        #[cfg_attr(test, strategy("[a-zA-Z]{0,16}"))]
+        name: String,
+        #[cfg_attr(test, strategy(proptest::array::uniform3(-100.0_f32..100.0)))]
+        value: [f32; 3],
+    },
+    #[brw(magic = 6u8)]
+    Text {
+        #[br(parse_with = read_seven_bit_string)]
+        #[bw(write_with = write_seven_bit_string)]
+        #[cfg_attr(test, strategy("[a-zA-Z]{0,16}"))]
+        name: String,
+        #[br(parse_with = read_seven_bit_string)]
+        #[bw(write_with = write_seven_bit_string)]
+        #[cfg_attr(test, strategy("[a-zA-Z]{0,16}"))]
+        value: String,
+    },
+    #[brw(magic = 7u8)]
+    Array {
+        #[br(parse_with = read_seven_bit_string)]
+        #[bw(write_with = write_seven_bit_string)]
+        #[cfg_attr(test, strategy("[a-zA-Z]{0,16}"))]
+        name: String,
+        #[br(temp)]
+        #[bw(calc = items.len() as u32)]
+        count: u32,
+        #[br(count(count as usize))]
+        #[cfg_attr(test, strategy(vec(any::<i32>().prop_map(|value| ExtensionItem::Int { name: String::new(), value }), 0..3)))]
+        items: Vec<ExtensionItem>,
+    },
+    #[brw(magic = 8u8)]
+    Rotation {
+        #[br(parse_with = read_seven_bit_string)]
+        #[bw(write_with = write_seven_bit_string)]
+        #[cfg_attr(test, strategy("[a-zA-Z]{0,16}"))]
+        name: String,
+        #[cfg_attr(test, strategy(proptest::array::uniform4(-1.0_f32..1.0)))]
+        value: [f32; 4],
+    },
+    #[brw(magic = 9u8)]
+    Binary {
+        #[br(parse_with = read_seven_bit_string)]
+        #[bw(write_with = write_seven_bit_string)]
+        #[cfg_attr(test, strategy("[a-zA-Z]{0,16}"))]
+        name: String,
+        #[br(temp)]
+        #[bw(calc = data.len() as u32)]
+        size: u32,
+        #[br(count(size as usize))]
+        #[cfg_attr(test, strategy(vec(any::<u8>(), 0..16)))]
+        data: Vec<u8>,
+    },
+}
+
+impl ExtensionItem {
+    #[must_use]
+    pub fn name(&self) -> &str {
+        match self {
+            ExtensionItem::Int { name, .. }
+            | ExtensionItem::Float { name, .. }
+            | ExtensionItem::Translation { name, .. }
+            | ExtensionItem::Text { name, .. }
+            | ExtensionItem::Array { name, .. }
+            | ExtensionItem::Rotation { name, .. }
+            | ExtensionItem::Binary { name, .. } => name,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+
+    test_parsing!(
+        [
+            0x12, // name length
+            0x63, 0x44, 0x61, 0x74, 0x61, 0x4C, 0x69, 0x73, 0x74, 0x45, 0x78, 0x74, 0x65, 0x6E,
+            0x73, 0x69, 0x6F, 0x6E, // cDataListExtension
+            0x56, 0x6D, 0x83, 0x6A, // block id
+            0x01, 0x00, 0x00, 0x00, // block version
+            0x0A, // name length
+            0x63, 0x45, 0x78, 0x74, 0x65, 0x6E, 0x73, 0x69, 0x6F, 0x6E, // cExtension
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x03, 0x00, 0x00, 0x00, // block version
+            0x07, // array
+            0x01, 0x45, // E
+            0x01, 0x00, 0x00, 0x00, // number of items
+            0x02, // int
+            0x01, 0x76, // v
+            0x05, 0x00, 0x00, 0x00, // value
+        ],
+        DataListExtension::new(ExtensionItem::Array {
+            name: "E".to_string(),
+            items: vec![ExtensionItem::Int {
+                name: "v".to_string(),
+                value: 5,
+            }],
+        }),
+        DataListExtension,
+        cdatalistextension
+    );
+
+    test_parsing!(
+        [
+            0x12, // name length
+            0x63, 0x42, 0x6F, 0x6E, 0x65, 0x44, 0x61, 0x74, 0x61, 0x45, 0x78, 0x74, 0x65, 0x6E,
+            0x73, 0x69, 0x6F, 0x6E, // cBoneDataExtension
+            0xC5, 0x5B, 0x07, 0xE9, // block id
+            0x05, 0x00, 0x00, 0x00, // block version
+            0x0A, // name length
+            0x63, 0x45, 0x78, 0x74, 0x65, 0x6E, 0x73, 0x69, 0x6F, 0x6E, // cExtension
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x03, 0x00, 0x00, 0x00, // block version
+            0x07, // array
+            0x00, // name length
+            0x00, 0x00, 0x00, 0x00, // number of items
+            0x01, 0x00, 0x00, 0x00, // unknown 1
+            0x02, 0x00, 0x00, 0x00, // unknown 2
+            0x00, 0x00, 0x80, 0x3F, // unknown 3
+            0x00, 0x00, 0x00, 0x00, // rotation x
+            0x00, 0x00, 0x00, 0x00, // rotation y
+            0x00, 0x00, 0x00, 0x00, // rotation z
+            0x00, 0x00, 0x80, 0x3F, // rotation w
+        ],
+        BoneDataExtension {
+            block: BlockHeader::of::<BoneDataExtension>(5),
+            extension: BlockHeader::new(DataListExtension::EXTENSION, 0, 3),
+            value: ExtensionItem::Array {
+                name: String::new(),
+                items: vec![],
+            },
+            unknown1: 1,
+            unknown2: 2,
+            unknown3: 1.0,
+            rotation: [0.0, 0.0, 0.0, 1.0],
+        },
+        BoneDataExtension,
+        cbonedataextension
+    );
+}
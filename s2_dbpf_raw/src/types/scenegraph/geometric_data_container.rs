@@ -4,4 +4,866 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct GeometricDataContainer {}
+//! Meshes (GMDC): vertex data split into elements, linkages tying elements together, groups of
+//! faces drawing a linkage, and the joints the mesh can be skinned to.
+
+use std::io::{Read, Seek, Write};
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId};
+use crate::types::util::parser_args::ParserArgs;
+use crate::types::util::resource_collection::{BlockHeader, ResourceCollection, SgResource};
+use crate::types::util::strings::{read_seven_bit_string, write_seven_bit_string};
+use binrw::{binrw, BinRead, BinResult, BinWrite, ReadOptions, WriteOptions};
+use derive_more::{Deref, DerefMut};
+#[cfg(test)]
+use proptest::collection::vec;
+#[cfg(test)]
+use proptest::prelude::*;
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type GMDC = GeometricDataContainer;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Deref, DerefMut)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(args: ParserArgs))]
+pub struct GeometricDataContainer {
+    #[br(args_raw = args)]
+    pub collection: ResourceCollection,
+}
+
+impl DbpfEntry for GeometricDataContainer {
+    fn id(&self) -> DbpfId {
+        DbpfId::GeometricDataContainer
+    }
+
+    fn name(&self) -> Option<String> {
+        self.geometry()
+            .map(|geometry| geometry.resource.file_name.clone())
+    }
+}
+
+impl GeometricDataContainer {
+    #[must_use]
+    pub fn geometry(&self) -> Option<&GeometryData> {
+        self.block()
+    }
+
+    pub fn geometry_mut(&mut self) -> Option<&mut GeometryData> {
+        self.block_mut()
+    }
+}
+
+/// The `cGeometryDataContainer` block. Version 4 stores indices as 16 bits, older versions as
+/// 32 bits; writing an index past 65535 in version 4 fails.
+#[binrw]
+#[derive(Debug, Clone, PartialEq)]
+#[brw(little)]
+pub struct GeometryData {
+    pub block: BlockHeader,
+    pub resource: SgResource,
+    #[br(temp)]
+    #[bw(calc = elements.len() as u32)]
+    num_elements: u32,
+    #[br(args { count: num_elements as usize, inner: (block.version,) })]
+    #[bw(args(block.version))]
+    pub elements: Vec<Element>,
+    #[br(temp)]
+    #[bw(calc = linkages.len() as u32)]
+    num_linkages: u32,
+    #[br(args { count: num_linkages as usize, inner: (block.version,) })]
+    #[bw(args(block.version))]
+    pub linkages: Vec<Linkage>,
+    #[br(temp)]
+    #[bw(calc = groups.len() as u32)]
+    num_groups: u32,
+    #[br(args { count: num_groups as usize, inner: (block.version,) })]
+    #[bw(args(block.version))]
+    pub groups: Vec<Group>,
+    #[brw(args(block.version))]
+    pub model: Model,
+    #[br(temp)]
+    #[bw(calc = joints.len() as u32)]
+    num_joints: u32,
+    /// The part of the mesh each joint moves, used for picking and bounds
+    #[br(args { count: num_joints as usize, inner: (block.version,) })]
+    #[bw(args(block.version))]
+    pub joints: Vec<BoundingMesh>,
+}
+
+impl GeometryData {
+    /// The vertex data and faces of a group, with aliases resolved and blend indices mapped to
+    /// the model's joints. `None` if the group or its linkage doesn't exist.
+    #[must_use]
+    pub fn mesh(&self, group: usize) -> Option<Mesh> {
+        let group = self.groups.get(group)?;
+        let linkage = self.linkages.get(group.linkage as usize)?;
+        let attribute = |identity: u32, aliases: &[u32]| -> Vec<Vec<f32>> {
+            let vectors = linkage
+                .elements
+                .iter()
+                .filter_map(|&index| self.elements.get(index as usize))
+                .find(|element| element.identity == identity)
+                .map(Element::vectors)
+                .unwrap_or_default();
+            if aliases.is_empty() {
+                vectors
+            } else {
+                aliases
+                    .iter()
+                    .filter_map(|&alias| vectors.get(alias as usize).cloned())
+                    .collect()
+            }
+        };
+
+        let blend_indices = attribute(Element::BLEND_INDICES, &linkage.vertex_aliases);
+        let blend_weights = attribute(Element::BLEND_WEIGHTS, &linkage.vertex_aliases);
+        let mut joints = Vec::with_capacity(blend_indices.len());
+        let mut weights = Vec::with_capacity(blend_indices.len());
+        for (index, packed) in blend_indices.iter().enumerate() {
+            let packed = packed.first().map_or(u32::MAX, |packed| packed.to_bits());
+            let stored = blend_weights.get(index).cloned().unwrap_or_default();
+            let mut vertex_joints = [0; 4];
+            let mut vertex_weights = [0.0; 4];
+            let mut remaining = 1.0;
+            for (slot, (joint, weight)) in vertex_joints
+                .iter_mut()
+                .zip(vertex_weights.iter_mut())
+                .enumerate()
+            {
+                let local = (packed >> (slot * 8)) & 0xFF;
+                let global = match group.joints.get(local as usize) {
+                    Some(&global) if local != 0xFF => global,
+                    _ => continue,
+                };
+                *joint = global;
+                // The last weight is left out, as it's whatever the others don't add up to
+                *weight = stored.get(slot).copied().unwrap_or(remaining);
+                remaining -= *weight;
+            }
+            joints.push(vertex_joints);
+            weights.push(vertex_weights);
+        }
+
+        Some(Mesh {
+            name: group.name.clone(),
+            positions: attribute(Element::VERTEX, &linkage.vertex_aliases)
+                .iter()
+                .map(|vector| to_array(vector))
+                .collect(),
+            normals: attribute(Element::NORMAL, &linkage.normal_aliases)
+                .iter()
+                .map(|vector| to_array(vector))
+                .collect(),
+            uvs: attribute(Element::UV, &linkage.uv_aliases)
+                .iter()
+                .map(|vector| to_array(vector))
+                .collect(),
+            joints,
+            weights,
+            indices: group.faces.clone(),
+        })
+    }
+
+    /// Every group as a mesh.
+    #[must_use]
+    pub fn meshes(&self) -> Vec<Mesh> {
+        (0..self.groups.len())
+            .filter_map(|group| self.mesh(group))
+            .collect()
+    }
+
+    /// Replaces the geometry of a group. The elements its linkage references are rewritten in
+    /// place, elements the mesh has no data for (such as morph deltas) are unlinked, and the
+    /// group's joint list is rebuilt from the joints the mesh uses. `None` if the group or its
+    /// linkage doesn't exist, or if the mesh uses more joints than the 255 a group can index.
+    pub fn set_mesh(&mut self, group: usize, mesh: &Mesh) -> Option<()> {
+        let linkage_index = self.groups.get(group)?.linkage as usize;
+        let linkage = self.linkages.get(linkage_index)?.clone();
+
+        let mut used_joints: Vec<u32> = vec![];
+        let packed_joints = mesh
+            .joints
+            .iter()
+            .zip(mesh.weights.iter().chain(std::iter::repeat(&[0.0; 4])))
+            .map(|(joints, weights)| {
+                let packed = joints.iter().zip(weights).enumerate().fold(
+                    0,
+                    |packed, (slot, (&joint, &weight))| {
+                        let local = if weight > 0.0 {
+                            let local = used_joints
+                                .iter()
+                                .position(|&used| used == joint)
+                                .unwrap_or_else(|| {
+                                    used_joints.push(joint);
+                                    used_joints.len() - 1
+                                });
+                            local as u32
+                        } else {
+                            0xFF
+                        };
+                        packed | (local << (slot * 8))
+                    },
+                );
+                vec![f32::from_bits(packed)]
+            })
+            .collect::<Vec<_>>();
+        // 0xFF marks an unused slot, so a group can only index 255 joints
+        if used_joints.len() > 0xFF {
+            return None;
+        }
+
+        let mut kept = vec![];
+        for &index in &linkage.elements {
+            let element = match self.elements.get_mut(index as usize) {
+                Some(element) => element,
+                None => continue,
+            };
+            let components = element.block_format.components();
+            let vectors: Option<Vec<Vec<f32>>> = match element.identity {
+                Element::VERTEX => Some(mesh.positions.iter().map(|x| x.to_vec()).collect()),
+                Element::NORMAL if !mesh.normals.is_empty() => {
+                    Some(mesh.normals.iter().map(|x| x.to_vec()).collect())
+                }
+                Element::UV if !mesh.uvs.is_empty() => {
+                    Some(mesh.uvs.iter().map(|x| x.to_vec()).collect())
+                }
+                Element::BLEND_INDICES if !mesh.joints.is_empty() => Some(packed_joints.clone()),
+                Element::BLEND_WEIGHTS if !mesh.weights.is_empty() => Some(
+                    mesh.weights
+                        .iter()
+                        .map(|weights| weights[..components.min(4)].to_vec())
+                        .collect(),
+                ),
+                _ => None,
+            };
+            if let Some(vectors) = vectors {
+                element.set_vectors(&vectors);
+                kept.push(index);
+            }
+        }
+
+        let linkage = &mut self.linkages[linkage_index];
+        linkage.active_elements = kept.len() as u32;
+        linkage.elements = kept;
+        linkage.referenced_size = mesh.positions.len() as u32;
+        linkage.vertex_aliases.clear();
+        linkage.normal_aliases.clear();
+        linkage.uv_aliases.clear();
+
+        let group = &mut self.groups[group];
+        group.name.clone_from(&mesh.name);
+        group.faces.clone_from(&mesh.indices);
+        group.joints = used_joints;
+        Some(())
+    }
+
+    /// Replaces the geometry of a group with a mesh from another tool. If the mesh isn't skinned,
+    /// each vertex is bound to the joints of the closest vertex the group had before, so edited
+    /// meshes keep following the skeleton. `None` when [`Self::set_mesh`] is.
+    pub fn import_mesh(&mut self, group: usize, mut mesh: Mesh) -> Option<()> {
+        if mesh.joints.is_empty() {
+            mesh.transfer_skinning(&self.mesh(group)?);
+        }
+        self.set_mesh(group, &mesh)
+    }
+}
+
+#[cfg(test)]
+prop_compose! {
+    fn geometry_data_mapper()(
+        version in 1_u32..5
+    )(
+        resource in any::<SgResource>(),
+        elements in vec(any::<Element>(), 0..3),
+        linkages in vec(any::<Linkage>(), 0..3),
+        groups in vec(any::<Group>(), 0..3),
+        model in any::<Model>(),
+        joints in vec(any::<BoundingMesh>(), 0..3),
+        version in Just(version),
+    ) -> GeometryData {
+        GeometryData {
+            block: BlockHeader::of::<GeometryData>(version),
+            resource,
+            elements,
+            linkages,
+            groups,
+            model,
+            joints,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Arbitrary for GeometryData {
+    type Parameters = ();
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        geometry_data_mapper().boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
+
+/// One attribute of the vertices, such as positions or UVs.
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little, import(version: u32))]
+pub struct Element {
+    /// Tells apart multiple elements with the same identity, such as UV sets
+    pub number: u32,
+    pub identity: u32,
+    pub repeat: u32,
+    pub block_format: BlockFormat,
+    pub set_format: u32,
+    #[br(temp)]
+    #[bw(calc = values.len() as u32 * 4)]
+    data_size: u32,
+    /// The raw 32 bit values, floats unless the format is [`BlockFormat::OneDword`]
+    #[br(count(data_size as usize / 4))]
+    #[cfg_attr(test, strategy(vec(any::<u32>(), 0..12)))]
+    pub values: Vec<u32>,
+    #[br(parse_with = read_indices, args(version))]
+    #[bw(write_with = write_indices, args(version))]
+    #[cfg_attr(test, strategy(indices()))]
+    pub references: Vec<u32>,
+}
+
+impl Element {
+    pub const BLEND_INDICES: u32 = 0x1C4A_FC56;
+    pub const BLEND_WEIGHTS: u32 = 0x5C4A_FC5C;
+    pub const TARGET_INDICES: u32 = 0x7C4D_EE82;
+    pub const NORMAL_MORPH_DELTAS: u32 = 0xCB6F_3A6A;
+    pub const COLOR: u32 = 0xCB72_06A1;
+    pub const COLOR_DELTAS: u32 = 0xEB72_0693;
+    pub const NORMAL: u32 = 0x3B83_078B;
+    pub const VERTEX: u32 = 0x5B83_0781;
+    pub const UV: u32 = 0xBB83_07AB;
+    pub const UV_DELTAS: u32 = 0xDB83_0795;
+    pub const BINORMAL: u32 = 0x9BB3_8AFB;
+    pub const BONE_WEIGHTS: u32 = 0x3BD7_0105;
+    pub const BONE_ASSIGNMENTS: u32 = 0xFBD7_0111;
+    pub const BUMP_MAP_NORMAL: u32 = 0x89D9_2BA0;
+    pub const BUMP_MAP_NORMAL_DELTAS: u32 = 0x69D9_2B93;
+    pub const MORPH_VERTEX_DELTAS: u32 = 0x5CF2_CFE1;
+    pub const MORPH_VERTEX_MAP: u32 = 0xDCF2_CFDC;
+
+    /// The values grouped into one vector per vertex.
+    #[must_use]
+    pub fn vectors(&self) -> Vec<Vec<f32>> {
+        self.values
+            .chunks(self.block_format.components())
+            .map(|chunk| chunk.iter().map(|&value| f32::from_bits(value)).collect())
+            .collect()
+    }
+
+    pub fn set_vectors(&mut self, vectors: &[Vec<f32>]) {
+        let components = self.block_format.components();
+        self.values = vectors
+            .iter()
+            .flat_map(|vector| {
+                (0..components).map(move |index| vector.get(index).copied().unwrap_or(0.0))
+            })
+            .map(f32::to_bits)
+            .collect();
+    }
+}
+
+#[binrw]
+#[brw(little, repr = u32)]
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+pub enum BlockFormat {
+    OneFloat = 0,
+    TwoFloat = 1,
+    ThreeFloat = 2,
+    /// Four bytes packed into a single value
+    OneDword = 4,
+}
+
+impl BlockFormat {
+    #[must_use]
+    pub fn components(self) -> usize {
+        match self {
+            BlockFormat::OneFloat | BlockFormat::OneDword => 1,
+            BlockFormat::TwoFloat => 2,
+            BlockFormat::ThreeFloat => 3,
+        }
+    }
+}
+
+/// The elements one group draws from. Aliases, when present, map each vertex to the element
+/// value it uses.
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little, import(version: u32))]
+pub struct Linkage {
+    #[br(parse_with = read_indices, args(version))]
+    #[bw(write_with = write_indices, args(version))]
+    #[cfg_attr(test, strategy(indices()))]
+    pub elements: Vec<u32>,
+    pub referenced_size: u32,
+    pub active_elements: u32,
+    #[br(parse_with = read_indices, args(version))]
+    #[bw(write_with = write_indices, args(version))]
+    #[cfg_attr(test, strategy(indices()))]
+    pub vertex_aliases: Vec<u32>,
+    #[br(parse_with = read_indices, args(version))]
+    #[bw(write_with = write_indices, args(version))]
+    #[cfg_attr(test, strategy(indices()))]
+    pub normal_aliases: Vec<u32>,
+    #[br(parse_with = read_indices, args(version))]
+    #[bw(write_with = write_indices, args(version))]
+    #[cfg_attr(test, strategy(indices()))]
+    pub uv_aliases: Vec<u32>,
+}
+
+/// A named set of faces, the unit the game hides and shows parts of a mesh by.
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little, import(version: u32))]
+pub struct Group {
+    pub primitive_type: u32,
+    pub linkage: u32,
+    #[br(parse_with = read_seven_bit_string)]
+    #[bw(write_with = write_seven_bit_string)]
+    #[cfg_attr(test, strategy("[a-zA-Z0-9_]{0,16}"))]
+    pub name: String,
+    #[br(parse_with = read_indices, args(version))]
+    #[bw(write_with = write_indices, args(version))]
+    #[cfg_attr(test, strategy(indices()))]
+    pub faces: Vec<u32>,
+    pub opacity: u32,
+    /// The model joints the blend indices of this group's vertices refer to
+    #[br(parse_with = read_indices, args(version))]
+    #[bw(write_with = write_indices, args(version))]
+    #[cfg_attr(test, strategy(indices()))]
+    pub joints: Vec<u32>,
+}
+
+impl Group {
+    pub const TRIANGLES: u32 = 2;
+}
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little, import(version: u32))]
+pub struct Model {
+    #[br(temp)]
+    #[bw(calc = transforms.len() as u32)]
+    num_transforms: u32,
+    /// The rest pose of every joint
+    #[br(count(num_transforms as usize))]
+    #[cfg_attr(test, strategy(vec(any::<Transform>(), 0..3)))]
+    pub transforms: Vec<Transform>,
+    #[br(temp)]
+    #[bw(calc = blend_groups.len() as u32)]
+    num_blend_groups: u32,
+    #[br(count(num_blend_groups as usize))]
+    #[cfg_attr(test, strategy(vec(any::<BlendGroup>(), 0..3)))]
+    pub blend_groups: Vec<BlendGroup>,
+    #[brw(args(version))]
+    pub bounding_mesh: BoundingMesh,
+}
+
+#[binrw]
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct Transform {
+    /// A quaternion, x y z w
+    #[cfg_attr(test, strategy(vector::<4>()))]
+    pub rotation: [f32; 4],
+    #[cfg_attr(test, strategy(vector::<3>()))]
+    pub translation: [f32; 3],
+}
+
+/// Pairs a morph target with the group it applies to.
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct BlendGroup {
+    #[br(parse_with = read_seven_bit_string)]
+    #[bw(write_with = write_seven_bit_string)]
+    #[cfg_attr(test, strategy("[a-zA-Z0-9_]{0,16}"))]
+    pub group: String,
+    #[br(parse_with = read_seven_bit_string)]
+    #[bw(write_with = write_seven_bit_string)]
+    #[cfg_attr(test, strategy("[a-zA-Z0-9_]{0,16}"))]
+    pub element: String,
+}
+
+/// A simplified triangle mesh. Faces are only stored when there are vertices.
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little, import(version: u32))]
+pub struct BoundingMesh {
+    #[br(temp)]
+    #[bw(calc = vertices.len() as u32)]
+    num_vertices: u32,
+    #[br(temp, if(num_vertices > 0))]
+    #[bw(calc = (!vertices.is_empty()).then(|| faces.len() as u32))]
+    num_faces: Option<u32>,
+    #[br(count(num_vertices as usize))]
+    #[cfg_attr(test, strategy(vec(vector::<3>(), 0..3)))]
+    pub vertices: Vec<[f32; 3]>,
+    #[br(parse_with = read_index_values, args(version, num_faces.unwrap_or(0)))]
+    #[bw(write_with = write_index_values, args(version))]
+    #[cfg_attr(test, strategy(indices().prop_map(move |faces| if #vertices.is_empty() { vec![] } else { faces })))]
+    pub faces: Vec<u32>,
+}
+
+/// The vertex data of a group in a form other tools understand.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Mesh {
+    pub name: String,
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub uvs: Vec<[f32; 2]>,
+    /// Up to four joints per vertex, as indices into the model's joints
+    pub joints: Vec<[u32; 4]>,
+    pub weights: Vec<[f32; 4]>,
+    /// Triangles, three vertex indices each
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    /// Copies the joints and weights of the closest vertex in `source` to every vertex. Does
+    /// nothing if `source` isn't skinned.
+    pub fn transfer_skinning(&mut self, source: &Mesh) {
+        if source.joints.is_empty() {
+            return;
+        }
+        let (joints, weights) = self
+            .positions
+            .iter()
+            .map(|position| {
+                let closest = source
+                    .positions
+                    .iter()
+                    .map(|other| {
+                        position
+                            .iter()
+                            .zip(other)
+                            .map(|(a, b)| (a - b) * (a - b))
+                            .sum::<f32>()
+                    })
+                    .enumerate()
+                    .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+                    .map_or(0, |(index, _)| index);
+                (
+                    source.joints.get(closest).copied().unwrap_or_default(),
+                    source.weights.get(closest).copied().unwrap_or_default(),
+                )
+            })
+            .unzip();
+        self.joints = joints;
+        self.weights = weights;
+    }
+}
+
+fn to_array<const N: usize>(vector: &[f32]) -> [f32; N] {
+    let mut array = [0.0; N];
+    for (value, &component) in array.iter_mut().zip(vector) {
+        *value = component;
+    }
+    array
+}
+
+fn read_index_values<R: Read + Seek>(
+    reader: &mut R,
+    options: &ReadOptions,
+    (version, count): (u32, u32),
+) -> BinResult<Vec<u32>> {
+    (0..count)
+        .map(|_| {
+            if version >= 4 {
+                u16::read_options(reader, options, ()).map(u32::from)
+            } else {
+                u32::read_options(reader, options, ())
+            }
+        })
+        .collect()
+}
+
+fn read_indices<R: Read + Seek>(
+    reader: &mut R,
+    options: &ReadOptions,
+    (version,): (u32,),
+) -> BinResult<Vec<u32>> {
+    let count = u32::read_options(reader, options, ())?;
+    read_index_values(reader, options, (version, count))
+}
+
+// Signature is dictated by binrw's write_with
+#[allow(clippy::ptr_arg, clippy::trivially_copy_pass_by_ref)]
+fn write_index_values<W: Write + Seek>(
+    indices: &Vec<u32>,
+    writer: &mut W,
+    options: &WriteOptions,
+    (version,): (u32,),
+) -> BinResult<()> {
+    for &index in indices {
+        if version >= 4 {
+            let short = match u16::try_from(index) {
+                Ok(short) => short,
+                Err(_) => {
+                    return Err(binrw::Error::AssertFail {
+                        pos: writer.stream_position()?,
+                        message: format!(
+                            "Index {} doesn't fit in the 16 bits version 4 stores",
+                            index
+                        ),
+                    })
+                }
+            };
+            short.write_options(writer, options, ())?;
+        } else {
+            index.write_options(writer, options, ())?;
+        }
+    }
+    Ok(())
+}
+
+// Signature is dictated by binrw's write_with
+#[allow(clippy::ptr_arg, clippy::trivially_copy_pass_by_ref)]
+fn write_indices<W: Write + Seek>(
+    indices: &Vec<u32>,
+    writer: &mut W,
+    options: &WriteOptions,
+    (version,): (u32,),
+) -> BinResult<()> {
+    (indices.len() as u32).write_options(writer, options, ())?;
+    write_index_values(indices, writer, options, (version,))
+}
+
+#[cfg(test)]
+fn indices() -> impl Strategy<Value = Vec<u32>> {
+    vec(any::<u16>().prop_map(u32::from), 0..6)
+}
+
+#[cfg(test)]
+fn vector<const N: usize>() -> impl Strategy<Value = [f32; N]> {
+    vec(-100.0_f32..100.0, N).prop_map(|vector| to_array(&vector))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+
+    test_parsing!(
+        [
+            0x00, 0x00, 0x00, 0x00, // number of links
+            0x01, 0x00, 0x00, 0x00, // number of blocks
+            0x87, 0x86, 0x4F, 0xAC, // block id
+            0x16, // name length
+            0x63, 0x47, 0x65, 0x6F, 0x6D, 0x65, 0x74, 0x72, 0x79, 0x44, 0x61, 0x74, 0x61, 0x43,
+            0x6F, 0x6E, 0x74, 0x61, 0x69, 0x6E, 0x65, 0x72, // cGeometryDataContainer
+            0x87, 0x86, 0x4F, 0xAC, // block id
+            0x04, 0x00, 0x00, 0x00, // block version
+            0x0B, // name length
+            0x63, 0x53, 0x47, 0x52, 0x65, 0x73, 0x6F, 0x75, 0x72, 0x63, 0x65, // cSGResource
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x02, 0x00, 0x00, 0x00, // block version
+            0x01, 0x47, // G
+            0x01, 0x00, 0x00, 0x00, // number of elements
+            0x00, 0x00, 0x00, 0x00, // number
+            0x81, 0x07, 0x83, 0x5B, // identity
+            0x01, 0x00, 0x00, 0x00, // repeat
+            0x02, 0x00, 0x00, 0x00, // block format
+            0x00, 0x00, 0x00, 0x00, // set format
+            0x0C, 0x00, 0x00, 0x00, // data size
+            0x00, 0x00, 0x80, 0x3F, // x
+            0x00, 0x00, 0x00, 0x40, // y
+            0x00, 0x00, 0x00, 0x00, // z
+            0x00, 0x00, 0x00, 0x00, // number of references
+            0x01, 0x00, 0x00, 0x00, // number of linkages
+            0x01, 0x00, 0x00, 0x00, // number of elements
+            0x00, 0x00, // element
+            0x01, 0x00, 0x00, 0x00, // referenced size
+            0x01, 0x00, 0x00, 0x00, // active elements
+            0x00, 0x00, 0x00, 0x00, // number of vertex aliases
+            0x00, 0x00, 0x00, 0x00, // number of normal aliases
+            0x00, 0x00, 0x00, 0x00, // number of uv aliases
+            0x01, 0x00, 0x00, 0x00, // number of groups
+            0x02, 0x00, 0x00, 0x00, // primitive type
+            0x00, 0x00, 0x00, 0x00, // linkage
+            0x04, 0x62, 0x6F, 0x64, 0x79, // body
+            0x03, 0x00, 0x00, 0x00, // number of faces
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // faces
+            0xFF, 0xFF, 0xFF, 0xFF, // opacity
+            0x00, 0x00, 0x00, 0x00, // number of joints
+            0x00, 0x00, 0x00, 0x00, // number of transforms
+            0x00, 0x00, 0x00, 0x00, // number of blend groups
+            0x00, 0x00, 0x00, 0x00, // number of bounding mesh vertices
+            0x00, 0x00, 0x00, 0x00, // number of joints
+        ],
+        GeometricDataContainer {
+            collection: ResourceCollection::new(vec![GeometryData {
+                block: BlockHeader::of::<GeometryData>(4),
+                resource: SgResource::new("G".to_string()),
+                elements: vec![Element {
+                    number: 0,
+                    identity: Element::VERTEX,
+                    repeat: 1,
+                    block_format: BlockFormat::ThreeFloat,
+                    set_format: 0,
+                    values: vec![0x3F80_0000, 0x4000_0000, 0],
+                    references: vec![],
+                }],
+                linkages: vec![Linkage {
+                    elements: vec![0],
+                    referenced_size: 1,
+                    active_elements: 1,
+                    ..Linkage::default()
+                }],
+                groups: vec![Group {
+                    primitive_type: Group::TRIANGLES,
+                    linkage: 0,
+                    name: "body".to_string(),
+                    faces: vec![0, 0, 0],
+                    opacity: u32::MAX,
+                    joints: vec![],
+                }],
+                model: Model {
+                    transforms: vec![],
+                    blend_groups: vec![],
+                    bounding_mesh: BoundingMesh::default(),
+                },
+                joints: vec![],
+            }
+            .into()]),
+        },
+        GeometricDataContainer,
+        gmdc
+    );
+
+    fn element(identity: u32, block_format: BlockFormat) -> Element {
+        Element {
+            number: 0,
+            identity,
+            repeat: 0,
+            block_format,
+            set_format: 0,
+            values: vec![],
+            references: vec![],
+        }
+    }
+
+    fn skinned_geometry() -> GeometryData {
+        GeometryData {
+            block: BlockHeader::of::<GeometryData>(4),
+            resource: SgResource::new("G".to_string()),
+            elements: vec![
+                element(Element::VERTEX, BlockFormat::ThreeFloat),
+                element(Element::UV, BlockFormat::TwoFloat),
+                element(Element::BLEND_INDICES, BlockFormat::OneDword),
+                element(Element::BLEND_WEIGHTS, BlockFormat::OneFloat),
+                element(Element::MORPH_VERTEX_DELTAS, BlockFormat::ThreeFloat),
+            ],
+            linkages: vec![Linkage {
+                elements: vec![0, 1, 2, 3, 4],
+                ..Linkage::default()
+            }],
+            groups: vec![Group {
+                primitive_type: Group::TRIANGLES,
+                linkage: 0,
+                name: String::new(),
+                faces: vec![],
+                opacity: u32::MAX,
+                joints: vec![],
+            }],
+            model: Model {
+                transforms: vec![],
+                blend_groups: vec![],
+                bounding_mesh: BoundingMesh::default(),
+            },
+            joints: vec![],
+        }
+    }
+
+    #[test]
+    fn mesh_round_trip() {
+        let mut geometry = skinned_geometry();
+        let mesh = Mesh {
+            name: "body".to_string(),
+            positions: vec![[0.0, 1.0, 2.0], [3.0, 4.0, 5.0], [6.0, 7.0, 8.0]],
+            normals: vec![],
+            uvs: vec![[0.0, 0.5], [1.0, 0.5], [0.5, 1.0]],
+            joints: vec![[7, 0, 0, 0], [7, 9, 0, 0], [9, 0, 0, 0]],
+            weights: vec![
+                [1.0, 0.0, 0.0, 0.0],
+                [0.25, 0.75, 0.0, 0.0],
+                [1.0, 0.0, 0.0, 0.0],
+            ],
+            indices: vec![0, 1, 2],
+        };
+
+        geometry.set_mesh(0, &mesh).unwrap();
+
+        assert_eq!(geometry.groups[0].joints, vec![7, 9]);
+        assert_eq!(geometry.linkages[0].elements, vec![0, 1, 2, 3]);
+        assert_eq!(geometry.mesh(0), Some(mesh));
+        assert_eq!(geometry.mesh(1), None);
+    }
+
+    #[test]
+    fn set_mesh_refuses_more_than_255_joints() {
+        let mut geometry = skinned_geometry();
+        let mut mesh = Mesh {
+            positions: vec![[0.0; 3]; 256],
+            joints: (0..256).map(|joint| [joint, 0, 0, 0]).collect(),
+            weights: vec![[1.0, 0.0, 0.0, 0.0]; 256],
+            ..Mesh::default()
+        };
+
+        assert_eq!(geometry.set_mesh(0, &mesh), None);
+        assert!(geometry.groups[0].joints.is_empty());
+
+        mesh.joints[255] = [254, 0, 0, 0];
+        geometry.set_mesh(0, &mesh).unwrap();
+
+        assert_eq!(geometry.groups[0].joints.len(), 255);
+        assert_eq!(geometry.mesh(0).unwrap().joints[254], [254, 0, 0, 0]);
+    }
+
+    #[test]
+    fn transfer_skinning() {
+        let source = Mesh {
+            positions: vec![[0.0, 0.0, 0.0], [10.0, 0.0, 0.0]],
+            joints: vec![[1, 0, 0, 0], [2, 0, 0, 0]],
+            weights: vec![[1.0, 0.0, 0.0, 0.0]; 2],
+            ..Mesh::default()
+        };
+        let mut mesh = Mesh {
+            positions: vec![[9.0, 1.0, 0.0], [1.0, -1.0, 0.0], [4.0, 0.0, 0.0]],
+            ..Mesh::default()
+        };
+
+        mesh.transfer_skinning(&source);
+
+        assert_eq!(mesh.joints, vec![[2, 0, 0, 0], [1, 0, 0, 0], [1, 0, 0, 0]]);
+        assert_eq!(mesh.weights, vec![[1.0, 0.0, 0.0, 0.0]; 3]);
+    }
+
+    #[test]
+    fn version_4_refuses_indices_past_16_bits() {
+        let mut cursor = Cursor::new(vec![]);
+        let options = WriteOptions::new(binrw::Endian::Little);
+
+        assert!(write_index_values(&vec![0x1_0000], &mut cursor, &options, (4,)).is_err());
+        assert!(write_index_values(&vec![0x1_0000], &mut cursor, &options, (3,)).is_ok());
+    }
+}
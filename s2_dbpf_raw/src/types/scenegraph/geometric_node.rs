@@ -4,4 +4,145 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct GeometricNode {}
+//! Geometry nodes (GMND), which place a mesh (GMDC, linked from the collection) in the
+//! scenegraph.
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId};
+#[cfg(test)]
+use crate::types::scenegraph::extension::DataListExtension;
+use crate::types::util::parser_args::ParserArgs;
+use crate::types::util::resource_collection::{
+    read_embedded_blocks, write_embedded_blocks, Block, BlockHeader, ObjectGraphNode,
+    ResourceCollection, SgResource,
+};
+use binrw::binrw;
+use derive_more::{Deref, DerefMut};
+#[cfg(test)]
+use proptest::collection::vec;
+#[cfg(test)]
+use proptest::prelude::*;
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type GMND = GeometricNode;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Deref, DerefMut)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(args: ParserArgs))]
+pub struct GeometricNode {
+    #[br(args_raw = args)]
+    pub collection: ResourceCollection,
+}
+
+impl DbpfEntry for GeometricNode {
+    fn id(&self) -> DbpfId {
+        DbpfId::GeometricNode
+    }
+
+    fn name(&self) -> Option<String> {
+        self.geometry_node()
+            .map(|node| node.resource.file_name.clone())
+    }
+}
+
+impl GeometricNode {
+    #[must_use]
+    pub fn geometry_node(&self) -> Option<&GeometryNode> {
+        self.block()
+    }
+
+    pub fn geometry_node_mut(&mut self) -> Option<&mut GeometryNode> {
+        self.block_mut()
+    }
+}
+
+/// The `cGeometryNode` block.
+#[binrw]
+#[derive(Debug, Clone, PartialEq)]
+#[brw(little)]
+pub struct GeometryNode {
+    pub block: BlockHeader,
+    pub graph: ObjectGraphNode,
+    /// Only stored in version 11
+    #[br(if(block.version == 11))]
+    pub unknown: Option<(u16, u16, u8)>,
+    /// Extensions such as `cDataListExtension`, each prefixed with its id
+    #[br(parse_with = read_embedded_blocks)]
+    #[bw(write_with = write_embedded_blocks)]
+    pub blocks: Vec<Block>,
+    pub resource: SgResource,
+}
+
+#[cfg(test)]
+prop_compose! {
+    fn geometry_node_mapper()(
+        version in 11_u32..13
+    )(
+        graph in any::<ObjectGraphNode>(),
+        unknown in any::<(u16, u16, u8)>(),
+        blocks in vec(any::<DataListExtension>().prop_map(Block::from), 0..2),
+        resource in any::<SgResource>(),
+        version in Just(version),
+    ) -> GeometryNode {
+        GeometryNode {
+            block: BlockHeader::of::<GeometryNode>(version),
+            graph,
+            unknown: (version == 11).then(|| unknown),
+            blocks,
+            resource,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Arbitrary for GeometryNode {
+    type Parameters = ();
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        geometry_node_mapper().boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+
+    test_parsing!(
+        [
+            0x0D, // name length
+            0x63, 0x47, 0x65, 0x6F, 0x6D, 0x65, 0x74, 0x72, 0x79, 0x4E, 0x6F, 0x64,
+            0x65, // cGeometryNode
+            0x8C, 0x83, 0xA3, 0x7B, // block id
+            0x0C, 0x00, 0x00, 0x00, // block version
+            0x10, // name length
+            0x63, 0x4F, 0x62, 0x6A, 0x65, 0x63, 0x74, 0x47, 0x72, 0x61, 0x70, 0x68, 0x4E, 0x6F,
+            0x64, 0x65, // cObjectGraphNode
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x04, 0x00, 0x00, 0x00, // block version
+            0x00, 0x00, 0x00, 0x00, // number of extensions
+            0x01, 0x4E, // N
+            0x00, 0x00, 0x00, 0x00, // number of blocks
+            0x0B, // name length
+            0x63, 0x53, 0x47, 0x52, 0x65, 0x73, 0x6F, 0x75, 0x72, 0x63, 0x65, // cSGResource
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x02, 0x00, 0x00, 0x00, // block version
+            0x01, 0x4E, // N
+        ],
+        GeometryNode {
+            block: BlockHeader::of::<GeometryNode>(12),
+            graph: ObjectGraphNode::new("N".to_string()),
+            unknown: None,
+            blocks: vec![],
+            resource: SgResource::new("N".to_string()),
+        },
+        GeometryNode,
+        cgeometrynode
+    );
+}
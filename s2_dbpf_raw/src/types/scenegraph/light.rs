@@ -4,4 +4,333 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct Light {}
+//! Lights (LGHT): ambient, directional, point and spot light blocks an object's scenegraph
+//! places, along with the base every light block shares.
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId};
+use crate::types::util::parser_args::ParserArgs;
+use crate::types::util::resource_collection::{
+    Block, BlockHeader, ObjectGraphNode, ResourceCollection, SgResource,
+};
+use binrw::binrw;
+use derive_more::{Deref, DerefMut};
+#[cfg(test)]
+use proptest::prelude::*;
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type LGHT = Light;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Deref, DerefMut)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(args: ParserArgs))]
+pub struct Light {
+    #[br(args_raw = args)]
+    pub collection: ResourceCollection,
+}
+
+impl DbpfEntry for Light {
+    fn id(&self) -> DbpfId {
+        DbpfId::Light
+    }
+
+    fn name(&self) -> Option<String> {
+        self.lights()
+            .next()
+            .map(|light| light.resource.file_name.clone())
+    }
+}
+
+impl Light {
+    /// Every light block in the collection, whatever its kind.
+    pub fn lights(&self) -> impl Iterator<Item = &LightBase> {
+        self.blocks.iter().filter_map(Block::light)
+    }
+
+    pub fn lights_mut(&mut self) -> impl Iterator<Item = &mut LightBase> {
+        self.blocks.iter_mut().filter_map(Block::light_mut)
+    }
+}
+
+impl Block {
+    /// The shared part of a light block, if this is one.
+    #[must_use]
+    pub fn light(&self) -> Option<&LightBase> {
+        match self {
+            Block::AmbientLight(light) => Some(&light.base),
+            Block::DirectionalLight(light) => Some(&light.base),
+            Block::PointLight(light) => Some(&light.base),
+            Block::SpotLight(light) => Some(&light.base),
+            Block::DrawStateLight(light) => Some(&light.base),
+            Block::LinearFogLight(light) => Some(&light.base),
+            Block::EnvironmentCubeLight(light) => Some(&light.base),
+            _ => None,
+        }
+    }
+
+    pub fn light_mut(&mut self) -> Option<&mut LightBase> {
+        match self {
+            Block::AmbientLight(light) => Some(&mut light.base),
+            Block::DirectionalLight(light) => Some(&mut light.base),
+            Block::PointLight(light) => Some(&mut light.base),
+            Block::SpotLight(light) => Some(&mut light.base),
+            Block::DrawStateLight(light) => Some(&mut light.base),
+            Block::LinearFogLight(light) => Some(&mut light.base),
+            Block::EnvironmentCubeLight(light) => Some(&mut light.base),
+            _ => None,
+        }
+    }
+}
+
+/// The embedded `cStandardLightBase`, `cLightT` and `cReferentNode` blocks every light block
+/// starts with, followed by its color.
+#[binrw]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct LightBase {
+    #[cfg_attr(
+        test,
+        strategy(Just(BlockHeader::new(LightBase::STANDARD_LIGHT_BASE, 0, 11)))
+    )]
+    pub standard: BlockHeader,
+    pub resource: SgResource,
+    #[cfg_attr(test, strategy(Just(BlockHeader::new(LightBase::LIGHT_T, 0, 11))))]
+    pub light: BlockHeader,
+    #[cfg_attr(test, strategy(Just(BlockHeader::new(LightBase::REFERENT_NODE, 0, 1))))]
+    pub referent: BlockHeader,
+    pub graph: ObjectGraphNode,
+    #[cfg_attr(test, strategy(0.0_f32..10.0))]
+    pub intensity: f32,
+    /// Red, green and blue, from 0 to 1
+    #[cfg_attr(test, strategy(proptest::array::uniform3(0.0_f32..1.0)))]
+    pub color: [f32; 3],
+}
+
+impl LightBase {
+    pub const STANDARD_LIGHT_BASE: &'static str = "cStandardLightBase";
+    pub const LIGHT_T: &'static str = "cLightT";
+    pub const REFERENT_NODE: &'static str = "cReferentNode";
+
+    #[must_use]
+    pub fn new(name: String, intensity: f32, color: [f32; 3]) -> Self {
+        Self {
+            standard: BlockHeader::new(Self::STANDARD_LIGHT_BASE, 0, 11),
+            resource: SgResource::new(name.clone()),
+            light: BlockHeader::new(Self::LIGHT_T, 0, 11),
+            referent: BlockHeader::new(Self::REFERENT_NODE, 0, 1),
+            graph: ObjectGraphNode::new(name),
+            intensity,
+            color,
+        }
+    }
+
+    pub fn scale_intensity(&mut self, factor: f32) {
+        self.intensity *= factor;
+    }
+
+    /// Multiplies the color channel by channel, keeping the result between 0 and 1.
+    pub fn tint(&mut self, tint: [f32; 3]) {
+        for (channel, tint) in self.color.iter_mut().zip(tint) {
+            *channel = (*channel * tint).clamp(0.0, 1.0);
+        }
+    }
+}
+
+/// The `cAmbientLight` block, lighting everything evenly.
+#[binrw]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct AmbientLight {
+    #[cfg_attr(test, strategy(Just(BlockHeader::of::<AmbientLight>(1))))]
+    pub block: BlockHeader,
+    pub base: LightBase,
+}
+
+/// The `cDirectionalLight` block, lighting from a direction like the sun.
+#[binrw]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct DirectionalLight {
+    #[cfg_attr(test, strategy(Just(BlockHeader::of::<DirectionalLight>(1))))]
+    pub block: BlockHeader,
+    pub base: LightBase,
+    pub shadows: ShadowFlags,
+}
+
+/// The `cPointLight` block, lighting in all directions from a point, fading between the falloff
+/// distances.
+#[binrw]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct PointLight {
+    #[cfg_attr(test, strategy(Just(BlockHeader::of::<PointLight>(1))))]
+    pub block: BlockHeader,
+    pub base: LightBase,
+    pub shadows: ShadowFlags,
+    #[cfg_attr(test, strategy(0.0_f32..100.0))]
+    pub falloff_start: f32,
+    #[cfg_attr(test, strategy(0.0_f32..100.0))]
+    pub falloff_end: f32,
+}
+
+/// The `cSpotLight` block, a point light limited to a cone, fading between the inner and outer
+/// angles.
+#[binrw]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct SpotLight {
+    #[cfg_attr(test, strategy(Just(BlockHeader::of::<SpotLight>(1))))]
+    pub block: BlockHeader,
+    pub base: LightBase,
+    pub shadows: ShadowFlags,
+    #[cfg_attr(test, strategy(0.0_f32..100.0))]
+    pub falloff_start: f32,
+    #[cfg_attr(test, strategy(0.0_f32..100.0))]
+    pub falloff_end: f32,
+    /// In radians
+    #[cfg_attr(test, strategy(0.0_f32..3.0))]
+    pub inner_angle: f32,
+    /// In radians
+    #[cfg_attr(test, strategy(0.0_f32..3.0))]
+    pub outer_angle: f32,
+}
+
+#[binrw]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct ShadowFlags(pub u32);
+
+impl ShadowFlags {
+    pub const CASTS_SHADOWS: u32 = 0x1;
+    pub const SOFT_SHADOWS: u32 = 0x2;
+
+    #[must_use]
+    pub fn casts_shadows(self) -> bool {
+        self.0 & Self::CASTS_SHADOWS != 0
+    }
+
+    #[must_use]
+    pub fn soft_shadows(self) -> bool {
+        self.0 & Self::SOFT_SHADOWS != 0
+    }
+
+    pub fn set_casts_shadows(&mut self, value: bool) {
+        self.set(Self::CASTS_SHADOWS, value);
+    }
+
+    pub fn set_soft_shadows(&mut self, value: bool) {
+        self.set(Self::SOFT_SHADOWS, value);
+    }
+
+    fn set(&mut self, flag: u32, value: bool) {
+        if value {
+            self.0 |= flag;
+        } else {
+            self.0 &= !flag;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+
+    test_parsing!(
+        [
+            0x0B, // name length
+            0x63, 0x50, 0x6F, 0x69, 0x6E, 0x74, 0x4C, 0x69, 0x67, 0x68, 0x74, // cPointLight
+            0xAD, 0x1B, 0xC8, 0xC9, // block id
+            0x01, 0x00, 0x00, 0x00, // block version
+            0x12, // name length
+            0x63, 0x53, 0x74, 0x61, 0x6E, 0x64, 0x61, 0x72, 0x64, 0x4C, 0x69, 0x67, 0x68, 0x74,
+            0x42, 0x61, 0x73, 0x65, // cStandardLightBase
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x0B, 0x00, 0x00, 0x00, // block version
+            0x0B, // name length
+            0x63, 0x53, 0x47, 0x52, 0x65, 0x73, 0x6F, 0x75, 0x72, 0x63, 0x65, // cSGResource
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x02, 0x00, 0x00, 0x00, // block version
+            0x01, 0x4C, // L
+            0x07, // name length
+            0x63, 0x4C, 0x69, 0x67, 0x68, 0x74, 0x54, // cLightT
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x0B, 0x00, 0x00, 0x00, // block version
+            0x0D, // name length
+            0x63, 0x52, 0x65, 0x66, 0x65, 0x72, 0x65, 0x6E, 0x74, 0x4E, 0x6F, 0x64,
+            0x65, // cReferentNode
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x01, 0x00, 0x00, 0x00, // block version
+            0x10, // name length
+            0x63, 0x4F, 0x62, 0x6A, 0x65, 0x63, 0x74, 0x47, 0x72, 0x61, 0x70, 0x68, 0x4E, 0x6F,
+            0x64, 0x65, // cObjectGraphNode
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x04, 0x00, 0x00, 0x00, // block version
+            0x00, 0x00, 0x00, 0x00, // number of extensions
+            0x01, 0x4C, // L
+            0x00, 0x00, 0x80, 0x3F, // intensity
+            0x00, 0x00, 0x80, 0x3F, // red
+            0x00, 0x00, 0x00, 0x3F, // green
+            0x00, 0x00, 0x00, 0x00, // blue
+            0x01, 0x00, 0x00, 0x00, // shadow flags
+            0x00, 0x00, 0x00, 0x40, // falloff start
+            0x00, 0x00, 0x20, 0x41, // falloff end
+        ],
+        PointLight {
+            block: BlockHeader::of::<PointLight>(1),
+            base: LightBase::new("L".to_string(), 1.0, [1.0, 0.5, 0.0]),
+            shadows: ShadowFlags(ShadowFlags::CASTS_SHADOWS),
+            falloff_start: 2.0,
+            falloff_end: 10.0,
+        },
+        PointLight,
+        cpointlight
+    );
+
+    #[test]
+    fn batch_adjust() {
+        let mut light = Light {
+            collection: ResourceCollection::new(vec![
+                AmbientLight {
+                    block: BlockHeader::of::<AmbientLight>(1),
+                    base: LightBase::new("ambient".to_string(), 1.0, [0.5, 0.5, 0.5]),
+                }
+                .into(),
+                SpotLight {
+                    block: BlockHeader::of::<SpotLight>(1),
+                    base: LightBase::new("spot".to_string(), 2.0, [1.0, 1.0, 1.0]),
+                    shadows: ShadowFlags::default(),
+                    falloff_start: 1.0,
+                    falloff_end: 5.0,
+                    inner_angle: 0.5,
+                    outer_angle: 1.0,
+                }
+                .into(),
+            ]),
+        };
+
+        for light in light.lights_mut() {
+            light.scale_intensity(0.5);
+            light.tint([1.0, 0.5, 4.0]);
+        }
+
+        assert_eq!(light.name(), Some("ambient".to_string()));
+        assert_eq!(
+            light.lights().cloned().collect::<Vec<_>>(),
+            vec![
+                LightBase::new("ambient".to_string(), 0.5, [0.5, 0.25, 1.0]),
+                LightBase::new("spot".to_string(), 1.0, [1.0, 0.5, 1.0]),
+            ]
+        );
+    }
+}
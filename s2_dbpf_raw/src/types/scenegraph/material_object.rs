@@ -4,4 +4,74 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct MaterialObject {}
+//! Material override XML (XMTO), a catalog entry for an object recolor.
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId};
+use crate::types::util::cpf::Cpf;
+use crate::types::util::parser_args::ParserArgs;
+use binrw::binrw;
+use derive_more::{Deref, DerefMut};
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type XMTO = MaterialObject;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Default, Deref, DerefMut)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(args: ParserArgs))]
+pub struct MaterialObject {
+    #[br(args_raw = args)]
+    pub cpf: Cpf,
+}
+
+impl DbpfEntry for MaterialObject {
+    fn id(&self) -> DbpfId {
+        DbpfId::MaterialObject
+    }
+
+    fn name(&self) -> Option<String> {
+        self.cpf.name().map(ToString::to_string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use crate::types::util::cpf::{CpfFormat, CpfProperty, CpfValue};
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+    use proptest::prelude::*;
+
+    const XML: &[u8] = b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<cGZPropertySetString>
+  <AnyString key=\"name\" type=\"0x0b8bea18\">chair_red</AnyString>
+  <AnyString key=\"type\" type=\"0x0b8bea18\">xmto</AnyString>
+</cGZPropertySetString>
+";
+
+    test_parsing!(
+        XML,
+        MaterialObject {
+            cpf: Cpf {
+                format: CpfFormat::Xml {
+                    root: "cGZPropertySetString".to_string(),
+                },
+                properties: vec![
+                    CpfProperty {
+                        name: "name".to_string(),
+                        value: CpfValue::String("chair_red".to_string()),
+                    },
+                    CpfProperty {
+                        name: "type".to_string(),
+                        value: CpfValue::String("xmto".to_string()),
+                    },
+                ],
+            },
+        },
+        MaterialObject,
+        xmto
+    );
+}
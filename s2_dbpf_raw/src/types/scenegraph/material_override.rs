@@ -0,0 +1,90 @@
+////////////////////////////////////////////////////////////////////////////////
+// This Source Code Form is subject to the terms of the Mozilla Public         /
+// License, v. 2.0. If a copy of the MPL was not distributed with this         /
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
+////////////////////////////////////////////////////////////////////////////////
+
+//! Material overrides (MMAT), swapping the material of one subset of an object's model. Every
+//! recolor of an object is one of these.
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId};
+use crate::types::util::cpf::{Cpf, CpfValue};
+use crate::types::util::parser_args::ParserArgs;
+use binrw::binrw;
+use derive_more::{Deref, DerefMut};
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type MMAT = MaterialOverride;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Default, Deref, DerefMut)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(args: ParserArgs))]
+pub struct MaterialOverride {
+    #[br(args_raw = args)]
+    pub cpf: Cpf,
+}
+
+impl DbpfEntry for MaterialOverride {
+    fn id(&self) -> DbpfId {
+        DbpfId::MaterialOverride
+    }
+
+    fn name(&self) -> Option<String> {
+        self.cpf.name().map(ToString::to_string)
+    }
+}
+
+impl MaterialOverride {
+    /// The CRES name of the model the override applies to.
+    #[must_use]
+    pub fn model_name(&self) -> Option<&str> {
+        self.string("modelName")
+    }
+
+    pub fn set_model_name(&mut self, name: String) {
+        self.set("modelName", CpfValue::String(name));
+    }
+
+    /// The subset of the model whose material is replaced.
+    #[must_use]
+    pub fn subset_name(&self) -> Option<&str> {
+        self.string("subsetName")
+    }
+
+    pub fn set_subset_name(&mut self, name: String) {
+        self.set("subsetName", CpfValue::String(name));
+    }
+
+    /// The name of the TXMT used instead, without the `_txmt` suffix.
+    #[must_use]
+    pub fn material_definition(&self) -> Option<&str> {
+        self.string("materialDefinition")
+    }
+
+    pub fn set_material_definition(&mut self, name: String) {
+        self.set("materialDefinition", CpfValue::String(name));
+    }
+
+    /// The GUID of the object the override shows up for in the catalog.
+    #[must_use]
+    pub fn object_guid(&self) -> Option<u32> {
+        self.uint("objectGUID")
+    }
+
+    pub fn set_object_guid(&mut self, guid: u32) {
+        self.set("objectGUID", CpfValue::Uint(guid));
+    }
+
+    /// Whether this is the material the object comes with rather than a recolor.
+    #[must_use]
+    pub fn default_material(&self) -> Option<bool> {
+        self.get("defaultMaterial")?.as_bool()
+    }
+
+    pub fn set_default_material(&mut self, default: bool) {
+        self.set("defaultMaterial", CpfValue::Bool(default));
+    }
+}
@@ -5,11 +5,14 @@
 ////////////////////////////////////////////////////////////////////////////////
 pub mod binary_index;
 pub mod creation_resource;
+pub mod extension;
 pub mod geometric_data_container;
 pub mod geometric_node;
 pub mod light;
 pub mod material_object;
+pub mod material_override;
 pub mod property_set;
+pub mod resolver;
 pub mod shape;
 pub mod texture_overlay_xml;
 pub mod textured_material;
@@ -4,4 +4,86 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct PropertySet {}
+//! Property sets (GZPS), describing a body part, outfit or hair in the catalog: its ages, genders,
+//! categories, family and the resources it's built from.
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId};
+use crate::types::util::cpf::Cpf;
+use crate::types::util::parser_args::ParserArgs;
+use binrw::binrw;
+use derive_more::{Deref, DerefMut};
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type GZPS = PropertySet;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Default, Deref, DerefMut)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(args: ParserArgs))]
+pub struct PropertySet {
+    #[br(args_raw = args)]
+    pub cpf: Cpf,
+}
+
+impl DbpfEntry for PropertySet {
+    fn id(&self) -> DbpfId {
+        DbpfId::PropertySet
+    }
+
+    fn name(&self) -> Option<String> {
+        self.cpf.name().map(ToString::to_string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use crate::types::util::cpf::{age, category, gender, CpfFormat, CpfProperty, CpfValue};
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+    use proptest::prelude::*;
+
+    test_parsing!(
+        [
+            0xE0, 0x50, 0xE7, 0xCB, // magic
+            0x02, 0x00, // version
+            0x03, 0x00, 0x00, 0x00, // number of properties
+            0xF7, 0xE4, 0x61, 0xEB, // uint
+            0x03, 0x00, 0x00, 0x00, // name length
+            0x61, 0x67, 0x65, // age
+            0x18, 0x00, 0x00, 0x00, // value
+            0xF7, 0xE4, 0x61, 0xEB, // uint
+            0x06, 0x00, 0x00, 0x00, // name length
+            0x67, 0x65, 0x6E, 0x64, 0x65, 0x72, // gender
+            0x01, 0x00, 0x00, 0x00, // value
+            0xF7, 0xE4, 0x61, 0xEB, // uint
+            0x08, 0x00, 0x00, 0x00, // name length
+            0x63, 0x61, 0x74, 0x65, 0x67, 0x6F, 0x72, 0x79, // category
+            0x09, 0x00, 0x00, 0x00, // value
+        ],
+        PropertySet {
+            cpf: Cpf {
+                format: CpfFormat::Binary { version: 2 },
+                properties: vec![
+                    CpfProperty {
+                        name: "age".to_string(),
+                        value: CpfValue::Uint(age::ADULT | age::ELDER),
+                    },
+                    CpfProperty {
+                        name: "gender".to_string(),
+                        value: CpfValue::Uint(gender::FEMALE),
+                    },
+                    CpfProperty {
+                        name: "category".to_string(),
+                        value: CpfValue::Uint(category::EVERYDAY | category::FORMAL),
+                    },
+                ],
+            },
+        },
+        PropertySet,
+        gzps
+    );
+}
@@ -0,0 +1,332 @@
+////////////////////////////////////////////////////////////////////////////////
+// This Source Code Form is subject to the terms of the Mozilla Public         /
+// License, v. 2.0. If a copy of the MPL was not distributed with this         /
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
+////////////////////////////////////////////////////////////////////////////////
+
+//! Walks an object's scenegraph from its CRES through shapes, geometry nodes and materials down
+//! to meshes and textures, across any number of packages.
+
+use std::collections::HashSet;
+
+use crate::constants::data_kinds::{DbpfId, DbpfKind};
+use crate::types::neighborhood::three_d_reference::find;
+use crate::types::package::database_packed_file::{Dbpf, Key};
+use crate::types::scenegraph::creation_resource::Bone;
+use crate::types::scenegraph::geometric_data_container::{GeometryData, Mesh};
+use crate::types::util::hash::{name_key, with_suffix};
+use crate::types::util::resource_collection::ResourceLink;
+
+/// Every resource an object's scenegraph is made of, in the order they were reached.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Scenegraph {
+    pub resources: Vec<Key>,
+    pub shapes: Vec<Key>,
+    pub geometry_nodes: Vec<Key>,
+    pub meshes: Vec<Key>,
+    pub materials: Vec<Key>,
+    pub textures: Vec<Key>,
+    /// References that couldn't be found in any of the packages
+    pub missing: Vec<MissingLink>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingLink {
+    /// The resource holding the reference
+    pub from: Key,
+    pub reference: Reference,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reference {
+    /// A link in the resource collection
+    Link(ResourceLink),
+    /// A resource referred to by its file name
+    Name { kind: DbpfId, name: String },
+}
+
+impl Scenegraph {
+    /// Resolves the scenegraph rooted at the CRES `cres`, looking resources up in `packages`
+    /// with [`find`]. Names are turned into keys with [`name_key`].
+    #[must_use]
+    pub fn resolve(packages: &[Dbpf], cres: &Key) -> Self {
+        let mut resolver = Resolver {
+            packages,
+            visited: HashSet::new(),
+            graph: Scenegraph::default(),
+        };
+        if let Some((key, data)) = find(packages, None, cres) {
+            resolver.visit(key, data);
+        }
+        resolver.graph
+    }
+
+    /// Every group of every GMDC reached, as meshes.
+    #[must_use]
+    pub fn mesh_data(&self, packages: &[Dbpf]) -> Vec<Mesh> {
+        self.meshes
+            .iter()
+            .filter_map(|key| match entry(packages, key)? {
+                DbpfKind::GeometricDataContainer(gmdc) => gmdc.geometry().map(GeometryData::meshes),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// The skeleton of the root CRES, empty if it has none.
+    #[must_use]
+    pub fn skeleton(&self, packages: &[Dbpf]) -> Vec<Bone> {
+        match self.resources.first().and_then(|key| entry(packages, key)) {
+            Some(DbpfKind::CreationResource(cres)) => cres.skeleton(),
+            _ => vec![],
+        }
+    }
+}
+
+/// The resource at exactly `key`, in the first package holding it.
+fn entry<'a>(packages: &'a [Dbpf], key: &Key) -> Option<&'a DbpfKind> {
+    packages
+        .iter()
+        .find_map(|dbpf| dbpf.entries.get(key))
+        .map(|entry| &entry.data)
+}
+
+struct Resolver<'a> {
+    packages: &'a [Dbpf],
+    visited: HashSet<Key>,
+    graph: Scenegraph,
+}
+
+impl<'a> Resolver<'a> {
+    fn visit(&mut self, key: Key, data: &'a DbpfKind) {
+        if !self.visited.insert(key) {
+            return;
+        }
+        match data {
+            DbpfKind::CreationResource(cres) => {
+                self.graph.resources.push(key);
+                self.links(key, &cres.links);
+            }
+            DbpfKind::Shape(shpe) => {
+                self.graph.shapes.push(key);
+                self.links(key, &shpe.links);
+                if let Some(shape) = shpe.shape() {
+                    for item in &shape.items {
+                        self.name(key, DbpfId::GeometricNode, &item.file_name, "_gmnd");
+                    }
+                    for part in &shape.parts {
+                        self.name(key, DbpfId::TexturedMaterial, &part.material, "_txmt");
+                    }
+                }
+            }
+            DbpfKind::GeometricNode(gmnd) => {
+                self.graph.geometry_nodes.push(key);
+                self.links(key, &gmnd.links);
+            }
+            DbpfKind::GeometricDataContainer(_) => self.graph.meshes.push(key),
+            DbpfKind::TexturedMaterial(txmt) => {
+                self.graph.materials.push(key);
+                self.links(key, &txmt.links);
+                if let Some(material) = txmt.material() {
+                    for name in material.texture_names() {
+                        self.name(key, DbpfId::TextureResource, name, "_txtr");
+                    }
+                }
+            }
+            DbpfKind::TextureResource(_) => self.graph.textures.push(key),
+            _ => {}
+        }
+    }
+
+    fn links(&mut self, from: Key, links: &[ResourceLink]) {
+        for link in links {
+            self.reference(from, &link.key(), || Reference::Link(link.clone()));
+        }
+    }
+
+    fn name(&mut self, from: Key, kind: DbpfId, name: &str, suffix: &str) {
+        if name.is_empty() {
+            return;
+        }
+        let name = with_suffix(name, suffix);
+        self.reference(from, &name_key(kind, &name), || Reference::Name {
+            kind,
+            name,
+        });
+    }
+
+    fn reference(&mut self, from: Key, key: &Key, reference: impl FnOnce() -> Reference) {
+        match find(self.packages, Some(&from), key) {
+            Some((key, data)) => self.visit(key, data),
+            None => self.graph.missing.push(MissingLink {
+                from,
+                reference: reference(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::dbpf_from;
+    use crate::types::package::database_packed_file::{GroupId, InstanceId};
+    use crate::types::scenegraph::creation_resource::{
+        ChildReference, CreationResource, ResourceNode, ResourceNodeKind,
+    };
+    use crate::types::scenegraph::geometric_data_container::GeometricDataContainer;
+    use crate::types::scenegraph::geometric_node::{GeometricNode, GeometryNode};
+    use crate::types::scenegraph::shape::{Shape, ShapeData, ShapeItem, ShapePart};
+    use crate::types::scenegraph::textured_material::{
+        MaterialDefinition, MaterialProperty, TexturedMaterial,
+    };
+    use crate::types::util::hash::instance_hash;
+    use crate::types::util::resource_collection::{
+        BlockHeader, ObjectGraphNode, ResourceCollection, SgResource,
+    };
+
+    fn key(kind: DbpfId, instance_id: InstanceId) -> Key {
+        Key {
+            kind,
+            group_id: GroupId(0x1C05_0000),
+            instance_id,
+            resource_id: None,
+        }
+    }
+
+    fn link(key: &Key) -> ResourceLink {
+        ResourceLink {
+            group_id: GroupId(0xFFFF_FFFF),
+            instance_id: key.instance_id,
+            resource_id: None,
+            kind: key.kind,
+        }
+    }
+
+    #[test]
+    fn resolve_chain() {
+        let cres_key = key(DbpfId::CreationResource, InstanceId(1));
+        let shpe_key = key(DbpfId::Shape, InstanceId(2));
+        let gmnd_key = key(
+            DbpfId::GeometricNode,
+            instance_hash("##0x1c050000!chair_tslocator_gmnd"),
+        );
+        let gmdc_key = key(DbpfId::GeometricDataContainer, InstanceId(4));
+        let txmt_key = key(DbpfId::TexturedMaterial, instance_hash("chair_seat_txmt"));
+
+        let cres = CreationResource {
+            collection: ResourceCollection {
+                links: vec![link(&shpe_key)],
+                ..ResourceCollection::new(vec![ResourceNode {
+                    block: BlockHeader::of::<ResourceNode>(7),
+                    node: ResourceNodeKind::Graph {
+                        graph: ObjectGraphNode::new("chair_cres".to_string()),
+                    },
+                    children: vec![ChildReference {
+                        unknown: 0,
+                        index: 1,
+                    }],
+                    unknown1: 0,
+                    unknown2: 0,
+                }
+                .into()])
+            },
+        };
+        let shpe = Shape {
+            collection: ResourceCollection::new(vec![ShapeData {
+                block: BlockHeader::of::<ShapeData>(8),
+                resource: SgResource::new("chair_shpe".to_string()),
+                referent: BlockHeader::new(ShapeData::REFERENT_NODE, 0, 1),
+                graph: ObjectGraphNode::new("chair_shpe".to_string()),
+                lod_levels: vec![0],
+                items: vec![ShapeItem {
+                    level: 0,
+                    enabled: 1,
+                    unknown: None,
+                    file_name: "##0x1c050000!chair_tslocator_gmnd".to_string(),
+                }],
+                parts: vec![ShapePart {
+                    subset: "seat".to_string(),
+                    material: "##0x1c050000!chair_seat".to_string(),
+                    unknown: [0; 9],
+                }],
+            }
+            .into()]),
+        };
+        let gmnd = GeometricNode {
+            collection: ResourceCollection {
+                // The mesh lives in another package, so the link names its group
+                links: vec![ResourceLink {
+                    group_id: gmdc_key.group_id,
+                    ..link(&gmdc_key)
+                }],
+                ..ResourceCollection::new(vec![GeometryNode {
+                    block: BlockHeader::of::<GeometryNode>(12),
+                    graph: ObjectGraphNode::new("chair_tslocator_gmnd".to_string()),
+                    unknown: None,
+                    blocks: vec![],
+                    resource: SgResource::new("chair_tslocator_gmnd".to_string()),
+                }
+                .into()])
+            },
+        };
+        let txmt = TexturedMaterial {
+            collection: ResourceCollection::new(vec![MaterialDefinition {
+                block: BlockHeader::of::<MaterialDefinition>(11),
+                resource: SgResource::new("chair_seat_txmt".to_string()),
+                description: "chair_seat".to_string(),
+                shader: "StandardMaterial".to_string(),
+                properties: vec![MaterialProperty {
+                    name: "stdMatBaseTextureName".to_string(),
+                    value: "chair_seat_base".to_string(),
+                }],
+                textures: vec![],
+            }
+            .into()]),
+        };
+
+        let objects = dbpf_from(vec![
+            (cres_key, DbpfKind::CreationResource(cres)),
+            (shpe_key, DbpfKind::Shape(shpe)),
+            (gmnd_key, DbpfKind::GeometricNode(gmnd)),
+            (txmt_key, DbpfKind::TexturedMaterial(txmt)),
+        ]);
+        let meshes = dbpf_from(vec![(
+            gmdc_key,
+            DbpfKind::GeometricDataContainer(GeometricDataContainer {
+                collection: ResourceCollection::default(),
+            }),
+        )]);
+
+        // Same name in another group, searched first
+        let other = dbpf_from(vec![(
+            Key {
+                group_id: GroupId(0x7F00_0001),
+                ..txmt_key
+            },
+            DbpfKind::TexturedMaterial(TexturedMaterial {
+                collection: ResourceCollection::default(),
+            }),
+        )]);
+
+        let graph = Scenegraph::resolve(&[other, objects, meshes], &cres_key);
+
+        assert_eq!(graph.resources, vec![cres_key]);
+        assert_eq!(graph.shapes, vec![shpe_key]);
+        assert_eq!(graph.geometry_nodes, vec![gmnd_key]);
+        assert_eq!(graph.meshes, vec![gmdc_key]);
+        assert_eq!(graph.materials, vec![txmt_key]);
+        assert_eq!(graph.textures, vec![]);
+        assert_eq!(
+            graph.missing,
+            vec![MissingLink {
+                from: txmt_key,
+                reference: Reference::Name {
+                    kind: DbpfId::TextureResource,
+                    name: "chair_seat_base_txtr".to_string(),
+                },
+            }]
+        );
+    }
+}
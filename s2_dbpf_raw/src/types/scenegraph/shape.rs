@@ -4,4 +4,221 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct Shape {}
+//! Shapes (SHPE), tying the geometry nodes of each level of detail to the materials their mesh
+//! groups are drawn with.
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId};
+use crate::types::util::parser_args::ParserArgs;
+use crate::types::util::resource_collection::{
+    BlockHeader, ObjectGraphNode, ResourceCollection, SgResource,
+};
+use crate::types::util::strings::{read_seven_bit_string, write_seven_bit_string};
+use binrw::binrw;
+use derive_more::{Deref, DerefMut};
+#[cfg(test)]
+use proptest::collection::vec;
+#[cfg(test)]
+use proptest::prelude::*;
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type SHPE = Shape;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Deref, DerefMut)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(args: ParserArgs))]
+pub struct Shape {
+    #[br(args_raw = args)]
+    pub collection: ResourceCollection,
+}
+
+impl DbpfEntry for Shape {
+    fn id(&self) -> DbpfId {
+        DbpfId::Shape
+    }
+
+    fn name(&self) -> Option<String> {
+        self.shape().map(|shape| shape.resource.file_name.clone())
+    }
+}
+
+impl Shape {
+    #[must_use]
+    pub fn shape(&self) -> Option<&ShapeData> {
+        self.block()
+    }
+
+    pub fn shape_mut(&mut self) -> Option<&mut ShapeData> {
+        self.block_mut()
+    }
+}
+
+/// The `cShape` block.
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[brw(little)]
+pub struct ShapeData {
+    pub block: BlockHeader,
+    pub resource: SgResource,
+    /// The embedded `cReferentNode` block, which has no data of its own
+    pub referent: BlockHeader,
+    pub graph: ObjectGraphNode,
+    #[br(temp, if(block.version != 6))]
+    #[bw(calc = (block.version != 6).then(|| lod_levels.len() as u32))]
+    num_lod_levels: Option<u32>,
+    /// Not stored in version 6
+    #[br(count(num_lod_levels.unwrap_or(0) as usize))]
+    pub lod_levels: Vec<u32>,
+    #[br(temp)]
+    #[bw(calc = items.len() as u32)]
+    num_items: u32,
+    #[br(args { count: num_items as usize, inner: (block.version,) })]
+    pub items: Vec<ShapeItem>,
+    #[br(temp)]
+    #[bw(calc = parts.len() as u32)]
+    num_parts: u32,
+    #[br(count(num_parts as usize))]
+    pub parts: Vec<ShapePart>,
+}
+
+impl ShapeData {
+    pub const REFERENT_NODE: &'static str = "cReferentNode";
+}
+
+#[cfg(test)]
+prop_compose! {
+    fn shape_data_mapper()(
+        version in 6_u32..9
+    )(
+        resource in any::<SgResource>(),
+        graph in any::<ObjectGraphNode>(),
+        lod_levels in vec(any::<u32>(), 0..3),
+        items in vec(any_with::<ShapeItem>((version,)), 0..3),
+        parts in vec(any::<ShapePart>(), 0..3),
+        version in Just(version),
+    ) -> ShapeData {
+        ShapeData {
+            block: BlockHeader::of::<ShapeData>(version),
+            resource,
+            referent: BlockHeader::new(ShapeData::REFERENT_NODE, 0, 1),
+            graph,
+            lod_levels: if version == 6 { vec![] } else { lod_levels },
+            items,
+            parts,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Arbitrary for ShapeData {
+    type Parameters = ();
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        shape_data_mapper().boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
+
+/// A level of detail, drawn with the geometry node named `file_name`.
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[brw(little)]
+#[br(import(version: u32))]
+#[cfg_attr(test, derive(Arbitrary))]
+#[cfg_attr(test, arbitrary(args = (u32,)))]
+pub struct ShapeItem {
+    pub level: u32,
+    pub enabled: u8,
+    /// Only stored before version 8
+    #[br(if(version < 8))]
+    #[cfg_attr(test, strategy(any::<(u32, u8)>().prop_map(move |x| (args.0 < 8).then(|| x))))]
+    pub unknown: Option<(u32, u8)>,
+    #[br(parse_with = read_seven_bit_string)]
+    #[bw(write_with = write_seven_bit_string)]
+    #[cfg_attr(test, strategy("[a-zA-Z0-9_!#]{0,32}"))]
+    pub file_name: String,
+}
+
+/// Draws the mesh group `subset` with the material named `material`.
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct ShapePart {
+    #[br(parse_with = read_seven_bit_string)]
+    #[bw(write_with = write_seven_bit_string)]
+    #[cfg_attr(test, strategy("[a-zA-Z0-9_]{0,32}"))]
+    pub subset: String,
+    #[br(parse_with = read_seven_bit_string)]
+    #[bw(write_with = write_seven_bit_string)]
+    #[cfg_attr(test, strategy("[a-zA-Z0-9_!#]{0,32}"))]
+    pub material: String,
+    pub unknown: [u8; 9],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+
+    test_parsing!(
+        [
+            0x06, // name length
+            0x63, 0x53, 0x68, 0x61, 0x70, 0x65, // cShape
+            0xF7, 0xB1, 0x6E, 0xFC, // block id
+            0x08, 0x00, 0x00, 0x00, // block version
+            0x0B, // name length
+            0x63, 0x53, 0x47, 0x52, 0x65, 0x73, 0x6F, 0x75, 0x72, 0x63, 0x65, // cSGResource
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x02, 0x00, 0x00, 0x00, // block version
+            0x01, 0x53, // S
+            0x0D, // name length
+            0x63, 0x52, 0x65, 0x66, 0x65, 0x72, 0x65, 0x6E, 0x74, 0x4E, 0x6F, 0x64,
+            0x65, // cReferentNode
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x01, 0x00, 0x00, 0x00, // block version
+            0x10, // name length
+            0x63, 0x4F, 0x62, 0x6A, 0x65, 0x63, 0x74, 0x47, 0x72, 0x61, 0x70, 0x68, 0x4E, 0x6F,
+            0x64, 0x65, // cObjectGraphNode
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x04, 0x00, 0x00, 0x00, // block version
+            0x00, 0x00, 0x00, 0x00, // number of extensions
+            0x01, 0x53, // S
+            0x01, 0x00, 0x00, 0x00, // number of lod levels
+            0x00, 0x00, 0x00, 0x00, // lod level
+            0x01, 0x00, 0x00, 0x00, // number of items
+            0x00, 0x00, 0x00, 0x00, // level
+            0x01, // enabled
+            0x01, 0x4E, // N
+            0x01, 0x00, 0x00, 0x00, // number of parts
+            0x04, 0x62, 0x6F, 0x64, 0x79, // body
+            0x01, 0x4D, // M
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // unknown
+        ],
+        ShapeData {
+            block: BlockHeader::of::<ShapeData>(8),
+            resource: SgResource::new("S".to_string()),
+            referent: BlockHeader::new(ShapeData::REFERENT_NODE, 0, 1),
+            graph: ObjectGraphNode::new("S".to_string()),
+            lod_levels: vec![0],
+            items: vec![ShapeItem {
+                level: 0,
+                enabled: 1,
+                unknown: None,
+                file_name: "N".to_string(),
+            }],
+            parts: vec![ShapePart {
+                subset: "body".to_string(),
+                material: "M".to_string(),
+                unknown: [0; 9],
+            }],
+        },
+        ShapeData,
+        cshape
+    );
+}
@@ -4,4 +4,79 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct TextureOverlayXml {}
+//! Texture overlay XML (XTOL), a catalog entry for makeup and other overlays drawn over a sim.
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId};
+use crate::types::util::cpf::Cpf;
+use crate::types::util::parser_args::ParserArgs;
+use binrw::binrw;
+use derive_more::{Deref, DerefMut};
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type XTOL = TextureOverlayXml;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Default, Deref, DerefMut)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(args: ParserArgs))]
+pub struct TextureOverlayXml {
+    #[br(args_raw = args)]
+    pub cpf: Cpf,
+}
+
+impl DbpfEntry for TextureOverlayXml {
+    fn id(&self) -> DbpfId {
+        DbpfId::TextureOverlayXML
+    }
+
+    fn name(&self) -> Option<String> {
+        self.cpf.name().map(ToString::to_string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use crate::types::util::cpf::{CpfFormat, CpfProperty, CpfValue};
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+    use proptest::prelude::*;
+
+    const XML: &[u8] = b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<cGZPropertySetString>
+  <AnyString key=\"name\" type=\"0x0b8bea18\">blush_pink</AnyString>
+  <AnyString key=\"family\" type=\"0x0b8bea18\">f2a3c6b6-0d1e-4e34-a2b9-5d8c9e3f1a20</AnyString>
+  <AnyUint32 key=\"gender\" type=\"0xeb61e4f7\">1</AnyUint32>
+</cGZPropertySetString>
+";
+
+    test_parsing!(
+        XML,
+        TextureOverlayXml {
+            cpf: Cpf {
+                format: CpfFormat::Xml {
+                    root: "cGZPropertySetString".to_string(),
+                },
+                properties: vec![
+                    CpfProperty {
+                        name: "name".to_string(),
+                        value: CpfValue::String("blush_pink".to_string()),
+                    },
+                    CpfProperty {
+                        name: "family".to_string(),
+                        value: CpfValue::String("f2a3c6b6-0d1e-4e34-a2b9-5d8c9e3f1a20".to_string()),
+                    },
+                    CpfProperty {
+                        name: "gender".to_string(),
+                        value: CpfValue::Uint(1),
+                    },
+                ],
+            },
+        },
+        TextureOverlayXml,
+        xtol
+    );
+}
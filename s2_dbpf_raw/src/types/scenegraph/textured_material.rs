@@ -4,4 +4,470 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct TexturedMaterial {}
+//! Materials (TXMT): a shader and the properties it's drawn with, including the names of the
+//! textures it samples.
+
+use std::str::FromStr;
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId};
+use crate::types::util::parser_args::ParserArgs;
+use crate::types::util::resource_collection::{BlockHeader, ResourceCollection, SgResource};
+use crate::types::util::strings::{
+    read_seven_bit_string, read_seven_bit_strings, write_seven_bit_string, write_seven_bit_strings,
+};
+use binrw::binrw;
+use derive_more::{Deref, DerefMut};
+#[cfg(test)]
+use proptest::collection::vec;
+#[cfg(test)]
+use proptest::prelude::*;
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type TXMT = TexturedMaterial;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Deref, DerefMut)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(args: ParserArgs))]
+pub struct TexturedMaterial {
+    #[br(args_raw = args)]
+    pub collection: ResourceCollection,
+}
+
+impl DbpfEntry for TexturedMaterial {
+    fn id(&self) -> DbpfId {
+        DbpfId::TexturedMaterial
+    }
+
+    fn name(&self) -> Option<String> {
+        self.material()
+            .map(|material| material.resource.file_name.clone())
+    }
+}
+
+impl TexturedMaterial {
+    #[must_use]
+    pub fn material(&self) -> Option<&MaterialDefinition> {
+        self.block()
+    }
+
+    pub fn material_mut(&mut self) -> Option<&mut MaterialDefinition> {
+        self.block_mut()
+    }
+}
+
+/// The `cMaterialDefinition` block.
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[brw(little)]
+pub struct MaterialDefinition {
+    pub block: BlockHeader,
+    pub resource: SgResource,
+    #[br(parse_with = read_seven_bit_string)]
+    #[bw(write_with = write_seven_bit_string)]
+    pub description: String,
+    /// The shader, such as `StandardMaterial`
+    #[br(parse_with = read_seven_bit_string)]
+    #[bw(write_with = write_seven_bit_string)]
+    pub shader: String,
+    #[br(temp)]
+    #[bw(calc = properties.len() as u32)]
+    num_properties: u32,
+    #[br(count(num_properties as usize))]
+    pub properties: Vec<MaterialProperty>,
+    #[br(temp, if(block.version > 8))]
+    #[bw(calc = (block.version > 8).then(|| textures.len() as u32))]
+    num_textures: Option<u32>,
+    /// The names of the textures the material uses. Not stored before version 9
+    #[br(parse_with = read_seven_bit_strings, args(num_textures.unwrap_or(0)))]
+    #[bw(write_with = write_seven_bit_strings)]
+    pub textures: Vec<String>,
+}
+
+impl MaterialDefinition {
+    pub const BASE_TEXTURE: &'static str = "stdMatBaseTextureName";
+    pub const NORMAL_MAP_TEXTURE: &'static str = "stdMatNormalMapTextureName";
+    pub const ENVIRONMENT_CUBE_TEXTURE: &'static str = "stdMatEnvCubeTextureName";
+    pub const ALPHA_BLEND_MODE: &'static str = "stdMatAlphaBlendMode";
+    pub const ALPHA_TEST_ENABLED: &'static str = "stdMatAlphaTestEnabled";
+    pub const ALPHA_REF_VALUE: &'static str = "stdMatAlphaRefValue";
+    pub const ALPHA_MULTIPLIER: &'static str = "stdMatAlphaMultiplier";
+    pub const DIFFUSE_COEFFICIENT: &'static str = "stdMatDiffCoef";
+    pub const SPECULAR_COEFFICIENT: &'static str = "stdMatSpecCoef";
+    pub const SPECULAR_POWER: &'static str = "stdMatSpecPower";
+    pub const EMISSIVE_COEFFICIENT: &'static str = "stdMatEmissiveCoef";
+    pub const LAYER: &'static str = "stdMatLayer";
+
+    /// The value of the property called `name`.
+    #[must_use]
+    pub fn property(&self, name: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|property| property.name == name)
+            .map(|property| property.value.as_str())
+    }
+
+    /// Sets the property called `name`, keeping its position if it already exists and adding it
+    /// at the end otherwise.
+    pub fn set_property(&mut self, name: &str, value: String) {
+        match self
+            .properties
+            .iter_mut()
+            .find(|property| property.name == name)
+        {
+            Some(property) => property.value = value,
+            None => self.properties.push(MaterialProperty {
+                name: name.to_string(),
+                value,
+            }),
+        }
+    }
+
+    /// Removes the property called `name`, returning its value.
+    pub fn remove_property(&mut self, name: &str) -> Option<String> {
+        let index = self
+            .properties
+            .iter()
+            .position(|property| property.name == name)?;
+        Some(self.properties.remove(index).value)
+    }
+
+    /// Every texture name the material refers to, from its texture list and from properties
+    /// naming a texture.
+    #[must_use]
+    pub fn texture_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.textures.iter().map(String::as_str).collect();
+        for property in &self.properties {
+            if property.name.ends_with("TextureName") && !names.contains(&property.value.as_str()) {
+                names.push(&property.value);
+            }
+        }
+        names
+    }
+
+    /// Points every reference to the texture `from`, in the texture list and in properties, at
+    /// `to` instead. Names are compared case insensitively, like the game does.
+    pub fn retarget_texture(&mut self, from: &str, to: &str) {
+        let from = from.to_lowercase();
+        for texture in &mut self.textures {
+            if texture.to_lowercase() == from {
+                *texture = to.to_string();
+            }
+        }
+        for property in &mut self.properties {
+            if property.name.ends_with("TextureName") && property.value.to_lowercase() == from {
+                property.value = to.to_string();
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn base_texture(&self) -> Option<&str> {
+        self.property(Self::BASE_TEXTURE)
+    }
+
+    /// Sets the base texture, updating the texture list to match.
+    pub fn set_base_texture(&mut self, name: &str) {
+        if let Some(old) = self.base_texture().map(ToString::to_string) {
+            self.retarget_texture(&old, name);
+        } else {
+            self.set_property(Self::BASE_TEXTURE, name.to_string());
+            if self.block.version > 8 {
+                self.textures.push(name.to_string());
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn normal_map_texture(&self) -> Option<&str> {
+        self.property(Self::NORMAL_MAP_TEXTURE)
+    }
+
+    #[must_use]
+    pub fn alpha_blend_mode(&self) -> Option<AlphaBlendMode> {
+        self.property(Self::ALPHA_BLEND_MODE)
+            .and_then(AlphaBlendMode::from_name)
+    }
+
+    pub fn set_alpha_blend_mode(&mut self, mode: AlphaBlendMode) {
+        self.set_property(Self::ALPHA_BLEND_MODE, mode.name().to_string());
+    }
+
+    #[must_use]
+    pub fn alpha_test_enabled(&self) -> Option<bool> {
+        self.parsed::<u8>(Self::ALPHA_TEST_ENABLED)
+            .map(|enabled| enabled != 0)
+    }
+
+    pub fn set_alpha_test_enabled(&mut self, enabled: bool) {
+        self.set_property(Self::ALPHA_TEST_ENABLED, u8::from(enabled).to_string());
+    }
+
+    #[must_use]
+    pub fn alpha_ref_value(&self) -> Option<u8> {
+        self.parsed(Self::ALPHA_REF_VALUE)
+    }
+
+    pub fn set_alpha_ref_value(&mut self, value: u8) {
+        self.set_property(Self::ALPHA_REF_VALUE, value.to_string());
+    }
+
+    #[must_use]
+    pub fn alpha_multiplier(&self) -> Option<f32> {
+        self.parsed(Self::ALPHA_MULTIPLIER)
+    }
+
+    pub fn set_alpha_multiplier(&mut self, value: f32) {
+        self.set_property(Self::ALPHA_MULTIPLIER, value.to_string());
+    }
+
+    /// The diffuse color, red green blue and sometimes alpha.
+    #[must_use]
+    pub fn diffuse_coefficient(&self) -> Option<Vec<f32>> {
+        self.color(Self::DIFFUSE_COEFFICIENT)
+    }
+
+    pub fn set_diffuse_coefficient(&mut self, color: &[f32]) {
+        self.set_color(Self::DIFFUSE_COEFFICIENT, color);
+    }
+
+    /// The specular color, red green blue.
+    #[must_use]
+    pub fn specular_coefficient(&self) -> Option<Vec<f32>> {
+        self.color(Self::SPECULAR_COEFFICIENT)
+    }
+
+    pub fn set_specular_coefficient(&mut self, color: &[f32]) {
+        self.set_color(Self::SPECULAR_COEFFICIENT, color);
+    }
+
+    #[must_use]
+    pub fn specular_power(&self) -> Option<f32> {
+        self.parsed(Self::SPECULAR_POWER)
+    }
+
+    pub fn set_specular_power(&mut self, value: f32) {
+        self.set_property(Self::SPECULAR_POWER, value.to_string());
+    }
+
+    /// The emissive color, red green blue.
+    #[must_use]
+    pub fn emissive_coefficient(&self) -> Option<Vec<f32>> {
+        self.color(Self::EMISSIVE_COEFFICIENT)
+    }
+
+    pub fn set_emissive_coefficient(&mut self, color: &[f32]) {
+        self.set_color(Self::EMISSIVE_COEFFICIENT, color);
+    }
+
+    /// The draw order of transparent materials, higher is drawn later.
+    #[must_use]
+    pub fn layer(&self) -> Option<i32> {
+        self.parsed(Self::LAYER)
+    }
+
+    pub fn set_layer(&mut self, layer: i32) {
+        self.set_property(Self::LAYER, layer.to_string());
+    }
+
+    fn parsed<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.property(name)?.trim().parse().ok()
+    }
+
+    /// Colors are stored as comma separated components.
+    fn color(&self, name: &str) -> Option<Vec<f32>> {
+        self.property(name)?
+            .split(',')
+            .map(|component| component.trim().parse().ok())
+            .collect()
+    }
+
+    fn set_color(&mut self, name: &str, color: &[f32]) {
+        let value = color
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        self.set_property(name, value);
+    }
+}
+
+/// How a material's alpha is combined with what's behind it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AlphaBlendMode {
+    None,
+    Blend,
+    Additive,
+}
+
+impl AlphaBlendMode {
+    /// The name stored in the property.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            AlphaBlendMode::None => "none",
+            AlphaBlendMode::Blend => "blend",
+            AlphaBlendMode::Additive => "additive",
+        }
+    }
+
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "none" => Some(AlphaBlendMode::None),
+            "blend" => Some(AlphaBlendMode::Blend),
+            "additive" => Some(AlphaBlendMode::Additive),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+prop_compose! {
+    fn material_definition_mapper()(
+        version in 8_u32..12
+    )(
+        resource in any::<SgResource>(),
+        description in "[a-zA-Z0-9_]{0,16}",
+        shader in "[a-zA-Z]{0,16}",
+        properties in vec(any::<MaterialProperty>(), 0..4),
+        textures in vec("[a-zA-Z0-9_]{0,16}", 0..3),
+        version in Just(version),
+    ) -> MaterialDefinition {
+        MaterialDefinition {
+            block: BlockHeader::of::<MaterialDefinition>(version),
+            resource,
+            description,
+            shader,
+            properties,
+            textures: if version > 8 { textures } else { vec![] },
+        }
+    }
+}
+
+#[cfg(test)]
+impl Arbitrary for MaterialDefinition {
+    type Parameters = ();
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        material_definition_mapper().boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct MaterialProperty {
+    #[br(parse_with = read_seven_bit_string)]
+    #[bw(write_with = write_seven_bit_string)]
+    #[cfg_attr(test, strategy("[a-zA-Z]{0,24}"))]
+    pub name: String,
+    #[br(parse_with = read_seven_bit_string)]
+    #[bw(write_with = write_seven_bit_string)]
+    #[cfg_attr(test, strategy("[a-zA-Z0-9_,. ]{0,24}"))]
+    pub value: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+
+    test_parsing!(
+        [
+            0x13, // name length
+            0x63, 0x4D, 0x61, 0x74, 0x65, 0x72, 0x69, 0x61, 0x6C, 0x44, 0x65, 0x66, 0x69, 0x6E,
+            0x69, 0x74, 0x69, 0x6F, 0x6E, // cMaterialDefinition
+            0x78, 0x69, 0x59, 0x49, // block id
+            0x0B, 0x00, 0x00, 0x00, // block version
+            0x0B, // name length
+            0x63, 0x53, 0x47, 0x52, 0x65, 0x73, 0x6F, 0x75, 0x72, 0x63, 0x65, // cSGResource
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x02, 0x00, 0x00, 0x00, // block version
+            0x01, 0x4D, // M
+            0x01, 0x44, // D
+            0x01, 0x53, // S
+            0x01, 0x00, 0x00, 0x00, // number of properties
+            0x01, 0x70, // p
+            0x01, 0x76, // v
+            0x01, 0x00, 0x00, 0x00, // number of textures
+            0x01, 0x54, // T
+        ],
+        MaterialDefinition {
+            block: BlockHeader::of::<MaterialDefinition>(11),
+            resource: SgResource::new("M".to_string()),
+            description: "D".to_string(),
+            shader: "S".to_string(),
+            properties: vec![MaterialProperty {
+                name: "p".to_string(),
+                value: "v".to_string(),
+            }],
+            textures: vec!["T".to_string()],
+        },
+        MaterialDefinition,
+        cmaterialdefinition
+    );
+
+    fn material() -> MaterialDefinition {
+        MaterialDefinition {
+            block: BlockHeader::of::<MaterialDefinition>(11),
+            resource: SgResource::new("chair_seat_txmt".to_string()),
+            description: "chair_seat".to_string(),
+            shader: "StandardMaterial".to_string(),
+            properties: vec![
+                MaterialProperty {
+                    name: MaterialDefinition::BASE_TEXTURE.to_string(),
+                    value: "chair_seat_base".to_string(),
+                },
+                MaterialProperty {
+                    name: MaterialDefinition::SPECULAR_COEFFICIENT.to_string(),
+                    value: "0.5, 0.5,0.25".to_string(),
+                },
+                MaterialProperty {
+                    name: MaterialDefinition::ALPHA_BLEND_MODE.to_string(),
+                    value: "none".to_string(),
+                },
+            ],
+            textures: vec!["Chair_Seat_Base".to_string()],
+        }
+    }
+
+    #[test]
+    fn typed_properties() {
+        let mut material = material();
+
+        assert_eq!(material.base_texture(), Some("chair_seat_base"));
+        assert_eq!(material.specular_coefficient(), Some(vec![0.5, 0.5, 0.25]));
+        assert_eq!(material.alpha_blend_mode(), Some(AlphaBlendMode::None));
+        assert_eq!(material.specular_power(), None);
+
+        material.set_alpha_blend_mode(AlphaBlendMode::Blend);
+        material.set_specular_coefficient(&[1.0, 0.75, 0.0]);
+        material.set_specular_power(8.5);
+
+        assert_eq!(material.property("stdMatAlphaBlendMode"), Some("blend"));
+        assert_eq!(material.property("stdMatSpecCoef"), Some("1,0.75,0"));
+        assert_eq!(material.specular_power(), Some(8.5));
+        // Existing properties keep their place, new ones go last
+        assert_eq!(material.properties[2].name, "stdMatAlphaBlendMode");
+        assert_eq!(material.properties[3].name, "stdMatSpecPower");
+    }
+
+    #[test]
+    fn retarget_base_texture() {
+        let mut material = material();
+
+        material.set_base_texture("chair_seat_red");
+
+        assert_eq!(material.base_texture(), Some("chair_seat_red"));
+        assert_eq!(material.textures, vec!["chair_seat_red".to_string()]);
+        assert_eq!(material.texture_names(), vec!["chair_seat_red"]);
+    }
+}
@@ -4,4 +4,241 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct BehaviorFlowchartTree {}
+//! Editor layout data for a BHAV, as written by Edith.
+//! A TREE shares its group and instance with the BHAV it lays out.
+
+use std::io::{Read, Seek, Write};
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId, DbpfKind};
+use crate::types::package::database_packed_file::{Dbpf, Key};
+use crate::types::simantic::behavior_function::{BehaviorFunction, Instruction};
+use crate::types::util::parser_args::ParserArgs;
+use binrw::{binrw, BinRead, BinResult, BinWrite, NullString, ReadOptions, WriteOptions};
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type TREE = BehaviorFlowchartTree;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(_args: ParserArgs))]
+pub struct BehaviorFlowchartTree {
+    #[br(try_map(NullString::try_into))]
+    #[bw(map(| x: &String | NullString::from(x.clone())))]
+    #[brw(pad_size_to = 64)]
+    #[cfg_attr(test, strategy("[a-zA-Z0-9 ]{0,63}"))]
+    pub file_name: String,
+    // Always zero in every file seen so far
+    pub unknown: u32,
+    pub version: u32,
+    #[brw(magic(b"EERT"))]
+    #[br(temp)]
+    #[bw(calc = nodes.len() as u32)]
+    num_nodes: u32,
+    #[br(count(num_nodes as usize))]
+    pub nodes: Vec<TreeNode>,
+}
+
+impl DbpfEntry for BehaviorFlowchartTree {
+    fn id(&self) -> DbpfId {
+        DbpfId::BehaviorFlowchartTree
+    }
+
+    fn name(&self) -> Option<String> {
+        Some(self.file_name.clone())
+    }
+}
+
+impl BehaviorFlowchartTree {
+    /// Finds the BHAV laid out by the TREE stored under `key`.
+    #[must_use]
+    pub fn behavior<'a>(key: &Key, dbpf: &'a Dbpf) -> Option<(&'a Key, &'a BehaviorFunction)> {
+        match dbpf.find(DbpfId::BehaviorFunction, key.group_id, key.instance_id) {
+            Some((key, DbpfKind::BehaviorFunction(bhav))) => Some((key, bhav)),
+            _ => None,
+        }
+    }
+
+    /// The node drawn for the instruction at `index` in the linked BHAV, if it has been placed.
+    #[must_use]
+    pub fn node_for_instruction(&self, index: u16) -> Option<&TreeNode> {
+        self.nodes
+            .iter()
+            .find(|node| node.kind == NodeKind::Primitive && i32::from(node.id) == i32::from(index))
+    }
+
+    /// Looks up the node a `true_target` or `false_target` refers to, `None` when unconnected.
+    #[must_use]
+    pub fn target(&self, target: i16) -> Option<&TreeNode> {
+        usize::try_from(target).ok().and_then(|i| self.nodes.get(i))
+    }
+}
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct TreeNode {
+    pub kind: NodeKind,
+    /// For primitive nodes, the index of the instruction within the BHAV.
+    pub id: i16,
+    #[br(map = |x: i16| x != 0)]
+    #[bw(map = |x: &bool| i16::from(*x))]
+    pub position_invalid: bool,
+    pub x: i16,
+    pub y: i16,
+    pub width: i16,
+    pub height: i16,
+    /// Index into the node list of the node the true connector points to, -1 if unconnected
+    pub true_target: i16,
+    /// Index into the node list of the node the false connector points to, -1 if unconnected
+    pub false_target: i16,
+    #[br(parse_with = read_comment)]
+    #[bw(write_with = write_comment)]
+    #[cfg_attr(test, strategy("[a-zA-Z0-9 ]{0,32}"))]
+    pub comment: String,
+}
+
+impl TreeNode {
+    /// The instruction this node represents, only primitive nodes map to an instruction.
+    #[must_use]
+    pub fn instruction<'a>(&self, bhav: &'a BehaviorFunction) -> Option<&'a Instruction> {
+        if self.kind == NodeKind::Primitive {
+            usize::try_from(self.id)
+                .ok()
+                .and_then(|i| bhav.instructions.get(i))
+        } else {
+            None
+        }
+    }
+
+    /// The point connectors attach to, the middle of the node.
+    #[must_use]
+    pub fn center(&self) -> (i32, i32) {
+        (
+            i32::from(self.x) + i32::from(self.width) / 2,
+            i32::from(self.y) + i32::from(self.height) / 2,
+        )
+    }
+}
+
+#[binrw]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little, repr = u16)]
+pub enum NodeKind {
+    Primitive = 0,
+    True = 1,
+    False = 2,
+    Comment = 3,
+    Label = 4,
+    GoTo = 5,
+}
+
+// Comments are null terminated and then padded so the next node starts on an even offset
+fn read_comment<R: Read + Seek>(reader: &mut R, options: &ReadOptions, _: ()) -> BinResult<String> {
+    let pos = reader.stream_position()?;
+    let comment: String = NullString::read_options(reader, options, ())?
+        .try_into()
+        .map_err(|err| binrw::Error::Custom {
+            pos,
+            err: Box::new(err),
+        })?;
+    if comment.len() % 2 == 0 {
+        u8::read_options(reader, options, ())?;
+    }
+    Ok(comment)
+}
+
+// Signature is dictated by binrw's write_with
+#[allow(clippy::ptr_arg, clippy::trivially_copy_pass_by_ref)]
+fn write_comment<W: Write + Seek>(
+    comment: &String,
+    writer: &mut W,
+    options: &WriteOptions,
+    _: (),
+) -> BinResult<()> {
+    NullString::from(comment.clone()).write_options(writer, options, ())?;
+    if comment.len() % 2 == 0 {
+        0u8.write_options(writer, options, ())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+    use proptest::prelude::*;
+
+    test_parsing!(
+        [
+            0x54, 0x65, 0x73, 0x74, 0x46, 0x69, 0x6C, 0x65, //TestFile - in ascii bytes
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // first padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // second padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // third padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fourth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fifth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sixth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // seventh padding block
+            0x00, 0x00, 0x00, 0x00, // unknown
+            0x01, 0x00, 0x00, 0x00, // version
+            0x45, 0x45, 0x52, 0x54, // EERT
+            0x02, 0x00, 0x00, 0x00, // number of nodes
+            0x00, 0x00, // kind
+            0x00, 0x00, // id
+            0x00, 0x00, // position invalid
+            0x10, 0x00, 0x20, 0x00, // x, y
+            0x40, 0x00, 0x18, 0x00, // width, height
+            0x01, 0x00, // true target
+            0xFF, 0xFF, // false target
+            0x48, 0x69, 0x00, // "Hi"
+            0x00, // padding
+            0x01, 0x00, // kind
+            0x01, 0x00, // id
+            0x01, 0x00, // position invalid
+            0x00, 0x00, 0x00, 0x00, // x, y
+            0x00, 0x00, 0x00, 0x00, // width, height
+            0xFF, 0xFF, // true target
+            0xFF, 0xFF, // false target
+            0x4F, 0x6B, 0x21, 0x00, // "Ok!"
+        ],
+        BehaviorFlowchartTree {
+            file_name: "TestFile".to_string(),
+            unknown: 0,
+            version: 1,
+            nodes: vec![
+                TreeNode {
+                    kind: NodeKind::Primitive,
+                    id: 0,
+                    position_invalid: false,
+                    x: 0x10,
+                    y: 0x20,
+                    width: 0x40,
+                    height: 0x18,
+                    true_target: 1,
+                    false_target: -1,
+                    comment: "Hi".to_string(),
+                },
+                TreeNode {
+                    kind: NodeKind::True,
+                    id: 1,
+                    position_invalid: true,
+                    x: 0,
+                    y: 0,
+                    width: 0,
+                    height: 0,
+                    true_target: -1,
+                    false_target: -1,
+                    comment: "Ok!".to_string(),
+                },
+            ]
+        },
+        BehaviorFlowchartTree,
+        tree
+    );
+}
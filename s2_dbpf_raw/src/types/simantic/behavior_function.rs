@@ -62,6 +62,32 @@ impl DbpfEntry for BehaviorFunction {
     }
 }
 
+/// Where the code an opcode runs lives, decided by the range the opcode falls in.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum CallScope {
+    /// Built into the game
+    Primitive,
+    /// A BHAV in the global group, shared by every object
+    Global,
+    /// A BHAV in the calling object's own group
+    Local,
+    /// A BHAV in the semiglobal group named by the object's GLOB
+    SemiGlobal,
+}
+
+impl CallScope {
+    #[must_use]
+    pub fn of(opcode: u16) -> Option<Self> {
+        match opcode {
+            0x0000..=0x00FF => Some(CallScope::Primitive),
+            0x0100..=0x0FFF => Some(CallScope::Global),
+            0x1000..=0x1FFF => Some(CallScope::Local),
+            0x2000..=0x2FFF => Some(CallScope::SemiGlobal),
+            _ => None,
+        }
+    }
+}
+
 #[binrw]
 #[derive(Debug, PartialOrd, PartialEq, Eq, Copy, Clone)]
 #[cfg_attr(test, derive(Arbitrary))]
@@ -105,6 +131,14 @@ pub struct Instruction {
     pub cache_flags: Option<u8>,
 }
 
+impl Instruction {
+    /// Where the code this instruction runs lives, `None` for opcodes past the semiglobal range.
+    #[must_use]
+    pub fn scope(&self) -> Option<CallScope> {
+        CallScope::of(self.opcode)
+    }
+}
+
 impl BinRead for Instruction {
     type Args = (Signature,);
 
@@ -4,13 +4,218 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
+//! Names the semiglobal group an object's group belongs to, which decides where its calls to
+//! 0x2000 range opcodes go.
+
+use std::iter;
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId, DbpfKind};
+use crate::types::package::database_packed_file::{Dbpf, GroupId, InstanceId, Key};
+use crate::types::simantic::behavior_function::{BehaviorFunction, CallScope};
+use crate::types::util::hash::group_hash;
+use crate::types::util::parser_args::ParserArgs;
+use crate::types::util::strings::{read_seven_bit_string, write_seven_bit_string};
 use binrw::{binrw, NullString};
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type GLOB = GlobalData;
+
+/// The group holding the BHAVs every object can call with 0x0100 range opcodes.
+pub const GLOBAL_GROUP: GroupId = GroupId(0x7FD4_6CD0);
 
 #[binrw]
-#[derive(Default, Clone, PartialEq)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(_args: ParserArgs))]
 pub struct GlobalData {
     #[br(try_map(NullString::try_into))]
     #[bw(map(| x: & String | NullString::from(x.clone())))]
     #[brw(pad_size_to = 64)]
+    #[cfg_attr(test, strategy("[a-zA-Z0-9 ]{0,63}"))]
     pub file_name: String,
+    #[br(parse_with = read_seven_bit_string)]
+    #[bw(write_with = write_seven_bit_string)]
+    #[cfg_attr(test, strategy("[a-zA-Z0-9 ]{0,32}"))]
+    pub semiglobal_name: String,
+}
+
+impl DbpfEntry for GlobalData {
+    fn id(&self) -> DbpfId {
+        DbpfId::GlobalData
+    }
+
+    fn name(&self) -> Option<String> {
+        Some(self.file_name.clone())
+    }
+}
+
+impl GlobalData {
+    /// The group the semiglobal BHAVs live in, derived from the semiglobal name.
+    #[must_use]
+    pub fn semiglobal_group(&self) -> GroupId {
+        group_hash(&self.semiglobal_name)
+    }
+}
+
+/// Works out which BHAV an object's call refers to, looking through the object's own package
+/// first and then the game's global and semiglobal packages.
+#[derive(Debug, Clone, Copy)]
+pub struct CallResolver<'a> {
+    object: &'a Dbpf,
+    libraries: &'a [Dbpf],
+}
+
+impl<'a> CallResolver<'a> {
+    #[must_use]
+    pub fn new(object: &'a Dbpf, libraries: &'a [Dbpf]) -> Self {
+        Self { object, libraries }
+    }
+
+    /// The GLOB of the object group `group`, if it has one.
+    #[must_use]
+    pub fn global_data(&self, group: GroupId) -> Option<&'a GlobalData> {
+        self.object
+            .entries
+            .iter()
+            .find_map(|(key, entry)| match &entry.data {
+                DbpfKind::GlobalData(glob) if key.group_id == group => Some(glob),
+                _ => None,
+            })
+    }
+
+    /// The semiglobal group BHAVs in `group` call into, `None` if the group has no GLOB.
+    #[must_use]
+    pub fn semiglobal_group(&self, group: GroupId) -> Option<GroupId> {
+        self.global_data(group).map(GlobalData::semiglobal_group)
+    }
+
+    /// The group a BHAV in `group` reaches with `opcode`, `None` for primitives and for
+    /// semiglobal calls from a group without a GLOB.
+    #[must_use]
+    pub fn target_group(&self, group: GroupId, opcode: u16) -> Option<GroupId> {
+        match CallScope::of(opcode)? {
+            CallScope::Primitive => None,
+            CallScope::Global => Some(GLOBAL_GROUP),
+            CallScope::Local => Some(group),
+            CallScope::SemiGlobal => self.semiglobal_group(group),
+        }
+    }
+
+    /// Finds the BHAV a BHAV in `group` runs with `opcode`.
+    #[must_use]
+    pub fn resolve(&self, group: GroupId, opcode: u16) -> Option<(&'a Key, &'a BehaviorFunction)> {
+        let target = self.target_group(group, opcode)?;
+        iter::once(self.object)
+            .chain(self.libraries.iter())
+            .find_map(|dbpf| {
+                match dbpf.find(
+                    DbpfId::BehaviorFunction,
+                    target,
+                    InstanceId(u32::from(opcode)),
+                ) {
+                    Some((key, DbpfKind::BehaviorFunction(bhav))) => Some((key, bhav)),
+                    _ => None,
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{dbpf_from, test_parsing};
+    use crate::types::simantic::behavior_function::Signature;
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+    use proptest::prelude::*;
+
+    test_parsing!(
+        [
+            0x54, 0x65, 0x73, 0x74, 0x46, 0x69, 0x6C, 0x65, //TestFile - in ascii bytes
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // first padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // second padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // third padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fourth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fifth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sixth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // seventh padding block
+            0x0C, // length of semiglobal name
+            0x43, 0x68, 0x61, 0x69, 0x72, 0x47, 0x6C, 0x6F, 0x62, 0x61, 0x6C,
+            0x73, // ChairGlobals
+        ],
+        GlobalData {
+            file_name: "TestFile".to_string(),
+            semiglobal_name: "ChairGlobals".to_string(),
+        },
+        GlobalData,
+        glob
+    );
+
+    fn bhav(file_name: &str) -> BehaviorFunction {
+        BehaviorFunction {
+            file_name: file_name.to_string(),
+            signature: Signature::Seven,
+            tree_type: 0,
+            num_parameters: 0,
+            num_locals: 0,
+            flag: 0,
+            tree_version: 0,
+            instructions: vec![],
+        }
+    }
+
+    #[test]
+    fn resolves_each_scope() {
+        let group = GroupId(0x7F00_0001);
+        let glob = GlobalData {
+            file_name: String::new(),
+            semiglobal_name: "ChairGlobals".to_string(),
+        };
+        let semiglobal_group = glob.semiglobal_group();
+        let object = dbpf_from(vec![
+            (
+                Key::new(DbpfId::GlobalData, group, InstanceId(1), None),
+                glob.into(),
+            ),
+            (
+                Key::new(DbpfId::BehaviorFunction, group, InstanceId(0x1000), None),
+                bhav("Local").into(),
+            ),
+        ]);
+        let libraries = vec![
+            dbpf_from(vec![(
+                Key::new(
+                    DbpfId::BehaviorFunction,
+                    GLOBAL_GROUP,
+                    InstanceId(0x100),
+                    None,
+                ),
+                bhav("Global").into(),
+            )]),
+            dbpf_from(vec![(
+                Key::new(
+                    DbpfId::BehaviorFunction,
+                    semiglobal_group,
+                    InstanceId(0x2000),
+                    None,
+                ),
+                bhav("SemiGlobal").into(),
+            )]),
+        ];
+        let resolver = CallResolver::new(&object, &libraries);
+        let name = |opcode| {
+            resolver
+                .resolve(group, opcode)
+                .map(|(_, bhav)| bhav.file_name.as_str())
+        };
+
+        assert_eq!(resolver.target_group(group, 0x2000), Some(semiglobal_group));
+        assert_eq!(name(0x0002), None);
+        assert_eq!(name(0x0100), Some("Global"));
+        assert_eq!(name(0x1000), Some("Local"));
+        assert_eq!(name(0x2000), Some("SemiGlobal"));
+        assert_eq!(resolver.target_group(GroupId(0x7F00_0002), 0x2000), None);
+    }
 }
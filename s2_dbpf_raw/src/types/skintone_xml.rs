@@ -4,4 +4,74 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct SkintoneXml {}
+//! Skin tone XML (XSTN), a catalog entry for a skin tone.
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId};
+use crate::types::util::cpf::Cpf;
+use crate::types::util::parser_args::ParserArgs;
+use binrw::binrw;
+use derive_more::{Deref, DerefMut};
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type XSTN = SkintoneXml;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Default, Deref, DerefMut)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(args: ParserArgs))]
+pub struct SkintoneXml {
+    #[br(args_raw = args)]
+    pub cpf: Cpf,
+}
+
+impl DbpfEntry for SkintoneXml {
+    fn id(&self) -> DbpfId {
+        DbpfId::SkinToneXml
+    }
+
+    fn name(&self) -> Option<String> {
+        self.cpf.name().map(ToString::to_string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use crate::types::util::cpf::{CpfFormat, CpfProperty, CpfValue};
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+    use proptest::prelude::*;
+
+    const XML: &[u8] = b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<cGZPropertySetString>
+  <AnyString key=\"name\" type=\"0x0b8bea18\">s1</AnyString>
+  <AnyUint32 key=\"age\" type=\"0xeb61e4f7\">127</AnyUint32>
+</cGZPropertySetString>
+";
+
+    test_parsing!(
+        XML,
+        SkintoneXml {
+            cpf: Cpf {
+                format: CpfFormat::Xml {
+                    root: "cGZPropertySetString".to_string(),
+                },
+                properties: vec![
+                    CpfProperty {
+                        name: "name".to_string(),
+                        value: CpfValue::String("s1".to_string()),
+                    },
+                    CpfProperty {
+                        name: "age".to_string(),
+                        value: CpfValue::Uint(127),
+                    },
+                ],
+            },
+        },
+        SkintoneXml,
+        xstn
+    );
+}
@@ -4,4 +4,690 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct Sprites {}
+//! Paletted, run length encoded sprites with optional depth and alpha channels.
+//! Frames are decoded into one byte per pixel per channel when read, and encoded again on write.
+
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId, DbpfKind};
+use crate::types::image_color_palette::ImageColorPalette;
+use crate::types::package::database_packed_file::{Dbpf, GroupId, InstanceId};
+use crate::types::util::image::RgbaImage;
+use crate::types::util::parser_args::ParserArgs;
+use binrw::io::Cursor;
+use binrw::{binrw, BinRead, BinResult, BinWrite, Endian, NullString, ReadOptions, WriteOptions};
+#[cfg(test)]
+use proptest::collection::vec;
+#[cfg(test)]
+use proptest::prelude::*;
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type SPR2 = Sprites;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(_args: ParserArgs))]
+pub struct Sprites {
+    #[br(try_map(NullString::try_into))]
+    #[bw(map(| x: &String | NullString::from(x.clone())))]
+    #[brw(pad_size_to = 64)]
+    #[cfg_attr(test, strategy("[a-zA-Z0-9 ]{0,63}"))]
+    pub file_name: String,
+    pub version: Version,
+    // Version 1001 stores the palette id before the frame count, 1000 after it
+    #[br(temp, if (version == Version::OneThousandOne))]
+    #[bw(calc = (* version == Version::OneThousandOne).then(|| * palette_id))]
+    palette_first: Option<u32>,
+    #[br(temp)]
+    #[bw(calc = frames.len() as u32)]
+    num_frames: u32,
+    #[br(temp, if (version == Version::OneThousand))]
+    #[bw(calc = (* version == Version::OneThousand).then(|| * palette_id))]
+    palette_last: Option<u32>,
+    #[br(calc = palette_first.or(palette_last).unwrap_or_default())]
+    #[bw(ignore)]
+    pub palette_id: u32,
+    // Frames are stored back to back, so the offsets are only written for the readers that use
+    // them
+    #[br(temp, if (version == Version::OneThousand), count = num_frames as usize)]
+    #[bw(calc = frame_offsets(frames, *version))]
+    offsets: Vec<u32>,
+    #[br(args { count: num_frames as usize, inner: (version,) })]
+    #[bw(args_raw = (* version,))]
+    #[cfg_attr(test, strategy(vec(any::< SpriteFrame > (), (0..4))))]
+    pub frames: Vec<SpriteFrame>,
+}
+
+impl DbpfEntry for Sprites {
+    fn id(&self) -> DbpfId {
+        DbpfId::Sprites
+    }
+
+    fn name(&self) -> Option<String> {
+        Some(self.file_name.clone())
+    }
+}
+
+impl Sprites {
+    /// Finds the PALT the frames index into, searching the sprite's `group`.
+    #[must_use]
+    pub fn palette<'a>(&self, dbpf: &'a Dbpf, group: GroupId) -> Option<&'a ImageColorPalette> {
+        match dbpf.find(
+            DbpfId::ImageColorPalette,
+            group,
+            InstanceId(self.palette_id),
+        ) {
+            Some((_, DbpfKind::ImageColorPalette(palette))) => Some(palette),
+            _ => None,
+        }
+    }
+}
+
+// Offsets count from the start of the resource, which begins with the file name
+fn frame_offsets(frames: &[SpriteFrame], version: Version) -> Vec<u32> {
+    if version != Version::OneThousand {
+        return vec![];
+    }
+    let mut offset = 64 + 12 + 4 * frames.len() as u32;
+    frames
+        .iter()
+        .map(|frame| {
+            let start = offset;
+            let mut cursor = Cursor::new(vec![]);
+            // A frame that fails to encode fails the whole write later on, so its size is moot
+            if frame
+                .write_options(&mut cursor, &WriteOptions::new(Endian::Little), (version,))
+                .is_ok()
+            {
+                offset += cursor.get_ref().len() as u32;
+            }
+            start
+        })
+        .collect()
+}
+
+#[binrw]
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub enum Version {
+    /// Frames are preceded by a table of their offsets
+    #[brw(magic(1000_u32))]
+    OneThousand,
+    /// Every frame carries its own version and size instead of an offset table
+    #[brw(magic(1001_u32))]
+    OneThousandOne,
+}
+
+impl Default for Version {
+    fn default() -> Self {
+        Version::OneThousand
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpriteFrame {
+    pub width: u16,
+    pub height: u16,
+    pub palette_id: u16,
+    /// Palette index treated as transparent in frames without an alpha channel
+    pub transparent_index: u16,
+    pub y: u16,
+    pub x: u16,
+    /// Palette index of every pixel, row by row
+    pub color: Vec<u8>,
+    /// Depth of every pixel, 0 nearest to the camera
+    pub depth: Option<Vec<u8>>,
+    /// Opacity of every pixel from 0 to 31
+    pub alpha: Option<Vec<u8>>,
+}
+
+impl SpriteFrame {
+    const FLAG_COLOR: u32 = 0x01;
+    const FLAG_DEPTH: u32 = 0x02;
+    const FLAG_ALPHA: u32 = 0x04;
+
+    const ROW: u16 = 0;
+    const DEPTH_COLOR: u16 = 1;
+    const DEPTH_COLOR_ALPHA: u16 = 2;
+    const TRANSPARENT: u16 = 3;
+    const BLANK_ROWS: u16 = 4;
+    const END: u16 = 5;
+    const COLOR: u16 = 6;
+
+    const MAX_COUNT: usize = 0x1FFF;
+    const MAX_ALPHA: u8 = 31;
+
+    /// A fully transparent frame.
+    #[must_use]
+    pub fn new(width: u16, height: u16, depth: bool, alpha: bool) -> Self {
+        let pixels = usize::from(width) * usize::from(height);
+        Self {
+            width,
+            height,
+            palette_id: 0,
+            transparent_index: 0,
+            y: 0,
+            x: 0,
+            color: vec![0; pixels],
+            depth: depth.then(|| vec![0xFF; pixels]),
+            alpha: alpha.then(|| vec![0; pixels]),
+        }
+    }
+
+    fn flags(&self) -> u32 {
+        let mut flags = Self::FLAG_COLOR;
+        if self.depth.is_some() {
+            flags |= Self::FLAG_DEPTH;
+        }
+        if self.alpha.is_some() {
+            flags |= Self::FLAG_ALPHA;
+        }
+        flags
+    }
+
+    #[must_use]
+    pub fn is_transparent(&self, index: usize) -> bool {
+        match &self.alpha {
+            Some(alpha) => alpha[index] == 0,
+            None => u16::from(self.color[index]) == self.transparent_index,
+        }
+    }
+
+    /// Looks every pixel up in `palette`, scaling alpha up to 8 bits.
+    #[must_use]
+    pub fn to_rgba(&self, palette: &ImageColorPalette) -> RgbaImage {
+        let mut image = RgbaImage::new(u32::from(self.width), u32::from(self.height));
+        for (index, pixel) in image.data.chunks_exact_mut(4).enumerate() {
+            if self.is_transparent(index) {
+                continue;
+            }
+            let [r, g, b] = palette.color(self.color[index]).rgb();
+            let a = self.alpha.as_ref().map_or(255, |alpha| {
+                (u32::from(alpha[index].min(Self::MAX_ALPHA)) * 255 / u32::from(Self::MAX_ALPHA))
+                    as u8
+            });
+            pixel.copy_from_slice(&[r, g, b, a]);
+        }
+        image
+    }
+
+    /// The depth channel as an opaque grayscale image.
+    #[must_use]
+    pub fn depth_image(&self) -> Option<RgbaImage> {
+        self.depth.as_ref().map(|depth| {
+            let data = depth
+                .iter()
+                .flat_map(|&value| [value, value, value, 255])
+                .collect();
+            RgbaImage {
+                width: u32::from(self.width),
+                height: u32::from(self.height),
+                data,
+            }
+        })
+    }
+
+    /// Encodes `image` against `palette`. Pixels with no alpha become transparent, and an alpha
+    /// channel is only kept if some pixel is partially transparent.
+    ///
+    /// # Panics
+    /// Panics if the image is larger than 65535 pixels in either direction or if `depth` doesn't
+    /// hold a value for every pixel.
+    #[must_use]
+    pub fn from_rgba(
+        image: &RgbaImage,
+        palette: &ImageColorPalette,
+        transparent_index: u8,
+        depth: Option<&[u8]>,
+    ) -> Self {
+        let width = u16::try_from(image.width).expect("sprite too wide");
+        let height = u16::try_from(image.height).expect("sprite too tall");
+        let translucent = image.pixels().any(|pixel| pixel[3] != 0 && pixel[3] != 255);
+        let mut frame = Self::new(width, height, depth.is_some(), translucent);
+        frame.transparent_index = u16::from(transparent_index);
+        if let (Some(frame_depth), Some(depth)) = (&mut frame.depth, depth) {
+            assert_eq!(frame_depth.len(), depth.len(), "depth size mismatch");
+            frame_depth.copy_from_slice(depth);
+        }
+        for (index, pixel) in image.pixels().enumerate() {
+            if pixel[3] == 0 {
+                frame.color[index] = transparent_index;
+                if let Some(depth) = &mut frame.depth {
+                    depth[index] = 0xFF;
+                }
+                continue;
+            }
+            frame.color[index] =
+                palette.nearest([pixel[0], pixel[1], pixel[2]], Some(transparent_index));
+            if let Some(alpha) = &mut frame.alpha {
+                alpha[index] =
+                    ((u32::from(pixel[3]) * u32::from(Self::MAX_ALPHA) + 127) / 255).max(1) as u8;
+            }
+        }
+        frame
+    }
+
+    fn clear_pixel(&mut self, index: usize) {
+        self.color[index] = self.transparent_index as u8;
+        if let Some(depth) = &mut self.depth {
+            depth[index] = 0xFF;
+        }
+        if let Some(alpha) = &mut self.alpha {
+            alpha[index] = 0;
+        }
+    }
+
+    fn encode_pixels(&self, pos: u64) -> BinResult<Vec<u8>> {
+        fn command(out: &mut Vec<u8>, command: u16, count: usize) {
+            out.extend_from_slice(&((command << 13) | count as u16).to_le_bytes());
+        }
+        fn blank_rows(out: &mut Vec<u8>, blank: &mut usize) {
+            while *blank > 0 {
+                let count = (*blank).min(SpriteFrame::MAX_COUNT);
+                command(out, SpriteFrame::BLANK_ROWS, count);
+                *blank -= count;
+            }
+        }
+
+        let width = usize::from(self.width);
+        let mut out = vec![];
+        let mut blank = 0;
+        for y in 0..usize::from(self.height) {
+            let row = y * width..(y + 1) * width;
+            let last = row.clone().rev().find(|&index| !self.is_transparent(index));
+            let last = if let Some(last) = last {
+                last
+            } else {
+                blank += 1;
+                continue;
+            };
+            blank_rows(&mut out, &mut blank);
+
+            let mut row_bytes = vec![];
+            let mut index = row.start;
+            while index <= last {
+                let transparent = self.is_transparent(index);
+                let start = index;
+                while index <= last
+                    && self.is_transparent(index) == transparent
+                    && index - start < Self::MAX_COUNT
+                {
+                    index += 1;
+                }
+                let count = index - start;
+                if transparent {
+                    command(&mut row_bytes, Self::TRANSPARENT, count);
+                } else if let Some(alpha) = &self.alpha {
+                    command(&mut row_bytes, Self::DEPTH_COLOR_ALPHA, count);
+                    for i in start..index {
+                        let depth = self.depth.as_ref().map_or(0, |depth| depth[i]);
+                        row_bytes.extend_from_slice(&[depth, self.color[i], alpha[i]]);
+                    }
+                    if count % 2 == 1 {
+                        row_bytes.push(0);
+                    }
+                } else if let Some(depth) = &self.depth {
+                    command(&mut row_bytes, Self::DEPTH_COLOR, count);
+                    for (&depth, &color) in
+                        depth[start..index].iter().zip(&self.color[start..index])
+                    {
+                        row_bytes.extend_from_slice(&[depth, color]);
+                    }
+                } else {
+                    command(&mut row_bytes, Self::COLOR, count);
+                    row_bytes.extend_from_slice(&self.color[start..index]);
+                    if count % 2 == 1 {
+                        row_bytes.push(0);
+                    }
+                }
+            }
+            if row_bytes.len() + 2 > Self::MAX_COUNT {
+                return Err(binrw::Error::AssertFail {
+                    pos,
+                    message: format!("Sprite row {} is too long to encode", y),
+                });
+            }
+            command(&mut out, Self::ROW, row_bytes.len() + 2);
+            out.append(&mut row_bytes);
+        }
+        blank_rows(&mut out, &mut blank);
+        command(&mut out, Self::END, 0);
+        Ok(out)
+    }
+
+    fn decode_pixels<R: Read + Seek>(
+        &mut self,
+        reader: &mut R,
+        options: &ReadOptions,
+    ) -> BinResult<()> {
+        let width = usize::from(self.width);
+        let height = usize::from(self.height);
+        let out_of_bounds = |pos| binrw::Error::AssertFail {
+            pos,
+            message: "Sprite pixel data runs past the frame".to_string(),
+        };
+        let mut y = 0;
+        loop {
+            let pos = reader.stream_position()?;
+            let header = u16::read_options(reader, options, ())?;
+            let count = usize::from(header & 0x1FFF);
+            match header >> 13 {
+                Self::ROW => {
+                    if y >= height {
+                        return Err(out_of_bounds(pos));
+                    }
+                    let end = pos + count as u64;
+                    let mut x = 0;
+                    while reader.stream_position()? < end {
+                        let pos = reader.stream_position()?;
+                        let header = u16::read_options(reader, options, ())?;
+                        let count = usize::from(header & 0x1FFF);
+                        let command = header >> 13;
+                        if x + count > width {
+                            return Err(out_of_bounds(pos));
+                        }
+                        let index = y * width + x;
+                        match command {
+                            Self::DEPTH_COLOR | Self::DEPTH_COLOR_ALPHA | Self::COLOR => {
+                                let stride = match command {
+                                    Self::DEPTH_COLOR => 2,
+                                    Self::DEPTH_COLOR_ALPHA => 3,
+                                    _ => 1,
+                                };
+                                let mut bytes = vec![0u8; count * stride];
+                                reader.read_exact(&mut bytes)?;
+                                if bytes.len() % 2 == 1 {
+                                    reader.seek(SeekFrom::Current(1))?;
+                                }
+                                for (i, pixel) in bytes.chunks_exact(stride).enumerate() {
+                                    let color = pixel[stride.min(2) - 1];
+                                    self.color[index + i] = color;
+                                    if let Some(depth) = &mut self.depth {
+                                        depth[index + i] = if stride > 1 { pixel[0] } else { 0 };
+                                    }
+                                    if let Some(alpha) = &mut self.alpha {
+                                        alpha[index + i] = if stride == 3 {
+                                            pixel[2]
+                                        } else {
+                                            Self::MAX_ALPHA
+                                        };
+                                    }
+                                }
+                            }
+                            Self::TRANSPARENT => {}
+                            _ => {
+                                return Err(binrw::Error::AssertFail {
+                                    pos,
+                                    message: format!("Unknown sprite pixel command {}", command),
+                                })
+                            }
+                        }
+                        x += count;
+                    }
+                    y += 1;
+                }
+                Self::BLANK_ROWS => y += count,
+                Self::END => return Ok(()),
+                command => {
+                    return Err(binrw::Error::AssertFail {
+                        pos,
+                        message: format!("Unknown sprite row command {}", command),
+                    })
+                }
+            }
+        }
+    }
+}
+
+impl BinRead for SpriteFrame {
+    type Args = (Version,);
+
+    fn read_options<R: Read + Seek>(
+        reader: &mut R,
+        options: &ReadOptions,
+        args: Self::Args,
+    ) -> BinResult<Self> {
+        if args.0 == Version::OneThousandOne {
+            let _version = u32::read_options(reader, options, ())?;
+            let _size = u32::read_options(reader, options, ())?;
+        }
+        let width = u16::read_options(reader, options, ())?;
+        let height = u16::read_options(reader, options, ())?;
+        let flags = u32::read_options(reader, options, ())?;
+        let mut frame = SpriteFrame::new(
+            width,
+            height,
+            flags & Self::FLAG_DEPTH != 0,
+            flags & Self::FLAG_ALPHA != 0,
+        );
+        frame.palette_id = u16::read_options(reader, options, ())?;
+        frame.transparent_index = u16::read_options(reader, options, ())?;
+        frame.y = u16::read_options(reader, options, ())?;
+        frame.x = u16::read_options(reader, options, ())?;
+        for index in 0..frame.color.len() {
+            frame.clear_pixel(index);
+        }
+        frame.decode_pixels(reader, options)?;
+        Ok(frame)
+    }
+}
+
+impl BinWrite for SpriteFrame {
+    type Args = (Version,);
+
+    fn write_options<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+        args: Self::Args,
+    ) -> BinResult<()> {
+        let pixels = self.encode_pixels(writer.stream_position()?)?;
+        if args.0 == Version::OneThousandOne {
+            1001_u32.write_options(writer, options, ())?;
+            (16 + pixels.len() as u32).write_options(writer, options, ())?;
+        }
+        self.width.write_options(writer, options, ())?;
+        self.height.write_options(writer, options, ())?;
+        self.flags().write_options(writer, options, ())?;
+        self.palette_id.write_options(writer, options, ())?;
+        self.transparent_index.write_options(writer, options, ())?;
+        self.y.write_options(writer, options, ())?;
+        self.x.write_options(writer, options, ())?;
+        writer.write_all(&pixels)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+prop_compose! {
+    fn sprite_frame_mapper()(
+        width in 0..8_u16,
+        height in 0..8_u16,
+    )(
+        width in Just(width),
+        height in Just(height),
+        palette_id in any::<u16>(),
+        transparent_index in 0..4_u16,
+        y in any::<u16>(),
+        x in any::<u16>(),
+        color in vec(0..8_u8, usize::from(width) * usize::from(height)),
+        depth in proptest::option::of(vec(any::<u8>(), usize::from(width) * usize::from(height))),
+        alpha in proptest::option::of(vec(0..4_u8, usize::from(width) * usize::from(height))),
+    ) -> SpriteFrame {
+        let mut frame = SpriteFrame {
+            width,
+            height,
+            palette_id,
+            transparent_index,
+            y,
+            x,
+            color,
+            depth,
+            alpha,
+        };
+        // Transparent pixels only keep their transparency through a round trip
+        for index in 0..frame.color.len() {
+            if frame.is_transparent(index) {
+                frame.clear_pixel(index);
+            }
+        }
+        frame
+    }
+}
+
+#[cfg(test)]
+impl Arbitrary for SpriteFrame {
+    type Parameters = ();
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        sprite_frame_mapper().boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use crate::types::image_color_palette::Color;
+    use binrw::{BinReaderExt, BinWriterExt};
+
+    fn frame() -> SpriteFrame {
+        SpriteFrame {
+            width: 3,
+            height: 3,
+            palette_id: 0,
+            transparent_index: 0,
+            y: 0,
+            x: 0,
+            color: vec![0, 1, 0, 0, 0, 0, 2, 2, 0],
+            depth: Some(vec![0xFF, 0x10, 0xFF, 0xFF, 0xFF, 0xFF, 0x20, 0x21, 0xFF]),
+            alpha: None,
+        }
+    }
+
+    test_parsing!(
+        [
+            0x54, 0x65, 0x73, 0x74, 0x46, 0x69, 0x6C, 0x65, //TestFile - in ascii bytes
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // first padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // second padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // third padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fourth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fifth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sixth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // seventh padding block
+            0xE8, 0x03, 0x00, 0x00, // version
+            0x01, 0x00, 0x00, 0x00, // number of frames
+            0x01, 0x00, 0x00, 0x00, // palette id
+            0x50, 0x00, 0x00, 0x00, // frame offset
+            0x03, 0x00, 0x03, 0x00, // width, height
+            0x03, 0x00, 0x00, 0x00, // flags
+            0x00, 0x00, 0x00, 0x00, // palette id, transparent index
+            0x00, 0x00, 0x00, 0x00, // y, x
+            0x08, 0x00, // row, 8 bytes
+            0x01, 0x60, // 1 transparent pixel
+            0x01, 0x20, // 1 pixel with depth
+            0x10, 0x01, // depth, color
+            0x01, 0x80, // 1 blank row
+            0x08, 0x00, // row, 8 bytes
+            0x02, 0x20, // 2 pixels with depth
+            0x20, 0x02, 0x21, 0x02, // depth, color
+            0x00, 0xA0, // end
+        ],
+        Sprites {
+            file_name: "TestFile".to_string(),
+            version: Version::OneThousand,
+            palette_id: 1,
+            frames: vec![frame()],
+        },
+        Sprites,
+        spr2
+    );
+
+    test_parsing!(
+        [
+            0x54, 0x65, 0x73, 0x74, 0x46, 0x69, 0x6C, 0x65, //TestFile - in ascii bytes
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // first padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // second padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // third padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fourth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fifth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sixth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // seventh padding block
+            0xE9, 0x03, 0x00, 0x00, // version
+            0x02, 0x00, 0x00, 0x00, // palette id
+            0x01, 0x00, 0x00, 0x00, // number of frames
+            0xE9, 0x03, 0x00, 0x00, // frame version
+            0x24, 0x00, 0x00, 0x00, // frame size
+            0x03, 0x00, 0x03, 0x00, // width, height
+            0x03, 0x00, 0x00, 0x00, // flags
+            0x00, 0x00, 0x00, 0x00, // palette id, transparent index
+            0x00, 0x00, 0x00, 0x00, // y, x
+            0x08, 0x00, // row, 8 bytes
+            0x01, 0x60, // 1 transparent pixel
+            0x01, 0x20, // 1 pixel with depth
+            0x10, 0x01, // depth, color
+            0x01, 0x80, // 1 blank row
+            0x08, 0x00, // row, 8 bytes
+            0x02, 0x20, // 2 pixels with depth
+            0x20, 0x02, 0x21, 0x02, // depth, color
+            0x00, 0xA0, // end
+        ],
+        Sprites {
+            file_name: "TestFile".to_string(),
+            version: Version::OneThousandOne,
+            palette_id: 2,
+            frames: vec![frame()],
+        },
+        Sprites,
+        spr2_1001
+    );
+
+    #[test]
+    fn rgba_round_trip() {
+        let palette = ImageColorPalette {
+            file_name: String::new(),
+            version: 1,
+            reserved: [0; 8],
+            colors: vec![
+                Color::TRANSPARENT,
+                Color { r: 255, g: 0, b: 0 },
+                Color { r: 0, g: 0, b: 255 },
+            ],
+        };
+        let frame = frame();
+        let image = frame.to_rgba(&palette);
+
+        assert_eq!(image.pixel(0, 0), [0, 0, 0, 0]);
+        assert_eq!(image.pixel(1, 0), [255, 0, 0, 255]);
+        assert_eq!(image.pixel(1, 2), [0, 0, 255, 255]);
+        assert_eq!(
+            frame.depth_image().unwrap().pixel(1, 0),
+            [0x10, 0x10, 0x10, 255]
+        );
+
+        let encoded = SpriteFrame::from_rgba(&image, &palette, 0, frame.depth.as_deref());
+
+        assert_eq!(encoded, frame);
+    }
+
+    #[test]
+    fn translucent_pixels_keep_alpha() {
+        let mut frame = SpriteFrame::new(1, 1, false, true);
+        frame.color[0] = 1;
+        frame.alpha.as_mut().unwrap()[0] = 16;
+        let mut cursor = Cursor::new(vec![]);
+        cursor
+            .write_le_args(&frame, (Version::OneThousandOne,))
+            .unwrap();
+        cursor.set_position(0);
+        let out: SpriteFrame = cursor.read_le_args((Version::OneThousandOne,)).unwrap();
+
+        assert_eq!(out, frame);
+    }
+}
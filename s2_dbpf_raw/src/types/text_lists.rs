@@ -4,4 +4,485 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct TextList {}
+//! Localized string tables. Every string is tagged with a language, and the strings of each
+//! language are numbered separately, so the third English string and the third French string
+//! are translations of each other.
+
+use std::io::{Read, Seek, Write};
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId};
+use crate::types::util::parser_args::ParserArgs;
+use binrw::helpers::until_eof;
+use binrw::{binrw, BinRead, BinResult, BinWrite, NullString, ReadOptions, WriteOptions};
+#[cfg(test)]
+use proptest::collection::vec;
+#[cfg(test)]
+use proptest::prelude::*;
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type STR = TextList;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(_args: ParserArgs))]
+pub struct TextList {
+    #[br(try_map(NullString::try_into))]
+    #[bw(map(| x: &String | NullString::from(x.clone())))]
+    #[brw(pad_size_to = 64)]
+    #[cfg_attr(test, strategy("[a-zA-Z0-9 ]{0,63}"))]
+    pub file_name: String,
+    pub format: Format,
+    #[br(temp)]
+    #[bw(calc = unknown.as_ref().map_or(items.len() as u16, |unknown| unknown.count))]
+    num_items: u16,
+    #[br(if(format.is_known()), args { count: num_items as usize, inner: (format,) })]
+    #[bw(assert(
+        format.is_known() || items.is_empty(),
+        "strings can't be written in unknown STR# format {:?}",
+        format
+    ))]
+    #[bw(args_raw = (* format,))]
+    #[cfg_attr(test, strategy(vec(any_with::< TextItem > ((# format,)), (0..8))))]
+    pub items: Vec<TextItem>,
+    /// The strings of a format whose layout isn't known, kept as they were read
+    #[br(if(!format.is_known()), args(num_items))]
+    #[cfg_attr(test, strategy(Just(None)))]
+    pub unknown: Option<UnknownItems>,
+}
+
+impl DbpfEntry for TextList {
+    fn id(&self) -> DbpfId {
+        DbpfId::TextLists
+    }
+
+    fn name(&self) -> Option<String> {
+        Some(self.file_name.clone())
+    }
+}
+
+impl TextList {
+    /// The `index`th string in `language`.
+    #[must_use]
+    pub fn get(&self, index: usize, language: LanguageId) -> Option<&TextItem> {
+        self.items
+            .iter()
+            .filter(|item| item.language == language)
+            .nth(index)
+    }
+
+    /// The `index`th string in `language`, falling back to US English like the game does when a
+    /// translation is missing.
+    #[must_use]
+    pub fn text(&self, index: usize, language: LanguageId) -> Option<&str> {
+        self.get(index, language)
+            .or_else(|| self.get(index, LanguageId::ENGLISH_US))
+            .map(|item| item.value.as_str())
+    }
+
+    /// Every language with at least one string, in order of first appearance.
+    #[must_use]
+    pub fn languages(&self) -> Vec<LanguageId> {
+        let mut languages = vec![];
+        for item in &self.items {
+            if !languages.contains(&item.language) {
+                languages.push(item.language);
+            }
+        }
+        languages
+    }
+}
+
+/// The strings of a table whose format isn't one of [`Format`]'s known ones.
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[brw(little)]
+#[br(import(count: u16))]
+pub struct UnknownItems {
+    #[br(calc = count)]
+    #[bw(ignore)]
+    pub count: u16,
+    /// The rest of the resource
+    #[br(parse_with = until_eof)]
+    pub data: Vec<u8>,
+}
+
+/// How the strings of a table are stored. Only [`Format::Localized`] tables keep a language
+/// and only it and [`Format::Described`] keep descriptions, strings in the other formats read
+/// as US English without a description. Other formats are kept as [`UnknownItems`].
+#[binrw]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub enum Format {
+    /// Values prefixed with their length as a byte
+    #[brw(magic(0x0000_u16))]
+    Pascal,
+    /// Null terminated values
+    #[brw(magic(0xFFFF_u16))]
+    NullTerminated,
+    /// Null terminated values, each followed by its description
+    #[brw(magic(0xFFFE_u16))]
+    Described,
+    /// Language, value and description of every string, written by everything in The Sims 2
+    #[brw(magic(0xFFFD_u16))]
+    Localized,
+    #[cfg_attr(test, weight(0))]
+    Other(u16),
+}
+
+impl Format {
+    /// Whether the string layout of this format is known.
+    #[must_use]
+    pub fn is_known(self) -> bool {
+        !matches!(self, Format::Other(_))
+    }
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Format::Localized
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextItem {
+    pub language: LanguageId,
+    pub value: String,
+    pub description: String,
+}
+
+impl BinRead for TextItem {
+    type Args = (Format,);
+
+    fn read_options<R: Read + Seek>(
+        reader: &mut R,
+        options: &ReadOptions,
+        args: Self::Args,
+    ) -> BinResult<Self> {
+        let format = args.0;
+        let language = if format == Format::Localized {
+            LanguageId::read_options(reader, options, ())?
+        } else {
+            LanguageId::default()
+        };
+        let pos = reader.stream_position()?;
+        let value = if format == Format::Pascal {
+            let len = u8::read_options(reader, options, ())?;
+            let mut bytes = vec![0u8; usize::from(len)];
+            reader.read_exact(&mut bytes)?;
+            String::from_utf8(bytes).map_err(|err| binrw::Error::Custom {
+                pos,
+                err: Box::new(err),
+            })?
+        } else {
+            read_null_string(reader, options)?
+        };
+        let description = if matches!(format, Format::Described | Format::Localized) {
+            read_null_string(reader, options)?
+        } else {
+            String::new()
+        };
+        Ok(Self {
+            language,
+            value,
+            description,
+        })
+    }
+}
+
+impl BinWrite for TextItem {
+    type Args = (Format,);
+
+    fn write_options<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+        args: Self::Args,
+    ) -> BinResult<()> {
+        let format = args.0;
+        if format == Format::Localized {
+            self.language.write_options(writer, options, ())?;
+        }
+        if format == Format::Pascal {
+            let pos = writer.stream_position()?;
+            let len = u8::try_from(self.value.len()).map_err(|_| binrw::Error::AssertFail {
+                pos,
+                message: format!(
+                    "{} bytes is too long for a length prefixed string",
+                    self.value.len()
+                ),
+            })?;
+            len.write_options(writer, options, ())?;
+            self.value.as_bytes().write_options(writer, options, ())?;
+        } else {
+            NullString::from(self.value.clone()).write_options(writer, options, ())?;
+        }
+        if matches!(format, Format::Described | Format::Localized) {
+            NullString::from(self.description.clone()).write_options(writer, options, ())?;
+        }
+        Ok(())
+    }
+}
+
+fn read_null_string<R: Read + Seek>(reader: &mut R, options: &ReadOptions) -> BinResult<String> {
+    let pos = reader.stream_position()?;
+    NullString::read_options(reader, options, ())?
+        .try_into()
+        .map_err(|err| binrw::Error::Custom {
+            pos,
+            err: Box::new(err),
+        })
+}
+
+#[cfg(test)]
+prop_compose! {
+    fn text_item_mapper(
+        format: Format
+    )(
+        language in any::<LanguageId>(),
+        value in "[a-zA-Z0-9 ]{0,32}",
+        description in "[a-zA-Z0-9 ]{0,32}",
+    ) -> TextItem {
+        TextItem {
+            language: if format == Format::Localized {
+                language
+            } else {
+                LanguageId::default()
+            },
+            value,
+            description: if matches!(format, Format::Described | Format::Localized) {
+                description
+            } else {
+                String::new()
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+impl Arbitrary for TextItem {
+    type Parameters = (Format,);
+
+    fn arbitrary_with(args: Self::Parameters) -> Self::Strategy {
+        text_item_mapper(args.0).boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
+
+#[binrw]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct LanguageId(pub u8);
+
+impl LanguageId {
+    pub const ENGLISH_US: LanguageId = LanguageId(1);
+    pub const ENGLISH_UK: LanguageId = LanguageId(2);
+    pub const FRENCH: LanguageId = LanguageId(3);
+    pub const GERMAN: LanguageId = LanguageId(4);
+    pub const ITALIAN: LanguageId = LanguageId(5);
+    pub const SPANISH: LanguageId = LanguageId(6);
+    pub const DUTCH: LanguageId = LanguageId(7);
+    pub const DANISH: LanguageId = LanguageId(8);
+    pub const SWEDISH: LanguageId = LanguageId(9);
+    pub const NORWEGIAN: LanguageId = LanguageId(10);
+    pub const FINNISH: LanguageId = LanguageId(11);
+    pub const HEBREW: LanguageId = LanguageId(12);
+    pub const RUSSIAN: LanguageId = LanguageId(13);
+    pub const PORTUGUESE: LanguageId = LanguageId(14);
+    pub const JAPANESE: LanguageId = LanguageId(15);
+    pub const POLISH: LanguageId = LanguageId(16);
+    pub const SIMPLIFIED_CHINESE: LanguageId = LanguageId(17);
+    pub const TRADITIONAL_CHINESE: LanguageId = LanguageId(18);
+    pub const THAI: LanguageId = LanguageId(19);
+    pub const KOREAN: LanguageId = LanguageId(20);
+}
+
+impl Default for LanguageId {
+    fn default() -> Self {
+        LanguageId::ENGLISH_US
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+
+    fn item(language: LanguageId, value: &str) -> TextItem {
+        TextItem {
+            language,
+            value: value.to_string(),
+            description: String::new(),
+        }
+    }
+
+    test_parsing!(
+        [
+            0x54, 0x65, 0x73, 0x74, 0x46, 0x69, 0x6C, 0x65, //TestFile - in ascii bytes
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // first padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // second padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // third padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fourth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fifth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sixth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // seventh padding block
+            0xFD, 0xFF, // format
+            0x02, 0x00, // number of items
+            0x01, // language
+            0x48, 0x69, 0x00, // "Hi"
+            0x00, // description
+            0x03, // language
+            0x53, 0x61, 0x6C, 0x75, 0x74, 0x00, // "Salut"
+            0x64, 0x00, // "d"
+        ],
+        TextList {
+            file_name: "TestFile".to_string(),
+            format: Format::Localized,
+            items: vec![
+                item(LanguageId::ENGLISH_US, "Hi"),
+                TextItem {
+                    language: LanguageId::FRENCH,
+                    value: "Salut".to_string(),
+                    description: "d".to_string(),
+                },
+            ],
+            unknown: None,
+        },
+        TextList,
+        str
+    );
+
+    test_parsing!(
+        [
+            0x54, 0x65, 0x73, 0x74, 0x46, 0x69, 0x6C, 0x65, //TestFile - in ascii bytes
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // first padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // second padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // third padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fourth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fifth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sixth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // seventh padding block
+            0x00, 0x00, // format
+            0x01, 0x00, // number of items
+            0x02, 0x48, 0x69, // "Hi"
+        ],
+        TextList {
+            file_name: "TestFile".to_string(),
+            format: Format::Pascal,
+            items: vec![item(LanguageId::ENGLISH_US, "Hi")],
+            unknown: None,
+        },
+        TextList,
+        str_pascal
+    );
+
+    test_parsing!(
+        [
+            0x54, 0x65, 0x73, 0x74, 0x46, 0x69, 0x6C, 0x65, //TestFile - in ascii bytes
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // first padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // second padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // third padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fourth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fifth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sixth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // seventh padding block
+            0xFC, 0xFF, // format
+            0x01, 0x00, // number of items
+            0x01, 0x48, 0x69, 0x00, // string data
+        ],
+        TextList {
+            file_name: "TestFile".to_string(),
+            format: Format::Other(0xFFFC),
+            items: vec![],
+            unknown: Some(UnknownItems {
+                count: 1,
+                data: vec![0x01, 0x48, 0x69, 0x00],
+            }),
+        },
+        TextList,
+        str_unknown_format
+    );
+
+    test_parsing!(
+        [
+            0x54, 0x65, 0x73, 0x74, 0x46, 0x69, 0x6C, 0x65, //TestFile - in ascii bytes
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // first padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // second padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // third padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fourth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fifth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sixth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // seventh padding block
+            0xFF, 0xFF, // format
+            0x01, 0x00, // number of items
+            0x48, 0x69, 0x00, // "Hi"
+        ],
+        TextList {
+            file_name: "TestFile".to_string(),
+            format: Format::NullTerminated,
+            items: vec![item(LanguageId::ENGLISH_US, "Hi")],
+            unknown: None,
+        },
+        TextList,
+        str_null_terminated
+    );
+
+    test_parsing!(
+        [
+            0x54, 0x65, 0x73, 0x74, 0x46, 0x69, 0x6C, 0x65, //TestFile - in ascii bytes
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // first padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // second padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // third padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fourth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // fifth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // sixth padding block
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // seventh padding block
+            0xFE, 0xFF, // format
+            0x01, 0x00, // number of items
+            0x48, 0x69, 0x00, // "Hi"
+            0x64, 0x00, // "d"
+        ],
+        TextList {
+            file_name: "TestFile".to_string(),
+            format: Format::Described,
+            items: vec![TextItem {
+                language: LanguageId::ENGLISH_US,
+                value: "Hi".to_string(),
+                description: "d".to_string(),
+            }],
+            unknown: None,
+        },
+        TextList,
+        str_described
+    );
+
+    #[test]
+    fn lookup_by_language() {
+        let list = TextList {
+            file_name: String::new(),
+            format: Format::Localized,
+            items: vec![
+                item(LanguageId::ENGLISH_US, "Sit"),
+                item(LanguageId::GERMAN, "Setzen"),
+                item(LanguageId::ENGLISH_US, "Stand"),
+            ],
+            unknown: None,
+        };
+
+        assert_eq!(list.text(1, LanguageId::ENGLISH_US), Some("Stand"));
+        assert_eq!(list.text(0, LanguageId::GERMAN), Some("Setzen"));
+        assert_eq!(list.text(1, LanguageId::GERMAN), Some("Stand"));
+        assert_eq!(list.text(2, LanguageId::ENGLISH_US), None);
+        assert_eq!(
+            list.languages(),
+            vec![LanguageId::ENGLISH_US, LanguageId::GERMAN]
+        );
+    }
+}
@@ -4,4 +4,373 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct TextureResource {}
+//! Textures (TXTR), a `cImageData` block holding every mip level of an image. Large levels can
+//! be split out into LIFO resources and referenced by name.
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId, DbpfKind};
+use crate::types::package::database_packed_file::Dbpf;
+use crate::types::util::image::RgbaImage;
+use crate::types::util::parser_args::ParserArgs;
+use crate::types::util::resource_collection::{
+    read_optional_string, write_optional_string, BlockHeader, ResourceCollection, SgResource,
+};
+use crate::types::util::strings::{read_seven_bit_string, write_seven_bit_string};
+use crate::types::util::texture::TextureFormat;
+use binrw::binrw;
+use derive_more::{Deref, DerefMut};
+#[cfg(test)]
+use proptest::collection::vec;
+#[cfg(test)]
+use proptest::prelude::*;
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type TXTR = TextureResource;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Deref, DerefMut)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(args: ParserArgs))]
+pub struct TextureResource {
+    #[br(args_raw = args)]
+    pub collection: ResourceCollection,
+}
+
+impl DbpfEntry for TextureResource {
+    fn id(&self) -> DbpfId {
+        DbpfId::TextureResource
+    }
+
+    fn name(&self) -> Option<String> {
+        self.image_data()
+            .map(|image| image.resource.file_name.clone())
+    }
+}
+
+impl TextureResource {
+    /// Stores `image` in `format` with a full chain of mipmaps.
+    #[must_use]
+    pub fn from_rgba(file_name: String, image: &RgbaImage, format: TextureFormat) -> Self {
+        Self {
+            collection: ResourceCollection::new(vec![ImageData::from_rgba(
+                file_name, image, format,
+            )
+            .into()]),
+        }
+    }
+
+    #[must_use]
+    pub fn image_data(&self) -> Option<&ImageData> {
+        self.block()
+    }
+
+    pub fn image_data_mut(&mut self) -> Option<&mut ImageData> {
+        self.block_mut()
+    }
+
+    /// Decodes the largest mip level. LIFO references are looked up in `dbpf` if given.
+    #[must_use]
+    pub fn to_rgba(&self, dbpf: Option<&Dbpf>) -> Option<RgbaImage> {
+        self.image_data()?.level(0, dbpf)
+    }
+}
+
+/// The `cImageData` block.
+#[binrw]
+#[derive(Debug, Clone, PartialEq)]
+#[brw(little)]
+pub struct ImageData {
+    pub block: BlockHeader,
+    pub resource: SgResource,
+    pub width: u32,
+    pub height: u32,
+    pub format: TextureFormat,
+    pub mip_levels: u32,
+    pub object_width: f32,
+    #[br(temp)]
+    #[bw(calc = blocks.len() as u32)]
+    num_blocks: u32,
+    pub unknown: u32,
+    /// Version 9 repeats the file name here
+    #[br(parse_with = read_optional_string, args(block.version >= 9))]
+    #[bw(write_with = write_optional_string)]
+    pub file_name_repeat: Option<String>,
+    #[br(args { count: num_blocks as usize, inner: (block.version,) })]
+    pub blocks: Vec<MipMapBlock>,
+}
+
+impl ImageData {
+    #[must_use]
+    pub fn from_rgba(file_name: String, image: &RgbaImage, format: TextureFormat) -> Self {
+        let mut image_data = Self {
+            block: BlockHeader::of::<Self>(9),
+            resource: SgResource::new(file_name.clone()),
+            width: 0,
+            height: 0,
+            format,
+            mip_levels: 0,
+            object_width: 1.0,
+            unknown: 0,
+            file_name_repeat: Some(file_name),
+            blocks: vec![],
+        };
+        image_data.set_rgba(image);
+        image_data
+    }
+
+    /// Replaces the image, regenerating every mip level in the current format.
+    pub fn set_rgba(&mut self, image: &RgbaImage) {
+        let mut levels = vec![image.clone()];
+        while let Some(last) = levels
+            .last()
+            .filter(|last| last.width > 1 || last.height > 1)
+        {
+            levels.push(last.downsample());
+        }
+        self.width = image.width;
+        self.height = image.height;
+        self.mip_levels = levels.len() as u32;
+        self.blocks = vec![MipMapBlock {
+            mipmaps: levels
+                .iter()
+                .rev()
+                .map(|level| MipMap::Texture {
+                    data: self.format.encode(level),
+                })
+                .collect(),
+            creator: u32::MAX,
+            unknown: (self.block.version >= 8).then(|| 0),
+        }];
+    }
+
+    /// The mip levels, largest first.
+    pub fn mipmaps(&self) -> impl Iterator<Item = &MipMap> {
+        self.blocks
+            .first()
+            .into_iter()
+            .flat_map(|block| block.mipmaps.iter().rev())
+    }
+
+    /// The size of a mip level, counting from the largest.
+    #[must_use]
+    pub fn level_size(&self, level: usize) -> (u32, u32) {
+        let shift = |size: u32| size.checked_shr(level as u32).unwrap_or(0).max(1);
+        (shift(self.width), shift(self.height))
+    }
+
+    /// Decodes a mip level, counting from the largest. LIFO references are looked up in `dbpf`
+    /// by name, and are `None` without one.
+    #[must_use]
+    pub fn level(&self, level: usize, dbpf: Option<&Dbpf>) -> Option<RgbaImage> {
+        match self.mipmaps().nth(level)? {
+            MipMap::Texture { data } => {
+                let (width, height) = self.level_size(level);
+                self.format.decode(width, height, data)
+            }
+            MipMap::Lifo { name } => dbpf?
+                .entries
+                .values()
+                .find_map(|entry| match &entry.data {
+                    DbpfKind::LargeImage(lifo) => lifo
+                        .level_info()
+                        .filter(|level| level.resource.file_name == *name),
+                    _ => None,
+                })?
+                .to_rgba(self.format),
+        }
+    }
+}
+
+#[cfg(test)]
+prop_compose! {
+    fn image_data_mapper()(
+        version in 7_u32..10
+    )(
+        resource in any::<SgResource>(),
+        width in any::<u32>(),
+        height in any::<u32>(),
+        format in any::<TextureFormat>(),
+        mip_levels in any::<u32>(),
+        object_width in -100.0_f32..100.0,
+        unknown in any::<u32>(),
+        file_name_repeat in "[a-zA-Z0-9_]{0,32}",
+        blocks in vec(any_with::<MipMapBlock>((version,)), 0..3),
+        version in Just(version),
+    ) -> ImageData {
+        ImageData {
+            block: BlockHeader::of::<ImageData>(version),
+            resource,
+            width,
+            height,
+            format,
+            mip_levels,
+            object_width,
+            unknown,
+            file_name_repeat: (version >= 9).then(|| file_name_repeat),
+            blocks,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Arbitrary for ImageData {
+    type Parameters = ();
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        image_data_mapper().boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[brw(little)]
+#[br(import(version: u32))]
+#[cfg_attr(test, derive(Arbitrary))]
+#[cfg_attr(test, arbitrary(args = (u32,)))]
+pub struct MipMapBlock {
+    #[br(temp)]
+    #[bw(calc = mipmaps.len() as u32)]
+    num_mipmaps: u32,
+    /// Smallest first
+    #[br(count(num_mipmaps as usize))]
+    #[cfg_attr(test, strategy(vec(any::<MipMap>(), 0..4)))]
+    pub mipmaps: Vec<MipMap>,
+    pub creator: u32,
+    #[br(if(version >= 8))]
+    #[cfg_attr(test, strategy(any::<u32>().prop_map(move |x| (args.0 >= 8).then(|| x))))]
+    pub unknown: Option<u32>,
+}
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub enum MipMap {
+    #[brw(magic = 0u8)]
+    Texture {
+        #[br(temp)]
+        #[bw(calc = data.len() as u32)]
+        size: u32,
+        #[br(count(size as usize))]
+        #[cfg_attr(test, strategy(vec(any::<u8>(), 0..32)))]
+        data: Vec<u8>,
+    },
+    /// Stored in the LIFO with this name instead.
+    #[brw(magic = 1u8)]
+    Lifo {
+        #[br(parse_with = read_seven_bit_string)]
+        #[bw(write_with = write_seven_bit_string)]
+        #[cfg_attr(test, strategy("[a-zA-Z0-9_!#]{0,32}"))]
+        name: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{dbpf_from, test_parsing};
+    use crate::types::large_image::LargeImage;
+    use crate::types::package::database_packed_file::{GroupId, InstanceId, Key};
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+
+    test_parsing!(
+        [
+            0x00, 0x00, 0x00, 0x00, // number of links
+            0x01, 0x00, 0x00, 0x00, // number of blocks
+            0x6C, 0x27, 0x4A, 0x1C, // block id
+            0x0A, // name length
+            0x63, 0x49, 0x6D, 0x61, 0x67, 0x65, 0x44, 0x61, 0x74, 0x61, // cImageData
+            0x6C, 0x27, 0x4A, 0x1C, // block id
+            0x09, 0x00, 0x00, 0x00, // block version
+            0x0B, // name length
+            0x63, 0x53, 0x47, 0x52, 0x65, 0x73, 0x6F, 0x75, 0x72, 0x63, 0x65, // cSGResource
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x02, 0x00, 0x00, 0x00, // block version
+            0x01, 0x54, // T
+            0x02, 0x00, 0x00, 0x00, // width
+            0x01, 0x00, 0x00, 0x00, // height
+            0x07, 0x00, 0x00, 0x00, // format
+            0x02, 0x00, 0x00, 0x00, // mip levels
+            0x00, 0x00, 0x80, 0x3F, // object width
+            0x01, 0x00, 0x00, 0x00, // number of mipmap blocks
+            0x00, 0x00, 0x00, 0x00, // unknown
+            0x01, 0x54, // T
+            0x02, 0x00, 0x00, 0x00, // number of mipmaps
+            0x00, // texture
+            0x01, 0x00, 0x00, 0x00, // size
+            0x80, // 1x1 level
+            0x01, // LIFO
+            0x01, 0x4C, // L
+            0xFF, 0xFF, 0xFF, 0xFF, // creator
+            0x00, 0x00, 0x00, 0x00, // unknown
+        ],
+        TextureResource {
+            collection: ResourceCollection::new(vec![ImageData {
+                block: BlockHeader::of::<ImageData>(9),
+                resource: SgResource::new("T".to_string()),
+                width: 2,
+                height: 1,
+                format: TextureFormat::Raw8,
+                mip_levels: 2,
+                object_width: 1.0,
+                unknown: 0,
+                file_name_repeat: Some("T".to_string()),
+                blocks: vec![MipMapBlock {
+                    mipmaps: vec![
+                        MipMap::Texture { data: vec![0x80] },
+                        MipMap::Lifo {
+                            name: "L".to_string()
+                        }
+                    ],
+                    creator: u32::MAX,
+                    unknown: Some(0),
+                }],
+            }
+            .into()]),
+        },
+        TextureResource,
+        txtr
+    );
+
+    #[test]
+    fn mipmaps_are_generated() {
+        let mut image = RgbaImage::new(8, 4);
+        image.set_pixel(0, 0, [0xFF, 0, 0, 0xFF]);
+        let txtr = TextureResource::from_rgba("T".to_string(), &image, TextureFormat::Dxt5);
+        let image_data = txtr.image_data().unwrap();
+
+        assert_eq!(image_data.mip_levels, 4);
+        assert_eq!(image_data.level_size(3), (1, 1));
+        assert_eq!(txtr.to_rgba(None).unwrap().pixel(0, 0)[0], 0xFF);
+        assert_eq!(image_data.level(1, None).unwrap().width, 4);
+        assert_eq!(image_data.level(4, None), None);
+    }
+
+    #[test]
+    fn lifo_levels_are_resolved() {
+        let mut image = RgbaImage::new(2, 1);
+        image.set_pixel(0, 0, [0x00, 0x00, 0x00, 0xFF]);
+        image.set_pixel(1, 0, [0x40, 0x40, 0x40, 0xFF]);
+        let mut txtr = TextureResource::from_rgba("T".to_string(), &image, TextureFormat::Raw8);
+        txtr.image_data_mut().unwrap().blocks[0].mipmaps[1] = MipMap::Lifo {
+            name: "L".to_string(),
+        };
+        let lifo = LargeImage::from_rgba("L".to_string(), &image, TextureFormat::Raw8);
+        let dbpf = dbpf_from(vec![(
+            Key {
+                kind: DbpfId::LargeImage,
+                group_id: GroupId(1),
+                instance_id: InstanceId(1),
+                resource_id: None,
+            },
+            DbpfKind::LargeImage(lifo),
+        )]);
+
+        assert_eq!(txtr.to_rgba(None), None);
+        assert_eq!(txtr.to_rgba(Some(&dbpf)), Some(image));
+    }
+}
@@ -4,4 +4,79 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct UnknownObject {}
+//! Object XML (XOBJ), a catalog entry for build mode items such as walls and floors.
+
+use crate::constants::data_kinds::{DbpfEntry, DbpfId};
+use crate::types::util::cpf::Cpf;
+use crate::types::util::parser_args::ParserArgs;
+use binrw::binrw;
+use derive_more::{Deref, DerefMut};
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub type XOBJ = UnknownObject;
+
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Default, Deref, DerefMut)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+#[br(import_raw(args: ParserArgs))]
+pub struct UnknownObject {
+    #[br(args_raw = args)]
+    pub cpf: Cpf,
+}
+
+impl DbpfEntry for UnknownObject {
+    fn id(&self) -> DbpfId {
+        DbpfId::UnknownObject
+    }
+
+    fn name(&self) -> Option<String> {
+        self.cpf.name().map(ToString::to_string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use crate::types::util::cpf::{CpfFormat, CpfProperty, CpfValue};
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+    use proptest::prelude::*;
+
+    const XML: &[u8] = b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<cGZPropertySetString>
+  <AnyString key=\"type\" type=\"0x0b8bea18\">floor</AnyString>
+  <AnyString key=\"name\" type=\"0x0b8bea18\">floor_wood</AnyString>
+  <AnyUint32 key=\"cost\" type=\"0xeb61e4f7\">12</AnyUint32>
+</cGZPropertySetString>
+";
+
+    test_parsing!(
+        XML,
+        UnknownObject {
+            cpf: Cpf {
+                format: CpfFormat::Xml {
+                    root: "cGZPropertySetString".to_string(),
+                },
+                properties: vec![
+                    CpfProperty {
+                        name: "type".to_string(),
+                        value: CpfValue::String("floor".to_string()),
+                    },
+                    CpfProperty {
+                        name: "name".to_string(),
+                        value: CpfValue::String("floor_wood".to_string()),
+                    },
+                    CpfProperty {
+                        name: "cost".to_string(),
+                        value: CpfValue::Uint(12),
+                    },
+                ],
+            },
+        },
+        UnknownObject,
+        xobj
+    );
+}
@@ -0,0 +1,653 @@
+////////////////////////////////////////////////////////////////////////////////
+// This Source Code Form is subject to the terms of the Mozilla Public         /
+// License, v. 2.0. If a copy of the MPL was not distributed with this         /
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
+////////////////////////////////////////////////////////////////////////////////
+
+//! The CPF property container property sets, catalog XML resources and the binary index share:
+//! an ordered list of named, typed values, stored either as binary or as XML.
+
+use std::fmt::Write as _;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use crate::error::{DbpfResult, Error};
+use crate::types::util::parser_args::ParserArgs;
+use binrw::{BinRead, BinResult, BinWrite, ReadOptions, WriteOptions};
+#[cfg(test)]
+use proptest::collection::vec;
+#[cfg(test)]
+use proptest::prelude::*;
+use quick_xml::events::{BytesStart, Event};
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+pub const CPF_MAGIC: u32 = 0xCBE7_50E0;
+
+/// Flags for the `age` property.
+pub mod age {
+    pub const TODDLER: u32 = 0x01;
+    pub const CHILD: u32 = 0x02;
+    pub const TEEN: u32 = 0x04;
+    pub const ADULT: u32 = 0x08;
+    pub const ELDER: u32 = 0x10;
+    pub const BABY: u32 = 0x20;
+    pub const YOUNG_ADULT: u32 = 0x40;
+}
+
+/// Flags for the `gender` property.
+pub mod gender {
+    pub const FEMALE: u32 = 0x01;
+    pub const MALE: u32 = 0x02;
+}
+
+/// Flags for the `category` property.
+pub mod category {
+    pub const EVERYDAY: u32 = 0x01;
+    pub const SWIMWEAR: u32 = 0x02;
+    pub const PAJAMAS: u32 = 0x04;
+    pub const FORMAL: u32 = 0x08;
+    pub const UNDERWEAR: u32 = 0x10;
+    pub const SKIN: u32 = 0x20;
+    pub const MATERNITY: u32 = 0x40;
+    pub const ATHLETIC: u32 = 0x80;
+    pub const TRY_ON: u32 = 0x100;
+    pub const NAKED_OVERLAY: u32 = 0x200;
+    pub const OUTERWEAR: u32 = 0x400;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(test, derive(Arbitrary))]
+pub struct Cpf {
+    /// XML trims the whitespace around values, so only binary is generated for tests
+    #[cfg_attr(test, strategy((1_u16..3).prop_map(|version| CpfFormat::Binary { version })))]
+    pub format: CpfFormat,
+    #[cfg_attr(test, strategy(vec(any::<CpfProperty>(), 0..5)))]
+    pub properties: Vec<CpfProperty>,
+}
+
+impl Default for Cpf {
+    fn default() -> Self {
+        Self {
+            format: CpfFormat::Binary { version: 2 },
+            properties: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CpfFormat {
+    Binary {
+        version: u16,
+    },
+    /// XML, with the name of its root element
+    Xml {
+        root: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(test, derive(Arbitrary))]
+pub struct CpfProperty {
+    #[cfg_attr(test, strategy("[a-zA-Z0-9_]{1,16}"))]
+    pub name: String,
+    pub value: CpfValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(test, derive(Arbitrary))]
+pub enum CpfValue {
+    Uint(u32),
+    Int(i32),
+    Float(#[cfg_attr(test, strategy(-1000.0_f32..1000.0))] f32),
+    Bool(bool),
+    String(#[cfg_attr(test, strategy("[a-zA-Z0-9_ ]{0,16}"))] String),
+}
+
+impl CpfValue {
+    pub const UINT: u32 = 0xEB61_E4F7;
+    pub const INT: u32 = 0x0C26_4712;
+    pub const FLOAT: u32 = 0xABC7_8708;
+    pub const BOOL: u32 = 0xCBA9_08E1;
+    pub const STRING: u32 = 0x0B8B_EA18;
+
+    /// The type id stored with the value.
+    #[must_use]
+    pub fn type_id(&self) -> u32 {
+        match self {
+            CpfValue::Uint(_) => Self::UINT,
+            CpfValue::Int(_) => Self::INT,
+            CpfValue::Float(_) => Self::FLOAT,
+            CpfValue::Bool(_) => Self::BOOL,
+            CpfValue::String(_) => Self::STRING,
+        }
+    }
+
+    /// The value as an unsigned integer, converting from a non-negative signed one.
+    #[must_use]
+    pub fn as_uint(&self) -> Option<u32> {
+        match self {
+            CpfValue::Uint(value) => Some(*value),
+            CpfValue::Int(value) => u32::try_from(*value).ok(),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn as_int(&self) -> Option<i32> {
+        match self {
+            CpfValue::Int(value) => Some(*value),
+            CpfValue::Uint(value) => i32::try_from(*value).ok(),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn as_float(&self) -> Option<f32> {
+        match self {
+            CpfValue::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            CpfValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            CpfValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The element name used in the XML form.
+    fn tag(&self) -> &'static str {
+        match self {
+            CpfValue::Uint(_) => "AnyUint32",
+            CpfValue::Int(_) => "AnySint32",
+            CpfValue::Float(_) => "AnyFloat32",
+            CpfValue::Bool(_) => "AnyBoolean",
+            CpfValue::String(_) => "AnyString",
+        }
+    }
+
+    fn parse(type_id: u32, text: &str) -> Option<Self> {
+        let text = text.trim();
+        Some(match type_id {
+            Self::UINT => CpfValue::Uint(match text.strip_prefix("0x") {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => text.parse().ok()?,
+            }),
+            Self::INT => CpfValue::Int(text.parse().ok()?),
+            Self::FLOAT => CpfValue::Float(text.parse().ok()?),
+            Self::BOOL => CpfValue::Bool(match text.to_lowercase().as_str() {
+                "true" | "1" => true,
+                "false" | "0" => false,
+                _ => return None,
+            }),
+            _ => CpfValue::String(text.to_string()),
+        })
+    }
+
+    fn to_text(&self) -> String {
+        match self {
+            CpfValue::Uint(value) => value.to_string(),
+            CpfValue::Int(value) => value.to_string(),
+            CpfValue::Float(value) => value.to_string(),
+            CpfValue::Bool(value) => value.to_string(),
+            CpfValue::String(value) => value.clone(),
+        }
+    }
+}
+
+impl Cpf {
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&CpfValue> {
+        self.properties
+            .iter()
+            .find(|property| property.name == name)
+            .map(|property| &property.value)
+    }
+
+    /// Sets the property called `name`, keeping its position if it already exists and adding it
+    /// at the end otherwise.
+    pub fn set(&mut self, name: &str, value: CpfValue) {
+        match self
+            .properties
+            .iter_mut()
+            .find(|property| property.name == name)
+        {
+            Some(property) => property.value = value,
+            None => self.properties.push(CpfProperty {
+                name: name.to_string(),
+                value,
+            }),
+        }
+    }
+
+    /// Removes the property called `name`, returning its value.
+    pub fn remove(&mut self, name: &str) -> Option<CpfValue> {
+        let index = self
+            .properties
+            .iter()
+            .position(|property| property.name == name)?;
+        Some(self.properties.remove(index).value)
+    }
+
+    #[must_use]
+    pub fn uint(&self, name: &str) -> Option<u32> {
+        self.get(name)?.as_uint()
+    }
+
+    #[must_use]
+    pub fn string(&self, name: &str) -> Option<&str> {
+        self.get(name)?.as_str()
+    }
+
+    /// The ages the resource applies to, a combination of the [`age`] flags.
+    #[must_use]
+    pub fn age(&self) -> Option<u32> {
+        self.uint("age")
+    }
+
+    pub fn set_age(&mut self, age: u32) {
+        self.set("age", CpfValue::Uint(age));
+    }
+
+    /// The genders the resource applies to, a combination of the [`gender`] flags.
+    #[must_use]
+    pub fn gender(&self) -> Option<u32> {
+        self.uint("gender")
+    }
+
+    pub fn set_gender(&mut self, gender: u32) {
+        self.set("gender", CpfValue::Uint(gender));
+    }
+
+    /// The outfit categories the resource shows up in, a combination of the [`category`] flags.
+    #[must_use]
+    pub fn category(&self) -> Option<u32> {
+        self.uint("category")
+    }
+
+    pub fn set_category(&mut self, category: u32) {
+        self.set("category", CpfValue::Uint(category));
+    }
+
+    /// The family the resource belongs to, a GUID shared by recolors of the same item.
+    #[must_use]
+    pub fn family(&self) -> Option<&str> {
+        self.string("family")
+    }
+
+    pub fn set_family(&mut self, family: String) {
+        self.set("family", CpfValue::String(family));
+    }
+
+    #[must_use]
+    pub fn name(&self) -> Option<&str> {
+        self.string("name")
+    }
+
+    pub fn set_name(&mut self, name: String) {
+        self.set("name", CpfValue::String(name));
+    }
+
+    /// Parses the XML form.
+    ///
+    /// # Errors
+    /// Returns [`Error::XmlError`] if the XML is malformed, and [`Error::CpfError`] if a value
+    /// doesn't match its type.
+    pub fn from_xml(xml: &str) -> DbpfResult<Self> {
+        let mut reader = quick_xml::Reader::from_str(xml);
+        let mut root = None;
+        let mut properties = vec![];
+        // The key and type of the element being read, and its text so far
+        let mut current: Option<(String, u32, String)> = None;
+        loop {
+            match reader.read_event()? {
+                Event::Start(element) | Event::Empty(element) if root.is_none() => {
+                    root = Some(String::from_utf8_lossy(element.name().as_ref()).into_owned());
+                }
+                Event::Start(element) => {
+                    let (key, type_id) = property_element(&element)?;
+                    current = Some((key, type_id, String::new()));
+                }
+                // A self-closing property has an empty value
+                Event::Empty(element) => {
+                    let (name, type_id) = property_element(&element)?;
+                    properties.push(CpfProperty {
+                        value: parse_value(&name, type_id, "")?,
+                        name,
+                    });
+                }
+                Event::Text(text) => {
+                    if let Some((_, _, value)) = &mut current {
+                        value.push_str(&text.unescape()?);
+                    }
+                }
+                Event::CData(text) => {
+                    if let Some((_, _, value)) = &mut current {
+                        value.push_str(&String::from_utf8_lossy(&text));
+                    }
+                }
+                Event::End(_) => {
+                    if let Some((name, type_id, text)) = current.take() {
+                        properties.push(CpfProperty {
+                            value: parse_value(&name, type_id, &text)?,
+                            name,
+                        });
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        Ok(Self {
+            format: CpfFormat::Xml {
+                root: root.ok_or_else(|| Error::CpfError("missing root element".to_string()))?,
+            },
+            properties,
+        })
+    }
+
+    /// The XML form, using the root element of the XML this was read from, or
+    /// `cGZPropertySetString` if it was binary.
+    #[must_use]
+    pub fn to_xml(&self) -> String {
+        let root = match &self.format {
+            CpfFormat::Xml { root } => root.as_str(),
+            CpfFormat::Binary { .. } => "cGZPropertySetString",
+        };
+        let mut xml = format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<{}>\n", root);
+        for property in &self.properties {
+            // Writing to a String can't fail
+            let _ = writeln!(
+                xml,
+                "  <{tag} key=\"{}\" type=\"0x{:08x}\">{}</{tag}>",
+                quick_xml::escape::escape(&property.name),
+                property.value.type_id(),
+                quick_xml::escape::escape(&property.value.to_text()),
+                tag = property.value.tag(),
+            );
+        }
+        let _ = writeln!(xml, "</{}>", root);
+        xml
+    }
+}
+
+/// The key of a property element and its type, from the `type` attribute or else the element
+/// name.
+fn property_element(element: &BytesStart) -> DbpfResult<(String, u32)> {
+    let mut key = String::new();
+    let mut type_id = None;
+    for attribute in element.attributes() {
+        let attribute = attribute.map_err(quick_xml::Error::from)?;
+        let value = attribute.unescape_value()?;
+        match attribute.key.as_ref() {
+            b"key" => key = value.into_owned(),
+            b"type" => {
+                type_id = value
+                    .strip_prefix("0x")
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok());
+            }
+            _ => {}
+        }
+    }
+    let type_id = type_id.unwrap_or(match element.name().as_ref() {
+        b"AnyUint32" => CpfValue::UINT,
+        b"AnySint32" => CpfValue::INT,
+        b"AnyFloat32" => CpfValue::FLOAT,
+        b"AnyBoolean" => CpfValue::BOOL,
+        _ => CpfValue::STRING,
+    });
+    Ok((key, type_id))
+}
+
+fn parse_value(name: &str, type_id: u32, text: &str) -> DbpfResult<CpfValue> {
+    CpfValue::parse(type_id, text)
+        .ok_or_else(|| Error::CpfError(format!("invalid value for {}: {}", name, text)))
+}
+
+impl BinRead for Cpf {
+    type Args = ParserArgs;
+
+    fn read_options<R: Read + Seek>(
+        reader: &mut R,
+        options: &ReadOptions,
+        _args: Self::Args,
+    ) -> BinResult<Self> {
+        let pos = reader.stream_position()?;
+        if u32::read_options(reader, options, ())? != CPF_MAGIC {
+            // XML runs to the end of the resource, which packages read from a buffer of its own
+            reader.seek(SeekFrom::Start(pos))?;
+            let mut data = vec![];
+            reader.read_to_end(&mut data)?;
+            let xml = String::from_utf8_lossy(&data);
+            return Self::from_xml(xml.trim_end_matches('\0')).map_err(|err| {
+                binrw::Error::Custom {
+                    pos,
+                    err: Box::new(err),
+                }
+            });
+        }
+
+        let version = u16::read_options(reader, options, ())?;
+        let count = u32::read_options(reader, options, ())?;
+        let properties = (0..count)
+            .map(|_| {
+                let pos = reader.stream_position()?;
+                let type_id = u32::read_options(reader, options, ())?;
+                let name = read_string(reader, options)?;
+                let value = match type_id {
+                    CpfValue::UINT => CpfValue::Uint(u32::read_options(reader, options, ())?),
+                    CpfValue::INT => CpfValue::Int(i32::read_options(reader, options, ())?),
+                    CpfValue::FLOAT => CpfValue::Float(f32::read_options(reader, options, ())?),
+                    CpfValue::BOOL => CpfValue::Bool(u8::read_options(reader, options, ())? != 0),
+                    CpfValue::STRING => CpfValue::String(read_string(reader, options)?),
+                    _ => {
+                        return Err(binrw::Error::AssertFail {
+                            pos,
+                            message: format!("Unknown property type {:#010x}", type_id),
+                        })
+                    }
+                };
+                Ok(CpfProperty { name, value })
+            })
+            .collect::<BinResult<_>>()?;
+        Ok(Self {
+            format: CpfFormat::Binary { version },
+            properties,
+        })
+    }
+}
+
+impl BinWrite for Cpf {
+    type Args = ();
+
+    fn write_options<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+        _args: Self::Args,
+    ) -> BinResult<()> {
+        let version = match &self.format {
+            CpfFormat::Binary { version } => *version,
+            CpfFormat::Xml { .. } => {
+                writer.write_all(self.to_xml().as_bytes())?;
+                return Ok(());
+            }
+        };
+        CPF_MAGIC.write_options(writer, options, ())?;
+        version.write_options(writer, options, ())?;
+        (self.properties.len() as u32).write_options(writer, options, ())?;
+        for property in &self.properties {
+            property
+                .value
+                .type_id()
+                .write_options(writer, options, ())?;
+            write_string(&property.name, writer, *options)?;
+            match &property.value {
+                CpfValue::Uint(value) => value.write_options(writer, options, ())?,
+                CpfValue::Int(value) => value.write_options(writer, options, ())?,
+                CpfValue::Float(value) => value.write_options(writer, options, ())?,
+                CpfValue::Bool(value) => u8::from(*value).write_options(writer, options, ())?,
+                CpfValue::String(value) => write_string(value, writer, *options)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Binary CPF strings are prefixed with a 32 bit length.
+fn read_string<R: Read + Seek>(reader: &mut R, options: &ReadOptions) -> BinResult<String> {
+    let pos = reader.stream_position()?;
+    let len = u32::read_options(reader, options, ())?;
+    let mut bytes = vec![0u8; len as usize];
+    reader.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map_err(|err| binrw::Error::Custom {
+        pos,
+        err: Box::new(err),
+    })
+}
+
+fn write_string<W: Write + Seek>(
+    string: &str,
+    writer: &mut W,
+    options: WriteOptions,
+) -> BinResult<()> {
+    (string.len() as u32).write_options(writer, &options, ())?;
+    writer.write_all(string.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+
+    test_parsing!(
+        [
+            0xE0, 0x50, 0xE7, 0xCB, // magic
+            0x02, 0x00, // version
+            0x02, 0x00, 0x00, 0x00, // number of properties
+            0xF7, 0xE4, 0x61, 0xEB, // uint
+            0x03, 0x00, 0x00, 0x00, // name length
+            0x61, 0x67, 0x65, // age
+            0x08, 0x00, 0x00, 0x00, // value
+            0x18, 0xEA, 0x8B, 0x0B, // string
+            0x04, 0x00, 0x00, 0x00, // name length
+            0x6E, 0x61, 0x6D, 0x65, // name
+            0x01, 0x00, 0x00, 0x00, // value length
+            0x61, // a
+        ],
+        Cpf {
+            format: CpfFormat::Binary { version: 2 },
+            properties: vec![
+                CpfProperty {
+                    name: "age".to_string(),
+                    value: CpfValue::Uint(age::ADULT),
+                },
+                CpfProperty {
+                    name: "name".to_string(),
+                    value: CpfValue::String("a".to_string()),
+                },
+            ],
+        },
+        Cpf,
+        cpf
+    );
+
+    const XML: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<cGZPropertySetString>
+  <AnyUint32 key=\"age\" type=\"0xeb61e4f7\">8</AnyUint32>
+  <AnySint32 key=\"layer\" type=\"0x0c264712\">-2</AnySint32>
+  <AnyFloat32 key=\"scale\" type=\"0xabc78708\">1.5</AnyFloat32>
+  <AnyBoolean key=\"enabled\" type=\"0xcba908e1\">true</AnyBoolean>
+  <AnyString key=\"name\" type=\"0x0b8bea18\">Tom &amp; Jerry</AnyString>
+</cGZPropertySetString>
+";
+
+    #[test]
+    fn xml_round_trip() {
+        let cpf = Cpf::from_xml(XML).unwrap();
+
+        assert_eq!(
+            cpf.format,
+            CpfFormat::Xml {
+                root: "cGZPropertySetString".to_string()
+            }
+        );
+        assert_eq!(cpf.age(), Some(age::ADULT));
+        assert_eq!(cpf.get("layer"), Some(&CpfValue::Int(-2)));
+        assert_eq!(cpf.get("scale"), Some(&CpfValue::Float(1.5)));
+        assert_eq!(cpf.get("enabled"), Some(&CpfValue::Bool(true)));
+        assert_eq!(cpf.name(), Some("Tom & Jerry"));
+        assert_eq!(cpf.to_xml(), XML);
+    }
+
+    #[test]
+    fn xml_without_type_attributes() {
+        let cpf = Cpf::from_xml(
+            "<cGZPropertySetString><AnyUint32 key=\"gender\">0x02</AnyUint32>\
+             <AnyString key=\"family\"/></cGZPropertySetString>",
+        )
+        .unwrap();
+
+        assert_eq!(cpf.gender(), Some(gender::MALE));
+        assert_eq!(cpf.family(), Some(""));
+    }
+
+    #[test]
+    fn read_xml_resource() {
+        let cpf: Cpf = Cursor::new(XML.as_bytes()).read_le().unwrap();
+
+        let mut cursor = Cursor::new(vec![]);
+        cursor.write_le(&cpf).unwrap();
+        assert_eq!(cursor.into_inner(), XML.as_bytes());
+    }
+
+    #[test]
+    fn xml_stops_at_the_end_of_its_entry() {
+        use crate::constants::data_kinds::{DbpfId, DbpfKind};
+        use crate::test_helpers::package_bytes;
+        use crate::types::package::database_packed_file::{Dbpf, GroupId, InstanceId, Key};
+
+        let key = |instance| Key::new(DbpfId::PropertySet, GroupId(1), InstanceId(instance), None);
+        let bytes = package_bytes(&[
+            (key(1), XML.as_bytes().to_vec()),
+            (key(2), XML.replace("Tom", "Tim").into_bytes()),
+        ]);
+        let dbpf: Dbpf = Cursor::new(bytes).read_le().unwrap();
+
+        for (instance, xml) in [(1, XML.to_string()), (2, XML.replace("Tom", "Tim"))] {
+            match &dbpf.entries[&key(instance)].data {
+                DbpfKind::PropertySet(gzps) => assert_eq!(gzps.cpf.to_xml(), xml),
+                other => panic!("{:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn set_keeps_order() {
+        let mut cpf = Cpf::from_xml(XML).unwrap();
+
+        cpf.set_age(age::TEEN | age::ADULT);
+        cpf.set_category(category::FORMAL);
+
+        assert_eq!(
+            cpf.properties[0].value,
+            CpfValue::Uint(age::TEEN | age::ADULT)
+        );
+        assert_eq!(cpf.properties[5].name, "category");
+        assert_eq!(cpf.remove("layer"), Some(CpfValue::Int(-2)));
+        assert_eq!(cpf.properties.len(), 5);
+    }
+}
@@ -0,0 +1,1064 @@
+////////////////////////////////////////////////////////////////////////////////
+// This Source Code Form is subject to the terms of the Mozilla Public         /
+// License, v. 2.0. If a copy of the MPL was not distributed with this         /
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
+////////////////////////////////////////////////////////////////////////////////
+
+//! glTF 2.0 export and import of meshes, including skinning, and export of skeletons and their
+//! animations. Exports are self-contained binary (`.glb`) files; imports accept those as well as
+//! `.gltf` files with embedded buffers.
+
+use serde_json::{json, Value};
+
+use crate::error::{DbpfResult, Error};
+use crate::types::package::database_packed_file::{Dbpf, Key};
+use crate::types::scenegraph::creation_resource::Bone;
+use crate::types::scenegraph::geometric_data_container::{Mesh, Transform};
+use crate::types::scenegraph::resolver::Scenegraph;
+
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const JSON_CHUNK: u32 = 0x4E4F_534A;
+const BIN_CHUNK: u32 = 0x004E_4942;
+
+const UNSIGNED_BYTE: u64 = 5121;
+const UNSIGNED_SHORT: u64 = 5123;
+const UNSIGNED_INT: u64 = 5125;
+const FLOAT: u64 = 5126;
+
+const ARRAY_BUFFER: u64 = 34962;
+const ELEMENT_ARRAY_BUFFER: u64 = 34963;
+const TRIANGLES: u64 = 4;
+
+/// The rate animation frames play at.
+pub const ANIMATION_FRAME_RATE: f32 = 30.0;
+
+/// A bone animation to export.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Clip {
+    pub name: String,
+    pub tracks: Vec<Track>,
+}
+
+/// The keyframes of the bone called `bone`, ignoring case, at [`ANIMATION_FRAME_RATE`] frames
+/// per second.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Track {
+    pub bone: String,
+    /// Frames and translations relative to the parent bone
+    pub translations: Vec<(u16, [f32; 3])>,
+    /// Frames and rotations relative to the parent bone, as x y z w quaternions
+    pub rotations: Vec<(u16, [f32; 4])>,
+}
+
+/// Writes meshes as a binary glTF file. `joints` are the model's joint transforms; every joint
+/// becomes a node of a single skin, with the transform as its inverse bind matrix, so skinned
+/// meshes show up in their rest pose.
+#[must_use]
+pub fn to_glb(meshes: &[Mesh], joints: &[Transform]) -> Vec<u8> {
+    let mut builder = Builder::default();
+    let skinned = !joints.is_empty() && meshes.iter().any(|mesh| !mesh.joints.is_empty());
+    let (mut nodes, gltf_meshes) = push_meshes(&mut builder, meshes, skinned);
+
+    let mut roots = (0..nodes.len()).collect::<Vec<_>>();
+    let mut skins = vec![];
+    if skinned {
+        let first_joint = nodes.len();
+        let matrices = joints
+            .iter()
+            .flat_map(|transform| matrix(transform.rotation, transform.translation))
+            .collect::<Vec<_>>();
+        let inverse_bind_matrices = builder.push(
+            &floats(matrices.iter()),
+            FLOAT,
+            "MAT4",
+            joints.len(),
+            None,
+            None,
+        );
+        for (index, transform) in joints.iter().enumerate() {
+            let inverse = conjugate(transform.rotation);
+            let [tx, ty, tz] = rotate(inverse, transform.translation);
+            nodes.push(json!({
+                "name": format!("joint{}", index),
+                "rotation": inverse,
+                "translation": [-tx, -ty, -tz],
+            }));
+        }
+        let joint_nodes = (first_joint..nodes.len()).collect::<Vec<_>>();
+        nodes.push(json!({ "name": "skeleton", "children": joint_nodes }));
+        roots.push(nodes.len() - 1);
+        skins.push(json!({
+            "inverseBindMatrices": inverse_bind_matrices,
+            "joints": joint_nodes,
+        }));
+    }
+
+    finish(builder, &roots, &nodes, gltf_meshes, skins, vec![])
+}
+
+/// Writes meshes skinned to a skeleton, such as [`CreationResource::skeleton`], as a binary glTF
+/// file, with each clip as an animation of the skeleton's nodes.
+///
+/// Bones are nodes in their rest pose, and the joints of the skin are the bones marked as
+/// joints, in joint order. Clip tracks drive the bone with the same name, at
+/// [`ANIMATION_FRAME_RATE`] frames per second; tracks without a matching bone are left out.
+///
+/// [`CreationResource::skeleton`]: crate::types::scenegraph::creation_resource::CreationResource::skeleton
+#[must_use]
+pub fn skeleton_to_glb(meshes: &[Mesh], bones: &[Bone], clips: &[Clip]) -> Vec<u8> {
+    let mut builder = Builder::default();
+    let joint_count = bones
+        .iter()
+        .filter_map(|bone| bone.joint)
+        .max()
+        .map_or(0, |joint| joint as usize + 1);
+    let skinned = joint_count > 0 && meshes.iter().any(|mesh| !mesh.joints.is_empty());
+    let (mut nodes, gltf_meshes) = push_meshes(&mut builder, meshes, skinned);
+
+    let mut roots = (0..nodes.len()).collect::<Vec<_>>();
+    let first_bone = nodes.len();
+    nodes.extend(bones.iter().map(|bone| {
+        json!({
+            "name": bone.name,
+            "translation": bone.translation,
+            "rotation": bone.rotation,
+        })
+    }));
+    // Parents can come after their children, so they're attached once every bone has a node
+    for (index, bone) in bones.iter().enumerate() {
+        match bone.parent {
+            Some(parent) if parent < bones.len() && !in_cycle(bones, index) => {
+                let children = nodes[first_bone + parent]
+                    .as_object_mut()
+                    .map(|node| node.entry("children").or_insert_with(|| json!([])));
+                if let Some(Value::Array(children)) = children {
+                    children.push(json!(first_bone + index));
+                }
+            }
+            _ => roots.push(first_bone + index),
+        }
+    }
+
+    let mut skins = vec![];
+    if joint_count > 0 {
+        let world = world_transforms(bones);
+        // Joint indices without a bone are bound to the first bone
+        let mut joints = vec![0; joint_count];
+        for (index, bone) in bones.iter().enumerate() {
+            if let Some(joint) = bone.joint {
+                joints[joint as usize] = index;
+            }
+        }
+        let matrices = joints
+            .iter()
+            .flat_map(|&bone| {
+                let (rotation, translation) = world[bone];
+                let inverse = conjugate(rotation);
+                let [tx, ty, tz] = rotate(inverse, translation);
+                matrix(inverse, [-tx, -ty, -tz])
+            })
+            .collect::<Vec<_>>();
+        let inverse_bind_matrices = builder.push(
+            &floats(matrices.iter()),
+            FLOAT,
+            "MAT4",
+            joint_count,
+            None,
+            None,
+        );
+        skins.push(json!({
+            "inverseBindMatrices": inverse_bind_matrices,
+            "joints": joints.iter().map(|bone| first_bone + bone).collect::<Vec<_>>(),
+        }));
+    }
+
+    let animations = clips
+        .iter()
+        .map(|clip| push_clip(&mut builder, clip, bones, first_bone))
+        .collect();
+
+    finish(builder, &roots, &nodes, gltf_meshes, skins, animations)
+}
+
+/// Writes the model the CRES `cres` is the root of as a binary glTF file: every mesh of the
+/// GMDCs its CRES, SHPE and GMND chain leads to across `packages`, skinned to the skeleton of
+/// the CRES as in [`skeleton_to_glb`].
+#[must_use]
+pub fn scenegraph_to_glb(packages: &[Dbpf], cres: &Key) -> Vec<u8> {
+    let graph = Scenegraph::resolve(packages, cres);
+    skeleton_to_glb(&graph.mesh_data(packages), &graph.skeleton(packages), &[])
+}
+
+/// Reads every triangle primitive of a glTF file as a mesh, taking the name of the mesh it
+/// belongs to. Both binary files and JSON files with `data:` URIs are accepted.
+///
+/// # Errors
+/// Returns [`Error::JsonError`] if the JSON is malformed, and [`Error::MeshError`] if the file
+/// isn't glTF, refers to external files or its accessors don't fit its buffers.
+// Indices and joints are read from unsigned components
+#[allow(clippy::cast_sign_loss)]
+pub fn from_gltf(data: &[u8]) -> DbpfResult<Vec<Mesh>> {
+    let (document, bin) = if data.get(0..4) == Some(&GLB_MAGIC.to_le_bytes()[..]) {
+        split_glb(data)?
+    } else {
+        (serde_json::from_slice::<Value>(data)?, None)
+    };
+
+    let buffers = document["buffers"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+        .iter()
+        .map(|buffer| match buffer["uri"].as_str() {
+            Some(uri) => {
+                let encoded = uri
+                    .strip_prefix("data:")
+                    .and_then(|uri| uri.split_once(";base64,"))
+                    .ok_or_else(|| mesh_error("only embedded buffers are supported"))?
+                    .1;
+                decode_base64(encoded).ok_or_else(|| mesh_error("invalid base64 buffer"))
+            }
+            None => bin
+                .clone()
+                .ok_or_else(|| mesh_error("missing binary chunk")),
+        })
+        .collect::<DbpfResult<Vec<_>>>()?;
+
+    let mut meshes = vec![];
+    for gltf_mesh in document["meshes"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+    {
+        let name = gltf_mesh["name"].as_str().unwrap_or_default();
+        let primitives = gltf_mesh["primitives"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        for primitive in primitives {
+            if primitive["mode"].as_u64().unwrap_or(TRIANGLES) != TRIANGLES {
+                continue;
+            }
+            let attributes = &primitive["attributes"];
+            let attribute = |name: &str| -> DbpfResult<Vec<Vec<f64>>> {
+                attributes[name].as_u64().map_or(Ok(vec![]), |index| {
+                    read_accessor(&document, &buffers, index)
+                })
+            };
+            let positions = attribute("POSITION")?;
+            let indices = match primitive["indices"].as_u64() {
+                Some(index) => read_accessor(&document, &buffers, index)?
+                    .iter()
+                    .map(|index| index[0] as u32)
+                    .collect(),
+                None => (0..positions.len() as u32).collect(),
+            };
+            meshes.push(Mesh {
+                name: name.to_string(),
+                positions: positions.iter().map(|vector| to_array(vector)).collect(),
+                normals: attribute("NORMAL")?
+                    .iter()
+                    .map(|vector| to_array(vector))
+                    .collect(),
+                uvs: attribute("TEXCOORD_0")?
+                    .iter()
+                    .map(|vector| to_array(vector))
+                    .collect(),
+                joints: attribute("JOINTS_0")?
+                    .iter()
+                    .map(|vector| to_array(vector).map(|joint: f32| joint as u32))
+                    .collect(),
+                weights: attribute("WEIGHTS_0")?
+                    .iter()
+                    .map(|vector| to_array(vector))
+                    .collect(),
+                indices,
+            });
+        }
+    }
+    Ok(meshes)
+}
+
+/// Adds each mesh as a glTF mesh and a node, returning the nodes and meshes.
+fn push_meshes(builder: &mut Builder, meshes: &[Mesh], skinned: bool) -> (Vec<Value>, Vec<Value>) {
+    let mut nodes = vec![];
+    let mut gltf_meshes = vec![];
+    for (index, mesh) in meshes.iter().enumerate() {
+        let mut attributes = serde_json::Map::new();
+        let (min, max) = bounds(&mesh.positions);
+        attributes.insert(
+            "POSITION".to_string(),
+            json!(builder.push(
+                &floats(mesh.positions.iter().flatten()),
+                FLOAT,
+                "VEC3",
+                mesh.positions.len(),
+                Some(ARRAY_BUFFER),
+                Some((&min, &max)),
+            )),
+        );
+        if !mesh.normals.is_empty() {
+            attributes.insert(
+                "NORMAL".to_string(),
+                json!(builder.push(
+                    &floats(mesh.normals.iter().flatten()),
+                    FLOAT,
+                    "VEC3",
+                    mesh.normals.len(),
+                    Some(ARRAY_BUFFER),
+                    None,
+                )),
+            );
+        }
+        if !mesh.uvs.is_empty() {
+            attributes.insert(
+                "TEXCOORD_0".to_string(),
+                json!(builder.push(
+                    &floats(mesh.uvs.iter().flatten()),
+                    FLOAT,
+                    "VEC2",
+                    mesh.uvs.len(),
+                    Some(ARRAY_BUFFER),
+                    None,
+                )),
+            );
+        }
+        if skinned && !mesh.joints.is_empty() {
+            let bytes = mesh
+                .joints
+                .iter()
+                .flatten()
+                .flat_map(|&joint| (joint as u16).to_le_bytes())
+                .collect::<Vec<_>>();
+            attributes.insert(
+                "JOINTS_0".to_string(),
+                json!(builder.push(
+                    &bytes,
+                    UNSIGNED_SHORT,
+                    "VEC4",
+                    mesh.joints.len(),
+                    Some(ARRAY_BUFFER),
+                    None,
+                )),
+            );
+            attributes.insert(
+                "WEIGHTS_0".to_string(),
+                json!(builder.push(
+                    &floats(mesh.weights.iter().flatten()),
+                    FLOAT,
+                    "VEC4",
+                    mesh.weights.len(),
+                    Some(ARRAY_BUFFER),
+                    None,
+                )),
+            );
+        }
+        let indices = builder.push(
+            &mesh
+                .indices
+                .iter()
+                .flat_map(|index| index.to_le_bytes())
+                .collect::<Vec<_>>(),
+            UNSIGNED_INT,
+            "SCALAR",
+            mesh.indices.len(),
+            Some(ELEMENT_ARRAY_BUFFER),
+            None,
+        );
+
+        gltf_meshes.push(json!({
+            "name": mesh.name,
+            "primitives": [{ "attributes": attributes, "indices": indices, "mode": TRIANGLES }],
+        }));
+        let mut node = json!({ "name": mesh.name, "mesh": index });
+        if skinned && !mesh.joints.is_empty() {
+            node["skin"] = json!(0);
+        }
+        nodes.push(node);
+    }
+    (nodes, gltf_meshes)
+}
+
+/// Adds a clip as an animation, with a sampler per animated bone and transform path.
+fn push_clip(builder: &mut Builder, clip: &Clip, bones: &[Bone], first_bone: usize) -> Value {
+    let mut samplers = vec![];
+    let mut channels = vec![];
+    for track in &clip.tracks {
+        let bone = match bones
+            .iter()
+            .position(|bone| bone.name.eq_ignore_ascii_case(&track.bone))
+        {
+            Some(bone) => bone,
+            None => continue,
+        };
+        let mut add = |path: &str, frames: Vec<u16>, values: Vec<f32>, kind: &str| {
+            if frames.is_empty() {
+                return;
+            }
+            let times = frames
+                .iter()
+                .map(|&frame| f32::from(frame) / ANIMATION_FRAME_RATE)
+                .collect::<Vec<_>>();
+            let input = builder.push(
+                &floats(times.iter()),
+                FLOAT,
+                "SCALAR",
+                times.len(),
+                None,
+                Some((&times[..1], &times[times.len() - 1..])),
+            );
+            let output = builder.push(&floats(values.iter()), FLOAT, kind, times.len(), None, None);
+            samplers.push(json!({ "input": input, "output": output, "interpolation": "LINEAR" }));
+            channels.push(json!({
+                "sampler": samplers.len() - 1,
+                "target": { "node": first_bone + bone, "path": path },
+            }));
+        };
+        add(
+            "translation",
+            track.translations.iter().map(|(frame, _)| *frame).collect(),
+            track
+                .translations
+                .iter()
+                .flat_map(|(_, value)| *value)
+                .collect(),
+            "VEC3",
+        );
+        add(
+            "rotation",
+            track.rotations.iter().map(|(frame, _)| *frame).collect(),
+            track
+                .rotations
+                .iter()
+                .flat_map(|(_, value)| normalize(*value))
+                .collect(),
+            "VEC4",
+        );
+    }
+    json!({
+        "name": clip.name,
+        "samplers": samplers,
+        "channels": channels,
+    })
+}
+
+/// Assembles the document and buffer into a binary glTF file.
+fn finish(
+    builder: Builder,
+    roots: &[usize],
+    nodes: &[Value],
+    meshes: Vec<Value>,
+    skins: Vec<Value>,
+    animations: Vec<Value>,
+) -> Vec<u8> {
+    let mut document = json!({
+        "asset": { "version": "2.0", "generator": "s2-dbpf" },
+        "scene": 0,
+        "scenes": [{ "nodes": roots }],
+        "nodes": nodes,
+        "accessors": builder.accessors,
+        "bufferViews": builder.views,
+        "buffers": [{ "byteLength": builder.buffer.len() }],
+    });
+    if !meshes.is_empty() {
+        document["meshes"] = Value::Array(meshes);
+    }
+    if !skins.is_empty() {
+        document["skins"] = Value::Array(skins);
+    }
+    if !animations.is_empty() {
+        document["animations"] = Value::Array(animations);
+    }
+
+    let mut json = document.to_string().into_bytes();
+    pad(&mut json, b' ');
+    let mut bin = builder.buffer;
+    pad(&mut bin, 0);
+
+    let length = 12 + 8 + json.len() + 8 + bin.len();
+    let mut glb = Vec::with_capacity(length);
+    glb.extend(GLB_MAGIC.to_le_bytes());
+    glb.extend(2_u32.to_le_bytes());
+    glb.extend((length as u32).to_le_bytes());
+    glb.extend((json.len() as u32).to_le_bytes());
+    glb.extend(JSON_CHUNK.to_le_bytes());
+    glb.extend(json);
+    glb.extend((bin.len() as u32).to_le_bytes());
+    glb.extend(BIN_CHUNK.to_le_bytes());
+    glb.extend(bin);
+    glb
+}
+
+#[derive(Default)]
+struct Builder {
+    buffer: Vec<u8>,
+    views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+impl Builder {
+    /// Appends data as its own buffer view and accessor, returning the accessor's index.
+    fn push(
+        &mut self,
+        bytes: &[u8],
+        component_type: u64,
+        kind: &str,
+        count: usize,
+        target: Option<u64>,
+        bounds: Option<(&[f32], &[f32])>,
+    ) -> usize {
+        let mut view = json!({
+            "buffer": 0,
+            "byteOffset": self.buffer.len(),
+            "byteLength": bytes.len(),
+        });
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+        self.buffer.extend(bytes);
+        pad(&mut self.buffer, 0);
+        self.views.push(view);
+
+        let mut accessor = json!({
+            "bufferView": self.views.len() - 1,
+            "componentType": component_type,
+            "count": count,
+            "type": kind,
+        });
+        if let Some((min, max)) = bounds {
+            accessor["min"] = json!(min);
+            accessor["max"] = json!(max);
+        }
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+}
+
+fn read_accessor(document: &Value, buffers: &[Vec<u8>], index: u64) -> DbpfResult<Vec<Vec<f64>>> {
+    let accessor = &document["accessors"][index as usize];
+    let count = accessor["count"].as_u64().unwrap_or(0) as usize;
+    let components = match accessor["type"].as_str() {
+        Some("SCALAR") => 1,
+        Some("VEC2") => 2,
+        Some("VEC3") => 3,
+        Some("VEC4") => 4,
+        Some("MAT4") => 16,
+        _ => return Err(mesh_error("unsupported accessor type")),
+    };
+    let component_type = accessor["componentType"].as_u64().unwrap_or(0);
+    let size = match component_type {
+        UNSIGNED_BYTE => 1,
+        UNSIGNED_SHORT => 2,
+        UNSIGNED_INT | FLOAT => 4,
+        _ => return Err(mesh_error("unsupported component type")),
+    };
+    let normalized = accessor["normalized"].as_bool().unwrap_or(false);
+    let view = match accessor["bufferView"].as_u64() {
+        Some(view) => &document["bufferViews"][view as usize],
+        // Accessors without a view are all zeroes
+        None => return Ok(vec![vec![0.0; components]; count]),
+    };
+    let buffer = view["buffer"]
+        .as_u64()
+        .and_then(|buffer| buffers.get(buffer as usize))
+        .ok_or_else(|| mesh_error("missing buffer"))?;
+    let start = view["byteOffset"].as_u64().unwrap_or(0) as usize
+        + accessor["byteOffset"].as_u64().unwrap_or(0) as usize;
+    let stride = view["byteStride"]
+        .as_u64()
+        .map_or(components * size, |stride| stride as usize);
+
+    (0..count)
+        .map(|element| {
+            (0..components)
+                .map(|component| {
+                    let offset = start + element * stride + component * size;
+                    let bytes = buffer
+                        .get(offset..offset + size)
+                        .ok_or_else(|| mesh_error("accessor is out of bounds"))?;
+                    let (value, max) = match component_type {
+                        UNSIGNED_BYTE => (f64::from(bytes[0]), f64::from(u8::MAX)),
+                        UNSIGNED_SHORT => (
+                            f64::from(u16::from_le_bytes([bytes[0], bytes[1]])),
+                            f64::from(u16::MAX),
+                        ),
+                        UNSIGNED_INT => (
+                            f64::from(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+                            f64::from(u32::MAX),
+                        ),
+                        _ => (
+                            f64::from(f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+                            1.0,
+                        ),
+                    };
+                    Ok(if normalized { value / max } else { value })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn split_glb(data: &[u8]) -> DbpfResult<(Value, Option<Vec<u8>>)> {
+    let mut document = None;
+    let mut bin = None;
+    let mut offset = 12;
+    while let Some(header) = data.get(offset..offset + 8) {
+        let length = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let kind = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        let chunk = data
+            .get(offset + 8..offset + 8 + length)
+            .ok_or_else(|| mesh_error("truncated chunk"))?;
+        match kind {
+            JSON_CHUNK => document = Some(serde_json::from_slice(chunk)?),
+            BIN_CHUNK => bin = Some(chunk.to_vec()),
+            _ => {}
+        }
+        offset += 8 + length;
+    }
+    Ok((
+        document.ok_or_else(|| mesh_error("missing JSON chunk"))?,
+        bin,
+    ))
+}
+
+fn decode_base64(encoded: &str) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(encoded.len() / 4 * 3);
+    let mut bits = 0_u32;
+    let mut count = 0;
+    for byte in encoded.bytes().take_while(|&byte| byte != b'=') {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        bits = (bits << 6) | u32::from(value);
+        count += 6;
+        if count >= 8 {
+            count -= 8;
+            decoded.push((bits >> count) as u8);
+        }
+    }
+    Some(decoded)
+}
+
+fn mesh_error(message: &str) -> Error {
+    Error::MeshError(message.to_string())
+}
+
+fn floats<'a>(values: impl Iterator<Item = &'a f32>) -> Vec<u8> {
+    values.flat_map(|value| value.to_le_bytes()).collect()
+}
+
+fn pad(bytes: &mut Vec<u8>, with: u8) {
+    while bytes.len() % 4 != 0 {
+        bytes.push(with);
+    }
+}
+
+fn bounds(positions: &[[f32; 3]]) -> ([f32; 3], [f32; 3]) {
+    if positions.is_empty() {
+        return ([0.0; 3], [0.0; 3]);
+    }
+    let mut min = [f32::MAX; 3];
+    let mut max = [f32::MIN; 3];
+    for position in positions {
+        for axis in 0..3 {
+            min[axis] = min[axis].min(position[axis]);
+            max[axis] = max[axis].max(position[axis]);
+        }
+    }
+    (min, max)
+}
+
+fn to_array<const N: usize>(vector: &[f64]) -> [f32; N] {
+    let mut array = [0.0; N];
+    for (value, &component) in array.iter_mut().zip(vector) {
+        *value = component as f32;
+    }
+    array
+}
+
+/// Rotates a vector by a quaternion (x y z w).
+fn rotate([x, y, z, w]: [f32; 4], [vx, vy, vz]: [f32; 3]) -> [f32; 3] {
+    // t = 2 * cross(q.xyz, v); v' = v + w * t + cross(q.xyz, t)
+    let tx = 2.0 * (y * vz - z * vy);
+    let ty = 2.0 * (z * vx - x * vz);
+    let tz = 2.0 * (x * vy - y * vx);
+    [
+        vx + w * tx + (y * tz - z * ty),
+        vy + w * ty + (z * tx - x * tz),
+        vz + w * tz + (x * ty - y * tx),
+    ]
+}
+
+/// The inverse of a unit quaternion.
+fn conjugate([x, y, z, w]: [f32; 4]) -> [f32; 4] {
+    [-x, -y, -z, w]
+}
+
+/// The product `a * b` of two quaternions, which rotates by `b` and then by `a`.
+fn multiply([ax, ay, az, aw]: [f32; 4], [bx, by, bz, bw]: [f32; 4]) -> [f32; 4] {
+    [
+        aw * bx + ax * bw + ay * bz - az * by,
+        aw * by - ax * bz + ay * bw + az * bx,
+        aw * bz + ax * by - ay * bx + az * bw,
+        aw * bw - ax * bx - ay * by - az * bz,
+    ]
+}
+
+fn normalize(quaternion: [f32; 4]) -> [f32; 4] {
+    let length = quaternion
+        .iter()
+        .map(|value| value * value)
+        .sum::<f32>()
+        .sqrt();
+    if length == 0.0 {
+        return [0.0, 0.0, 0.0, 1.0];
+    }
+    quaternion.map(|value| value / length)
+}
+
+/// Whether following the parents of the bone at `index` leads back to it, which glTF's node
+/// tree can't hold.
+fn in_cycle(bones: &[Bone], index: usize) -> bool {
+    let mut parent = bones[index].parent;
+    for _ in 0..bones.len() {
+        match parent {
+            Some(above) if above == index => return true,
+            Some(above) => parent = bones.get(above).and_then(|bone| bone.parent),
+            None => break,
+        }
+    }
+    false
+}
+
+/// The rotation and translation of each bone relative to the root, composing its parents'.
+fn world_transforms(bones: &[Bone]) -> Vec<([f32; 4], [f32; 3])> {
+    bones
+        .iter()
+        .map(|bone| {
+            let (mut rotation, mut translation) = (bone.rotation, bone.translation);
+            let mut parent = bone.parent;
+            // Bounded by the number of bones in case the tree has a cycle
+            for _ in 0..bones.len() {
+                let above = match parent.and_then(|parent| bones.get(parent)) {
+                    Some(above) => above,
+                    None => break,
+                };
+                let [x, y, z] = rotate(above.rotation, translation);
+                translation = [
+                    x + above.translation[0],
+                    y + above.translation[1],
+                    z + above.translation[2],
+                ];
+                rotation = multiply(above.rotation, rotation);
+                parent = above.parent;
+            }
+            (rotation, translation)
+        })
+        .collect()
+}
+
+/// A column-major 4x4 matrix from a rotation quaternion and a translation.
+fn matrix([x, y, z, w]: [f32; 4], [tx, ty, tz]: [f32; 3]) -> [f32; 16] {
+    [
+        1.0 - 2.0 * (y * y + z * z),
+        2.0 * (x * y + w * z),
+        2.0 * (x * z - w * y),
+        0.0,
+        2.0 * (x * y - w * z),
+        1.0 - 2.0 * (x * x + z * z),
+        2.0 * (y * z + w * x),
+        0.0,
+        2.0 * (x * z + w * y),
+        2.0 * (y * z - w * x),
+        1.0 - 2.0 * (x * x + y * y),
+        0.0,
+        tx,
+        ty,
+        tz,
+        1.0,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mesh() -> Mesh {
+        Mesh {
+            name: "body".to_string(),
+            positions: vec![[0.0, 1.0, 2.0], [3.0, 4.5, 5.0], [6.0, 7.0, -8.25]],
+            normals: vec![[0.0, 1.0, 0.0], [0.0, 0.0, 1.0], [1.0, 0.0, 0.0]],
+            uvs: vec![[0.0, 0.25], [1.0, 0.5], [0.5, 1.0]],
+            joints: vec![[0, 0, 0, 0], [0, 1, 0, 0], [1, 0, 0, 0]],
+            weights: vec![
+                [1.0, 0.0, 0.0, 0.0],
+                [0.25, 0.75, 0.0, 0.0],
+                [1.0, 0.0, 0.0, 0.0],
+            ],
+            indices: vec![0, 1, 2],
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let joints = vec![
+            Transform {
+                rotation: [0.0, 0.0, 0.0, 1.0],
+                translation: [0.0, -1.0, 0.0],
+            },
+            Transform {
+                rotation: [0.0, 0.707_106_77, 0.0, 0.707_106_77],
+                translation: [1.0, 2.0, 3.0],
+            },
+        ];
+        let meshes = vec![mesh()];
+
+        let glb = to_glb(&meshes, &joints);
+
+        assert_eq!(&glb[0..4], b"glTF");
+        assert_eq!(glb.len() % 4, 0);
+        assert_eq!(from_gltf(&glb).unwrap(), meshes);
+    }
+
+    #[test]
+    fn unskinned() {
+        let meshes = vec![Mesh {
+            joints: vec![],
+            weights: vec![],
+            ..mesh()
+        }];
+
+        assert_eq!(from_gltf(&to_glb(&meshes, &[])).unwrap(), meshes);
+    }
+
+    #[test]
+    fn embedded_buffer() {
+        // One triangle, positions only, without indices
+        let gltf = r#"{
+            "asset": { "version": "2.0" },
+            "meshes": [{ "name": "tri", "primitives": [{ "attributes": { "POSITION": 0 } }] }],
+            "accessors": [{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3" }],
+            "bufferViews": [{ "buffer": 0, "byteLength": 36 }],
+            "buffers": [{
+                "byteLength": 36,
+                "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAA"
+            }]
+        }"#;
+
+        let meshes = from_gltf(gltf.as_bytes()).unwrap();
+
+        assert_eq!(meshes.len(), 1);
+        assert_eq!(meshes[0].name, "tri");
+        assert_eq!(
+            meshes[0].positions,
+            vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]
+        );
+        assert_eq!(meshes[0].indices, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn inverse_joint_is_inverse() {
+        let rotation = [0.0, 0.707_106_77, 0.0, 0.707_106_77];
+        let [x, y, z, w] = rotation;
+        let point = [1.0, 2.0, 3.0];
+        let back = rotate([-x, -y, -z, w], rotate(rotation, point));
+        for (a, b) in back.iter().zip(&point) {
+            assert!((a - b).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn bones_out_of_order() {
+        let bone = |name: &str, parent| Bone {
+            name: name.to_string(),
+            parent,
+            translation: [0.0; 3],
+            rotation: [0.0, 0.0, 0.0, 1.0],
+            joint: None,
+        };
+        // The hand comes before its arm, the loop parents itself
+        let bones = vec![
+            bone("hand", Some(2)),
+            bone("root", None),
+            bone("arm", Some(1)),
+            bone("loop", Some(3)),
+        ];
+
+        let glb = skeleton_to_glb(&[], &bones, &[]);
+        let (document, _) = split_glb(&glb).unwrap();
+
+        assert_eq!(document["scenes"][0]["nodes"], json!([1, 3]));
+        assert_eq!(document["nodes"][1]["children"], json!([2]));
+        assert_eq!(document["nodes"][2]["children"], json!([0]));
+    }
+
+    #[test]
+    fn skeleton_with_clip() {
+        let bones = vec![
+            Bone {
+                name: "root".to_string(),
+                parent: None,
+                translation: [0.0, 1.0, 0.0],
+                rotation: [0.0, 0.0, 0.0, 1.0],
+                joint: Some(0),
+            },
+            Bone {
+                name: "arm".to_string(),
+                parent: Some(0),
+                translation: [1.0, 0.0, 0.0],
+                rotation: [0.0, 0.0, 0.0, 1.0],
+                joint: Some(1),
+            },
+        ];
+        let clip = Clip {
+            name: "wave".to_string(),
+            tracks: vec![
+                Track {
+                    bone: "ARM".to_string(),
+                    translations: vec![],
+                    rotations: vec![(0, [0.0, 0.0, 0.0, 2.0]), (1, [0.0, 0.0, 0.5, 1.0])],
+                },
+                Track {
+                    bone: "missing".to_string(),
+                    translations: vec![(0, [0.0; 3])],
+                    rotations: vec![],
+                },
+            ],
+        };
+        let meshes = vec![mesh()];
+
+        let glb = skeleton_to_glb(&meshes, &bones, &[clip]);
+        let (document, bin) = split_glb(&glb).unwrap();
+        let buffers = vec![bin.unwrap()];
+
+        assert_eq!(from_gltf(&glb).unwrap(), meshes);
+        assert_eq!(document["scenes"][0]["nodes"], json!([0, 1]));
+        assert_eq!(document["nodes"][1]["children"], json!([2]));
+        assert_eq!(document["skins"][0]["joints"], json!([1, 2]));
+        // The arm's inverse bind matrix undoes both translations
+        let inverse_bind_matrices = document["skins"][0]["inverseBindMatrices"].as_u64();
+        let matrices = read_accessor(&document, &buffers, inverse_bind_matrices.unwrap()).unwrap();
+        assert_eq!(matrices[1][12..15], [-1.0, -1.0, 0.0]);
+
+        let animation = &document["animations"][0];
+        assert_eq!(animation["name"], "wave");
+        assert_eq!(animation["channels"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            animation["channels"][0]["target"],
+            json!({ "node": 2, "path": "rotation" })
+        );
+        let sampler = &animation["samplers"][0];
+        let times = read_accessor(&document, &buffers, sampler["input"].as_u64().unwrap()).unwrap();
+        assert_eq!(times, vec![vec![0.0], vec![f64::from(1.0_f32 / 30.0)]]);
+        let rotations =
+            read_accessor(&document, &buffers, sampler["output"].as_u64().unwrap()).unwrap();
+        assert_eq!(rotations[0], vec![0.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn scenegraph_chain() {
+        use crate::constants::data_kinds::{DbpfId, DbpfKind};
+        use crate::test_helpers::dbpf_from;
+        use crate::types::package::database_packed_file::{GroupId, InstanceId};
+        use crate::types::scenegraph::creation_resource::CreationResource;
+        use crate::types::scenegraph::geometric_data_container::{
+            BlockFormat, BoundingMesh, Element, GeometricDataContainer, GeometryData, Group,
+            Linkage, Model,
+        };
+        use crate::types::scenegraph::geometric_node::GeometricNode;
+        use crate::types::util::hash::LOCAL_GROUP;
+        use crate::types::util::resource_collection::{
+            BlockHeader, ResourceCollection, ResourceLink, SgResource,
+        };
+
+        let key = |kind, instance| Key {
+            kind,
+            group_id: GroupId(0x1C05_0000),
+            instance_id: InstanceId(instance),
+            resource_id: None,
+        };
+        let link = |key: Key| ResourceLink {
+            group_id: LOCAL_GROUP,
+            instance_id: key.instance_id,
+            resource_id: None,
+            kind: key.kind,
+        };
+        let cres = key(DbpfId::CreationResource, 1);
+        let gmnd = key(DbpfId::GeometricNode, 2);
+        let gmdc = key(DbpfId::GeometricDataContainer, 3);
+
+        let mut geometry = GeometryData {
+            block: BlockHeader::of::<GeometryData>(4),
+            resource: SgResource::new("chair_gmdc".to_string()),
+            elements: vec![Element {
+                number: 0,
+                identity: Element::VERTEX,
+                repeat: 0,
+                block_format: BlockFormat::ThreeFloat,
+                set_format: 0,
+                values: vec![],
+                references: vec![],
+            }],
+            linkages: vec![Linkage {
+                elements: vec![0],
+                ..Linkage::default()
+            }],
+            groups: vec![Group {
+                primitive_type: Group::TRIANGLES,
+                linkage: 0,
+                name: String::new(),
+                faces: vec![],
+                opacity: u32::MAX,
+                joints: vec![],
+            }],
+            model: Model {
+                transforms: vec![],
+                blend_groups: vec![],
+                bounding_mesh: BoundingMesh::default(),
+            },
+            joints: vec![],
+        };
+        let seat = Mesh {
+            name: "seat".to_string(),
+            positions: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            indices: vec![0, 1, 2],
+            ..Mesh::default()
+        };
+        geometry.set_mesh(0, &seat).unwrap();
+        let packages = [dbpf_from(vec![
+            (
+                cres,
+                DbpfKind::CreationResource(CreationResource {
+                    collection: ResourceCollection {
+                        links: vec![link(gmnd)],
+                        ..ResourceCollection::default()
+                    },
+                }),
+            ),
+            (
+                gmnd,
+                DbpfKind::GeometricNode(GeometricNode {
+                    collection: ResourceCollection {
+                        links: vec![link(gmdc)],
+                        ..ResourceCollection::default()
+                    },
+                }),
+            ),
+            (
+                gmdc,
+                DbpfKind::GeometricDataContainer(GeometricDataContainer {
+                    collection: ResourceCollection::new(vec![geometry.into()]),
+                }),
+            ),
+        ])];
+
+        let glb = scenegraph_to_glb(&packages, &cres);
+
+        assert_eq!(from_gltf(&glb).unwrap(), vec![seat]);
+    }
+}
@@ -0,0 +1,167 @@
+////////////////////////////////////////////////////////////////////////////////
+// This Source Code Form is subject to the terms of the Mozilla Public         /
+// License, v. 2.0. If a copy of the MPL was not distributed with this         /
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
+////////////////////////////////////////////////////////////////////////////////
+
+//! The hashes the game uses to turn names into resource ids.
+
+use crate::constants::data_kinds::DbpfId;
+use crate::types::package::database_packed_file::{GroupId, InstanceId, Key, ResourceId};
+
+const CRC24_INIT: u32 = 0x00B7_04CE;
+const CRC24_POLY: u32 = 0x0086_4CFB;
+const CRC32_POLY: u32 = 0x04C1_1DB7;
+
+/// The group of a name without a `##0x...!` prefix. It stands for the group of the package
+/// holding the reference, the resource is expected to sit next to whatever refers to it.
+pub const LOCAL_GROUP: GroupId = GroupId(0xFFFF_FFFF);
+
+/// CRC-24 as used by `OpenPGP`.
+#[must_use]
+pub fn crc24(data: &[u8]) -> u32 {
+    let mut crc = CRC24_INIT;
+    for byte in data {
+        crc ^= u32::from(*byte) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x0100_0000 != 0 {
+                crc ^= CRC24_POLY;
+            }
+        }
+    }
+    crc & 0x00FF_FFFF
+}
+
+/// CRC-32 in its unreflected BZIP2 form.
+#[must_use]
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFF_u32;
+    for byte in data {
+        crc ^= u32::from(*byte) << 24;
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 == 0 {
+                crc << 1
+            } else {
+                (crc << 1) ^ CRC32_POLY
+            };
+        }
+    }
+    !crc
+}
+
+/// The group a name maps to, used for semiglobal groups and custom content.
+/// Names are hashed case insensitively.
+#[must_use]
+pub fn group_hash(name: &str) -> GroupId {
+    GroupId(crc24(name.to_lowercase().as_bytes()) | 0x7F00_0000)
+}
+
+/// Splits the `##0x1c050000!` group prefix off a scenegraph file name, leaving names without
+/// one, or with one that isn't valid hex, as they are.
+#[must_use]
+pub fn split_group(name: &str) -> (Option<GroupId>, &str) {
+    let prefixed = name
+        .strip_prefix("##")
+        .and_then(|rest| rest.split_once('!'))
+        .and_then(|(group, rest)| {
+            let hex = group
+                .strip_prefix("0x")
+                .or_else(|| group.strip_prefix("0X"))
+                .unwrap_or(group);
+            u32::from_str_radix(hex, 16)
+                .ok()
+                .map(|group| (GroupId(group), rest))
+        });
+    match prefixed {
+        Some((group, rest)) => (Some(group), rest),
+        None => (None, name),
+    }
+}
+
+/// The instance a scenegraph file name hashes to, leaving out any group prefix.
+#[must_use]
+pub fn instance_hash(name: &str) -> InstanceId {
+    let (_, name) = split_group(name);
+    InstanceId(crc24(name.to_lowercase().as_bytes()) | 0xFF00_0000)
+}
+
+/// The resource id a scenegraph file name hashes to, leaving out any group prefix.
+#[must_use]
+pub fn resource_hash(name: &str) -> ResourceId {
+    let (_, name) = split_group(name);
+    ResourceId(crc32(name.to_lowercase().as_bytes()))
+}
+
+/// The key a scenegraph file name refers to, in the group its prefix names or in
+/// [`LOCAL_GROUP`] if it has none.
+#[must_use]
+pub fn name_key(kind: DbpfId, name: &str) -> Key {
+    Key {
+        kind,
+        group_id: split_group(name).0.unwrap_or(LOCAL_GROUP),
+        instance_id: instance_hash(name),
+        resource_id: Some(resource_hash(name)),
+    }
+}
+
+/// `name` with `suffix` added if it doesn't already end in it, ignoring case. References
+/// often leave out the `_txmt`, `_gmnd` and similar suffixes the files are named with.
+#[must_use]
+pub fn with_suffix(name: &str, suffix: &str) -> String {
+    if name.to_lowercase().ends_with(suffix) {
+        name.to_string()
+    } else {
+        format!("{}{}", name, suffix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc24_check_value() {
+        assert_eq!(crc24(b"123456789"), 0x0021_CF02);
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xFC89_1918);
+    }
+
+    #[test]
+    fn split_group_prefix() {
+        assert_eq!(
+            split_group("##0x1c050000!chair_seat_txmt"),
+            (Some(GroupId(0x1C05_0000)), "chair_seat_txmt")
+        );
+        assert_eq!(split_group("chair_seat_txmt"), (None, "chair_seat_txmt"));
+        assert_eq!(split_group("##chair!seat"), (None, "##chair!seat"));
+    }
+
+    #[test]
+    fn name_key_hashes_without_prefix() {
+        let key = name_key(DbpfId::TexturedMaterial, "##0x1C050000!Chair_Seat_txmt");
+        assert_eq!(key.group_id, GroupId(0x1C05_0000));
+        assert_eq!(key.instance_id, instance_hash("chair_seat_txmt"));
+        assert_eq!(key.instance_id.0 & 0xFF00_0000, 0xFF00_0000);
+        assert_eq!(key.resource_id, Some(resource_hash("chair_seat_txmt")));
+        assert_eq!(
+            name_key(DbpfId::TexturedMaterial, "chair_seat_txmt").group_id,
+            LOCAL_GROUP
+        );
+    }
+
+    #[test]
+    fn with_suffix_ignores_case() {
+        assert_eq!(with_suffix("Chair_Seat_TXMT", "_txmt"), "Chair_Seat_TXMT");
+        assert_eq!(with_suffix("chair_seat", "_txmt"), "chair_seat_txmt");
+    }
+
+    #[test]
+    fn group_hash_ignores_case() {
+        assert_eq!(group_hash("ChairGlobals"), group_hash("chairglobals"));
+        assert_eq!(group_hash("chairglobals").0 & 0xFF00_0000, 0x7F00_0000);
+    }
+}
@@ -0,0 +1,373 @@
+////////////////////////////////////////////////////////////////////////////////
+// This Source Code Form is subject to the terms of the Mozilla Public         /
+// License, v. 2.0. If a copy of the MPL was not distributed with this         /
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
+////////////////////////////////////////////////////////////////////////////////
+
+//! The decoded form every image resource converts to and from, and the plain image files some
+//! resources wrap.
+
+use std::io::{Cursor, Read, Seek, Write};
+
+use crate::error::DbpfResult;
+use crate::types::util::parser_args::ParserArgs;
+use binrw::{BinRead, BinResult, BinWrite, NullString, ReadOptions, WriteOptions};
+use image::ImageOutputFormat;
+
+/// 8 bit per channel RGBA pixels, stored row by row from the top left.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl RgbaImage {
+    /// A fully transparent image.
+    #[must_use]
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            data: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    /// Wraps existing pixel data, `None` if it doesn't hold exactly `width` by `height` pixels.
+    #[must_use]
+    pub fn from_raw(width: u32, height: u32, data: Vec<u8>) -> Option<Self> {
+        (data.len() == width as usize * height as usize * 4).then(|| Self {
+            width,
+            height,
+            data,
+        })
+    }
+
+    fn offset(&self, x: u32, y: u32) -> usize {
+        (y as usize * self.width as usize + x as usize) * 4
+    }
+
+    /// # Panics
+    /// Panics if the pixel is out of bounds.
+    #[must_use]
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        assert!(x < self.width && y < self.height, "pixel out of bounds");
+        let offset = self.offset(x, y);
+        [
+            self.data[offset],
+            self.data[offset + 1],
+            self.data[offset + 2],
+            self.data[offset + 3],
+        ]
+    }
+
+    /// # Panics
+    /// Panics if the pixel is out of bounds.
+    pub fn set_pixel(&mut self, x: u32, y: u32, pixel: [u8; 4]) {
+        assert!(x < self.width && y < self.height, "pixel out of bounds");
+        let offset = self.offset(x, y);
+        self.data[offset..offset + 4].copy_from_slice(&pixel);
+    }
+
+    pub fn pixels(&self) -> impl Iterator<Item = [u8; 4]> + '_ {
+        self.data
+            .chunks_exact(4)
+            .map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]])
+    }
+
+    /// Decodes a BMP, JPEG or PNG file.
+    ///
+    /// # Errors
+    /// Fails if the data isn't one of those formats or is corrupt.
+    pub fn decode(bytes: &[u8]) -> DbpfResult<Self> {
+        let image = image::load_from_memory(bytes)?.into_rgba8();
+        Ok(Self {
+            width: image.width(),
+            height: image.height(),
+            data: image.into_raw(),
+        })
+    }
+
+    /// Encodes the image as a file in `format`. JPEG has no alpha channel, so it is dropped.
+    ///
+    /// # Errors
+    /// Fails if the image is too large for the format.
+    ///
+    /// # Panics
+    /// Panics if `data` doesn't hold exactly `width` by `height` pixels.
+    pub fn encode(&self, format: ImageFileFormat) -> DbpfResult<Vec<u8>> {
+        let image = image::RgbaImage::from_raw(self.width, self.height, self.data.clone())
+            .expect("pixel data matches the image size");
+        let mut cursor = Cursor::new(vec![]);
+        match format {
+            ImageFileFormat::Bmp => image.write_to(&mut cursor, ImageOutputFormat::Bmp)?,
+            ImageFileFormat::Jpeg => image::DynamicImage::ImageRgba8(image)
+                .into_rgb8()
+                .write_to(&mut cursor, ImageOutputFormat::Jpeg(90))?,
+            ImageFileFormat::Png => image.write_to(&mut cursor, ImageOutputFormat::Png)?,
+        }
+        Ok(cursor.into_inner())
+    }
+
+    /// The next mip level: half the size, each pixel averaging the 2x2 block it covers.
+    #[must_use]
+    pub fn downsample(&self) -> RgbaImage {
+        let mut out = RgbaImage::new((self.width / 2).max(1), (self.height / 2).max(1));
+        for y in 0..out.height {
+            for x in 0..out.width {
+                let mut sums = [0u32; 4];
+                let mut count = 0;
+                for source_y in (y * 2..y * 2 + 2).filter(|&source_y| source_y < self.height) {
+                    for source_x in (x * 2..x * 2 + 2).filter(|&source_x| source_x < self.width) {
+                        for (sum, value) in sums.iter_mut().zip(self.pixel(source_x, source_y)) {
+                            *sum += u32::from(value);
+                        }
+                        count += 1;
+                    }
+                }
+                if count > 0 {
+                    out.set_pixel(x, y, sums.map(|sum| (sum / count) as u8));
+                }
+            }
+        }
+        out
+    }
+
+    /// The image mirrored left to right.
+    #[must_use]
+    pub fn flip_horizontal(&self) -> RgbaImage {
+        let mut out = RgbaImage::new(self.width, self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                out.set_pixel(self.width - 1 - x, y, self.pixel(x, y));
+            }
+        }
+        out
+    }
+
+    /// Draws `source` with its top left corner at `x`, `y` using "over" alpha blending, clipping
+    /// anything that falls outside this image.
+    pub fn blend(&mut self, source: &RgbaImage, x: i64, y: i64) {
+        for source_y in 0..source.height {
+            let target_y = y + i64::from(source_y);
+            let target_y = match u32::try_from(target_y) {
+                Ok(target_y) if target_y < self.height => target_y,
+                _ => continue,
+            };
+            for source_x in 0..source.width {
+                let target_x = x + i64::from(source_x);
+                let target_x = match u32::try_from(target_x) {
+                    Ok(target_x) if target_x < self.width => target_x,
+                    _ => continue,
+                };
+                let top = source.pixel(source_x, source_y);
+                let bottom = self.pixel(target_x, target_y);
+                self.set_pixel(target_x, target_y, over(top, bottom));
+            }
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ImageFileFormat {
+    Bmp,
+    Jpeg,
+    Png,
+}
+
+impl ImageFileFormat {
+    /// Recognizes a file by its magic bytes.
+    #[must_use]
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(b"BM") {
+            Some(ImageFileFormat::Bmp)
+        } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(ImageFileFormat::Jpeg)
+        } else if bytes.starts_with(b"\x89PNG") {
+            Some(ImageFileFormat::Png)
+        } else {
+            None
+        }
+    }
+}
+
+/// A standard image file stored as a resource, sometimes behind the usual 64 byte file name.
+/// The file runs to the end of the data, so in a package this relies on each entry being read
+/// from a buffer of its own.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ImageFile {
+    pub file_name: Option<String>,
+    pub data: Vec<u8>,
+}
+
+impl ImageFile {
+    /// Encodes `image` into a new file.
+    ///
+    /// # Errors
+    /// Fails if the image can't be encoded in `format`.
+    pub fn encode(
+        image: &RgbaImage,
+        format: ImageFileFormat,
+        file_name: Option<String>,
+    ) -> DbpfResult<Self> {
+        Ok(Self {
+            file_name,
+            data: image.encode(format)?,
+        })
+    }
+
+    #[must_use]
+    pub fn format(&self) -> Option<ImageFileFormat> {
+        ImageFileFormat::detect(&self.data)
+    }
+
+    /// # Errors
+    /// Fails if the data isn't an image file that can be decoded.
+    pub fn decode(&self) -> DbpfResult<RgbaImage> {
+        RgbaImage::decode(&self.data)
+    }
+}
+
+impl BinRead for ImageFile {
+    type Args = ParserArgs;
+
+    fn read_options<R: Read + Seek>(
+        reader: &mut R,
+        _options: &ReadOptions,
+        _args: Self::Args,
+    ) -> BinResult<Self> {
+        let pos = reader.stream_position()?;
+        let mut data = vec![];
+        reader.read_to_end(&mut data)?;
+        // Only treat the start as a file name if an image follows it
+        if ImageFileFormat::detect(&data).is_none()
+            && data.len() > 64
+            && ImageFileFormat::detect(&data[64..]).is_some()
+        {
+            let name = data[..64].iter().take_while(|&&byte| byte != 0).copied();
+            let file_name =
+                String::from_utf8(name.collect()).map_err(|err| binrw::Error::Custom {
+                    pos,
+                    err: Box::new(err),
+                })?;
+            data.drain(..64);
+            Ok(Self {
+                file_name: Some(file_name),
+                data,
+            })
+        } else {
+            Ok(Self {
+                file_name: None,
+                data,
+            })
+        }
+    }
+}
+
+impl BinWrite for ImageFile {
+    type Args = ();
+
+    fn write_options<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+        _args: Self::Args,
+    ) -> BinResult<()> {
+        if let Some(file_name) = &self.file_name {
+            let mut name = NullString::from(file_name.clone()).0;
+            name.resize(64, 0);
+            writer.write_all(&name)?;
+        }
+        self.data.write_options(writer, options, ())
+    }
+}
+
+fn over(top: [u8; 4], bottom: [u8; 4]) -> [u8; 4] {
+    let top_alpha = u32::from(top[3]);
+    match top_alpha {
+        255 => top,
+        0 => bottom,
+        _ => {
+            let bottom_alpha = u32::from(bottom[3]) * (255 - top_alpha) / 255;
+            let alpha = top_alpha + bottom_alpha;
+            let blend = |top: u8, bottom: u8| {
+                ((u32::from(top) * top_alpha + u32::from(bottom) * bottom_alpha) / alpha) as u8
+            };
+            [
+                blend(top[0], bottom[0]),
+                blend(top[1], bottom[1]),
+                blend(top[2], bottom[2]),
+                alpha as u8,
+            ]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::package::index_table::Entry;
+    use crate::types::util::bytes::Size;
+    use binrw::{BinReaderExt, BinWriterExt};
+
+    #[test]
+    fn pixel_access() {
+        let mut image = RgbaImage::new(2, 2);
+        image.set_pixel(1, 1, [1, 2, 3, 4]);
+
+        assert_eq!(image.pixel(1, 1), [1, 2, 3, 4]);
+        assert_eq!(image.data[12..], [1, 2, 3, 4]);
+        assert_eq!(RgbaImage::from_raw(2, 2, vec![0; 15]), None);
+    }
+
+    #[test]
+    fn blend_clips_and_composites() {
+        let mut bottom = RgbaImage::new(2, 1);
+        bottom.set_pixel(0, 0, [0, 0, 255, 255]);
+        let mut top = RgbaImage::new(2, 1);
+        top.set_pixel(0, 0, [255, 0, 0, 255]);
+
+        bottom.blend(&top, -1, 0);
+        assert_eq!(bottom.pixel(0, 0), [0, 0, 255, 255]);
+        assert_eq!(top.flip_horizontal().pixel(1, 0), [255, 0, 0, 255]);
+
+        bottom.blend(&top, 1, 0);
+        assert_eq!(bottom.pixel(1, 0), [255, 0, 0, 255]);
+
+        top.set_pixel(0, 0, [255, 0, 0, 128]);
+        bottom.blend(&top, 0, 0);
+        assert_eq!(bottom.pixel(0, 0), [128, 0, 127, 255]);
+    }
+
+    #[test]
+    fn png_round_trip() {
+        let mut image = RgbaImage::new(3, 2);
+        image.set_pixel(2, 1, [10, 20, 30, 40]);
+        let png = image.encode(ImageFileFormat::Png).unwrap();
+
+        assert_eq!(ImageFileFormat::detect(&png), Some(ImageFileFormat::Png));
+        assert_eq!(RgbaImage::decode(&png).unwrap(), image);
+    }
+
+    #[test]
+    fn image_file_keeps_file_name() {
+        let image = RgbaImage::new(1, 1);
+        let file =
+            ImageFile::encode(&image, ImageFileFormat::Bmp, Some("Thumb".to_string())).unwrap();
+        let mut cursor = Cursor::new(vec![]);
+        cursor.write_le(&file).unwrap();
+        let args = ParserArgs {
+            index_entry: Entry {
+                size: Size(cursor.get_ref().len() as u32),
+                ..Entry::default()
+            },
+            ..ParserArgs::default()
+        };
+        cursor.set_position(0);
+        let out: ImageFile = cursor.read_le_args(args).unwrap();
+
+        assert_eq!(out, file);
+        assert_eq!(out.format(), Some(ImageFileFormat::Bmp));
+        assert_eq!(out.decode().unwrap().pixel(0, 0)[3], 0);
+    }
+}
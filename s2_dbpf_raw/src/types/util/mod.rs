@@ -4,4 +4,12 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 pub mod bytes;
+pub mod cpf;
+pub mod gltf;
+pub mod hash;
+pub mod image;
 pub mod parser_args;
+pub mod resource_collection;
+pub mod strings;
+pub mod texture;
+pub mod wavefront;
@@ -4,4 +4,765 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
 ////////////////////////////////////////////////////////////////////////////////
 
-pub struct ResourceCollectionHeader {}
+//! The resource collection (RCOL) container the scenegraph resources are stored in: a list of
+//! links to other resources followed by a list of typed blocks.
+
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use crate::constants::data_kinds::DbpfId;
+use crate::types::draw_state_lighting::DrawStateLight;
+use crate::types::environment_cube_lighting::EnvironmentCubeLight;
+use crate::types::large_image::LevelInfo;
+use crate::types::linear_fog_lighting::LinearFogLight;
+use crate::types::package::database_packed_file::{GroupId, InstanceId, Key, ResourceId};
+use crate::types::scenegraph::creation_resource::{
+    LightRefNode, ResourceNode, ShapeRefNode, TransformNode, ViewerRefNode,
+};
+use crate::types::scenegraph::extension::{BoneDataExtension, DataListExtension};
+use crate::types::scenegraph::geometric_data_container::GeometryData;
+use crate::types::scenegraph::geometric_node::GeometryNode;
+use crate::types::scenegraph::light::{AmbientLight, DirectionalLight, PointLight, SpotLight};
+use crate::types::scenegraph::shape::ShapeData;
+use crate::types::scenegraph::textured_material::MaterialDefinition;
+use crate::types::texture_resource::ImageData;
+use crate::types::util::parser_args::ParserArgs;
+use crate::types::util::strings::{read_seven_bit_string, write_seven_bit_string};
+use binrw::{binrw, BinRead, BinResult, BinWrite, ReadOptions, WriteOptions};
+#[cfg(test)]
+use proptest::collection::vec;
+#[cfg(test)]
+use proptest::prelude::*;
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+/// Replaces the link count in collections whose links carry a resource id.
+const RESOURCE_ID_MARKER: u32 = 0xFFFF_0001;
+
+/// A block type that can be stored in a [`ResourceCollection`], registered with [`blocks!`].
+pub trait RcolBlock: Sized {
+    const NAME: &'static str;
+    const ID: u32;
+    /// The block versions this implementation can read.
+    const VERSIONS: &'static [u32];
+
+    fn from_block(block: &Block) -> Option<&Self>;
+
+    fn from_block_mut(block: &mut Block) -> Option<&mut Self>;
+}
+
+/// Registers block types by name, type id and the versions they can read, generating the
+/// [`Block`] enum dispatching between them.
+macro_rules! blocks {
+    ($($kind:ident = ($name:literal, $id:literal, [$($version:literal),* $(,)?])),* $(,)?) => {
+        #[derive(Debug, Clone, PartialEq)]
+        #[cfg_attr(test, derive(Arbitrary))]
+        pub enum Block {
+            $($kind($kind),)*
+            /// A block without a registered implementation, kept as raw bytes. Blocks have no
+            /// length prefix, so this can only be the last block of a collection, anywhere else
+            /// the collection keeps its [`ResourceCollection::raw_blocks`] instead.
+            #[cfg_attr(test, weight(0))]
+            Unknown(UnknownBlock),
+        }
+
+        $(
+            impl RcolBlock for $kind {
+                const NAME: &'static str = $name;
+                const ID: u32 = $id;
+                const VERSIONS: &'static [u32] = &[$($version),*];
+
+                fn from_block(block: &Block) -> Option<&Self> {
+                    if let Block::$kind(block) = block {
+                        Some(block)
+                    } else {
+                        None
+                    }
+                }
+
+                fn from_block_mut(block: &mut Block) -> Option<&mut Self> {
+                    if let Block::$kind(block) = block {
+                        Some(block)
+                    } else {
+                        None
+                    }
+                }
+            }
+
+            impl From<$kind> for Block {
+                fn from(block: $kind) -> Self {
+                    Block::$kind(block)
+                }
+            }
+        )*
+
+        impl Block {
+            #[must_use]
+            pub fn header(&self) -> &BlockHeader {
+                match self {
+                    $(Block::$kind(block) => &block.block,)*
+                    Block::Unknown(block) => &block.header,
+                }
+            }
+
+            /// Reads the block with the implementation registered for `header`, if there is one.
+            fn read_registered<R: Read + Seek>(
+                reader: &mut R,
+                options: &ReadOptions,
+                header: &BlockHeader,
+            ) -> Option<BinResult<Self>> {
+                $(
+                    if header.name == $name && <$kind as RcolBlock>::VERSIONS.contains(&header.version) {
+                        return Some($kind::read_options(reader, options, ()).map(Block::$kind));
+                    }
+                )*
+                None
+            }
+        }
+
+        impl BinWrite for Block {
+            type Args = ();
+
+            fn write_options<W: Write + Seek>(
+                &self,
+                writer: &mut W,
+                options: &WriteOptions,
+                _args: Self::Args,
+            ) -> BinResult<()> {
+                match self {
+                    $(Block::$kind(block) => block.write_options(writer, options, ()),)*
+                    Block::Unknown(block) => block.write_options(writer, options, ()),
+                }
+            }
+        }
+    };
+}
+
+blocks! {
+    AmbientLight = ("cAmbientLight", 0xC9C8_1BA3, [1]),
+    BoneDataExtension = ("cBoneDataExtension", 0xE907_5BC5, [5]),
+    DataListExtension = ("cDataListExtension", 0x6A83_6D56, [1]),
+    DirectionalLight = ("cDirectionalLight", 0xC9C8_1BA9, [1]),
+    DrawStateLight = ("cDrawStateLight", 0xAC06_A676, [1]),
+    EnvironmentCubeLight = ("cEnvironmentCubeLight", 0x6A97_042F, [1]),
+    GeometryData = ("cGeometryDataContainer", 0xAC4F_8687, [1, 2, 3, 4]),
+    GeometryNode = ("cGeometryNode", 0x7BA3_838C, [11, 12]),
+    ImageData = ("cImageData", 0x1C4A_276C, [7, 8, 9]),
+    LevelInfo = ("cLevelInfo", 0xED53_4136, [4]),
+    LightRefNode = ("cLightRefNode", 0x253D_2018, [10]),
+    LinearFogLight = ("cLinearFogLight", 0xAC06_A66F, [1]),
+    MaterialDefinition = ("cMaterialDefinition", 0x4959_6978, [8, 9, 10, 11]),
+    PointLight = ("cPointLight", 0xC9C8_1BAD, [1]),
+    ResourceNode = ("cResourceNode", 0xE519_C933, [7]),
+    ShapeData = ("cShape", 0xFC6E_B1F7, [6, 7, 8]),
+    ShapeRefNode = ("cShapeRefNode", 0x6524_5517, [21]),
+    SpotLight = ("cSpotLight", 0xC9C8_1BAE, [1]),
+    TransformNode = ("cTransformNode", 0x6524_6462, [7]),
+    ViewerRefNode = ("cViewerRefNode", 0xDFFB_2003, [1]),
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ResourceCollection {
+    pub has_resource_ids: bool,
+    pub links: Vec<ResourceLink>,
+    pub blocks: Vec<Block>,
+    /// The block list as it was read, when it couldn't be split into blocks. Blocks carry no
+    /// length, so a block without a registered implementation anywhere but at the end hides
+    /// where every following block starts. `blocks` is empty then.
+    pub raw_blocks: Option<Vec<u8>>,
+}
+
+impl ResourceCollection {
+    #[must_use]
+    pub fn new(blocks: Vec<Block>) -> Self {
+        Self {
+            blocks,
+            ..Self::default()
+        }
+    }
+
+    /// The first block of type `T`.
+    #[must_use]
+    pub fn block<T: RcolBlock>(&self) -> Option<&T> {
+        self.blocks.iter().find_map(T::from_block)
+    }
+
+    pub fn block_mut<T: RcolBlock>(&mut self) -> Option<&mut T> {
+        self.blocks.iter_mut().find_map(T::from_block_mut)
+    }
+
+    pub fn blocks_of<'a, T: RcolBlock + 'a>(&'a self) -> impl Iterator<Item = &'a T> {
+        self.blocks.iter().filter_map(T::from_block)
+    }
+}
+
+impl BinRead for ResourceCollection {
+    type Args = ParserArgs;
+
+    fn read_options<R: Read + Seek>(
+        reader: &mut R,
+        options: &ReadOptions,
+        _args: Self::Args,
+    ) -> BinResult<Self> {
+        let mut num_links = u32::read_options(reader, options, ())?;
+        let has_resource_ids = num_links == RESOURCE_ID_MARKER;
+        if has_resource_ids {
+            num_links = u32::read_options(reader, options, ())?;
+        }
+        let links = (0..num_links)
+            .map(|_| ResourceLink::read_options(reader, options, (has_resource_ids,)))
+            .collect::<BinResult<_>>()?;
+        let blocks_pos = reader.stream_position()?;
+        let (blocks, raw_blocks) = if let Ok(blocks) = read_blocks(reader, options) {
+            (blocks, None)
+        } else {
+            // Packages hand each entry its own buffer, so this stops at the end of the resource
+            reader.seek(SeekFrom::Start(blocks_pos))?;
+            let mut data = vec![];
+            reader.read_to_end(&mut data)?;
+            (vec![], Some(data))
+        };
+        Ok(Self {
+            has_resource_ids,
+            links,
+            blocks,
+            raw_blocks,
+        })
+    }
+}
+
+/// Reads the block list, failing if a block can't be read or data is left after the last one.
+fn read_blocks<R: Read + Seek>(reader: &mut R, options: &ReadOptions) -> BinResult<Vec<Block>> {
+    // The ids are repeated in each block's header
+    let num_blocks = u32::read_options(reader, options, ())?;
+    reader.seek(SeekFrom::Current(i64::from(num_blocks) * 4))?;
+
+    let mut blocks = Vec::with_capacity(num_blocks as usize);
+    for index in 0..num_blocks {
+        let pos = reader.stream_position()?;
+        let header = BlockHeader::read_options(reader, options, ())?;
+        let data_pos = reader.stream_position()?;
+        reader.seek(SeekFrom::Start(pos))?;
+        let block = match Block::read_registered(reader, options, &header) {
+            Some(block) => block?,
+            None if index + 1 == num_blocks => {
+                reader.seek(SeekFrom::Start(data_pos))?;
+                let mut data = vec![];
+                reader.read_to_end(&mut data)?;
+                Block::Unknown(UnknownBlock { header, data })
+            }
+            None => return Err(unregistered(pos, &header)),
+        };
+        blocks.push(block);
+    }
+    let pos = reader.stream_position()?;
+    if reader.read(&mut [0])? != 0 {
+        return Err(binrw::Error::AssertFail {
+            pos,
+            message: "Data left after the last block".to_string(),
+        });
+    }
+    Ok(blocks)
+}
+
+impl BinWrite for ResourceCollection {
+    type Args = ();
+
+    fn write_options<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+        _args: Self::Args,
+    ) -> BinResult<()> {
+        if self.has_resource_ids {
+            RESOURCE_ID_MARKER.write_options(writer, options, ())?;
+        }
+        (self.links.len() as u32).write_options(writer, options, ())?;
+        for link in &self.links {
+            ResourceLink {
+                resource_id: self
+                    .has_resource_ids
+                    .then(|| link.resource_id.unwrap_or_default()),
+                ..link.clone()
+            }
+            .write_options(writer, options, ())?;
+        }
+        if let Some(raw_blocks) = &self.raw_blocks {
+            return raw_blocks.write_options(writer, options, ());
+        }
+        (self.blocks.len() as u32).write_options(writer, options, ())?;
+        for block in &self.blocks {
+            block.header().id.write_options(writer, options, ())?;
+        }
+        self.blocks.write_options(writer, options, ())
+    }
+}
+
+#[cfg(test)]
+prop_compose! {
+    fn resource_collection_mapper()(
+        has_resource_ids in any::<bool>()
+    )(
+        links in vec(any_with::<ResourceLink>((has_resource_ids,)), 0..4),
+        blocks in vec(any::<Block>(), 0..3),
+        has_resource_ids in Just(has_resource_ids),
+    ) -> ResourceCollection {
+        ResourceCollection {
+            has_resource_ids,
+            links,
+            blocks,
+            raw_blocks: None,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Arbitrary for ResourceCollection {
+    type Parameters = ();
+
+    fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
+        resource_collection_mapper().boxed()
+    }
+
+    type Strategy = BoxedStrategy<Self>;
+}
+
+/// Another resource the collection depends on, such as the textures of a material.
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[brw(little)]
+#[br(import(has_resource_id: bool))]
+#[cfg_attr(test, derive(Arbitrary))]
+#[cfg_attr(test, arbitrary(args = (bool,)))]
+pub struct ResourceLink {
+    pub group_id: GroupId,
+    pub instance_id: InstanceId,
+    #[br(if(has_resource_id))]
+    #[cfg_attr(test, strategy(any::<ResourceId>().prop_map(move |x| if args.0 { Some(x)} else { None })))]
+    pub resource_id: Option<ResourceId>,
+    pub kind: DbpfId,
+}
+
+impl ResourceLink {
+    /// The key of the resource the link points at.
+    #[must_use]
+    pub fn key(&self) -> Key {
+        Key {
+            kind: self.kind,
+            group_id: self.group_id,
+            instance_id: self.instance_id,
+            resource_id: self.resource_id,
+        }
+    }
+}
+
+/// The name, type id and version every block starts with.
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct BlockHeader {
+    #[br(parse_with = read_seven_bit_string)]
+    #[bw(write_with = write_seven_bit_string)]
+    #[cfg_attr(test, strategy("[a-zA-Z]{0,16}"))]
+    pub name: String,
+    pub id: u32,
+    pub version: u32,
+}
+
+impl BlockHeader {
+    #[must_use]
+    pub fn new(name: &str, id: u32, version: u32) -> Self {
+        Self {
+            name: name.to_string(),
+            id,
+            version,
+        }
+    }
+
+    /// The header for a registered block type.
+    #[must_use]
+    pub fn of<T: RcolBlock>(version: u32) -> Self {
+        Self::new(T::NAME, T::ID, version)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+pub struct UnknownBlock {
+    pub header: BlockHeader,
+    pub data: Vec<u8>,
+}
+
+impl BinWrite for UnknownBlock {
+    type Args = ();
+
+    fn write_options<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        options: &WriteOptions,
+        _args: Self::Args,
+    ) -> BinResult<()> {
+        self.header.write_options(writer, options, ())?;
+        self.data.write_options(writer, options, ())
+    }
+}
+
+/// The `cSGResource` block most other blocks embed, naming the resource.
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct SgResource {
+    pub header: BlockHeader,
+    #[br(parse_with = read_seven_bit_string)]
+    #[bw(write_with = write_seven_bit_string)]
+    #[cfg_attr(test, strategy("[a-zA-Z0-9_]{0,32}"))]
+    pub file_name: String,
+}
+
+impl SgResource {
+    pub const NAME: &'static str = "cSGResource";
+    pub const ID: u32 = 0;
+
+    #[must_use]
+    pub fn new(file_name: String) -> Self {
+        Self {
+            header: BlockHeader::new(Self::NAME, Self::ID, 2),
+            file_name,
+        }
+    }
+}
+
+/// The `cObjectGraphNode` block embedded in the scenegraph nodes, listing the extension blocks
+/// attached to a node.
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct ObjectGraphNode {
+    #[cfg_attr(test, strategy((3_u32..5).prop_map(|version| BlockHeader::new(ObjectGraphNode::NAME, ObjectGraphNode::ID, version))))]
+    pub header: BlockHeader,
+    #[br(temp)]
+    #[bw(calc = extensions.len() as u32)]
+    num_extensions: u32,
+    #[br(count(num_extensions as usize))]
+    #[cfg_attr(test, strategy(vec(any::<ObjectGraphReference>(), 0..4)))]
+    pub extensions: Vec<ObjectGraphReference>,
+    /// Only stored from version 4 on
+    #[br(parse_with = read_optional_string, args(header.version >= 4))]
+    #[bw(write_with = write_optional_string)]
+    #[cfg_attr(test, strategy("[a-zA-Z0-9_]{0,32}".prop_map(move |x| (#header.version >= 4).then(|| x))))]
+    pub file_name: Option<String>,
+}
+
+impl ObjectGraphNode {
+    pub const NAME: &'static str = "cObjectGraphNode";
+    pub const ID: u32 = 0;
+
+    #[must_use]
+    pub fn new(file_name: String) -> Self {
+        Self {
+            header: BlockHeader::new(Self::NAME, Self::ID, 4),
+            extensions: vec![],
+            file_name: Some(file_name),
+        }
+    }
+}
+
+/// A reference to another block of the same collection, by index.
+#[binrw]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct ObjectGraphReference {
+    #[br(map = |x: u8| x != 0)]
+    #[bw(map = |x: &bool| u8::from(*x))]
+    pub enabled: bool,
+    #[br(map = |x: u8| x != 0)]
+    #[bw(map = |x: &bool| u8::from(*x))]
+    pub dependent: bool,
+    pub index: u32,
+}
+
+/// The `cCompositionTreeNode` block embedded in resource and transform nodes.
+#[binrw]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Arbitrary))]
+#[brw(little)]
+pub struct CompositionTreeNode {
+    #[cfg_attr(
+        test,
+        strategy(Just(BlockHeader::new(CompositionTreeNode::NAME, CompositionTreeNode::ID, 11)))
+    )]
+    pub header: BlockHeader,
+    pub graph: ObjectGraphNode,
+}
+
+impl CompositionTreeNode {
+    pub const NAME: &'static str = "cCompositionTreeNode";
+    pub const ID: u32 = 0;
+
+    #[must_use]
+    pub fn new(file_name: String) -> Self {
+        Self {
+            header: BlockHeader::new(Self::NAME, Self::ID, 11),
+            graph: ObjectGraphNode::new(file_name),
+        }
+    }
+}
+
+/// Reads a count followed by blocks embedded in another block, each prefixed with its type id.
+/// Unlike the blocks of the collection itself, these must all have a registered implementation.
+///
+/// # Errors
+/// Fails when the reader runs out or a block isn't registered.
+pub fn read_embedded_blocks<R: Read + Seek>(
+    reader: &mut R,
+    options: &ReadOptions,
+    _: (),
+) -> BinResult<Vec<Block>> {
+    let count = u32::read_options(reader, options, ())?;
+    (0..count)
+        .map(|_| {
+            // The id is repeated in the header
+            u32::read_options(reader, options, ())?;
+            let pos = reader.stream_position()?;
+            let header = BlockHeader::read_options(reader, options, ())?;
+            reader.seek(SeekFrom::Start(pos))?;
+            Block::read_registered(reader, options, &header)
+                .unwrap_or_else(|| Err(unregistered(pos, &header)))
+        })
+        .collect()
+}
+
+/// # Errors
+/// Fails when the writer does.
+// Signature is dictated by binrw's write_with
+#[allow(clippy::ptr_arg, clippy::trivially_copy_pass_by_ref)]
+pub fn write_embedded_blocks<W: Write + Seek>(
+    blocks: &Vec<Block>,
+    writer: &mut W,
+    options: &WriteOptions,
+    _: (),
+) -> BinResult<()> {
+    (blocks.len() as u32).write_options(writer, options, ())?;
+    for block in blocks {
+        block.header().id.write_options(writer, options, ())?;
+        block.write_options(writer, options, ())?;
+    }
+    Ok(())
+}
+
+fn unregistered(pos: u64, header: &BlockHeader) -> binrw::Error {
+    binrw::Error::AssertFail {
+        pos,
+        message: format!(
+            "No implementation for block {} version {}",
+            header.name, header.version
+        ),
+    }
+}
+
+/// Reads a 7 bit length prefixed string if `present`, for fields only some versions have.
+///
+/// # Errors
+/// Fails when the reader runs out or the string isn't valid UTF-8.
+pub fn read_optional_string<R: Read + Seek>(
+    reader: &mut R,
+    options: &ReadOptions,
+    (present,): (bool,),
+) -> BinResult<Option<String>> {
+    present
+        .then(|| read_seven_bit_string(reader, options, ()))
+        .transpose()
+}
+
+/// # Errors
+/// Fails when the writer does.
+// Signature is dictated by binrw's write_with
+#[allow(clippy::ref_option, clippy::trivially_copy_pass_by_ref)]
+pub fn write_optional_string<W: Write + Seek>(
+    string: &Option<String>,
+    writer: &mut W,
+    options: &WriteOptions,
+    _: (),
+) -> BinResult<()> {
+    match string {
+        Some(string) => write_seven_bit_string(string, writer, options, ()),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::test_parsing;
+    use binrw::io::Cursor;
+    use binrw::{BinReaderExt, BinWriterExt};
+
+    test_parsing!(
+        [
+            0x01, 0x00, 0xFF, 0xFF, // resource id marker
+            0x01, 0x00, 0x00, 0x00, // number of links
+            0x01, 0x00, 0x00, 0x00, // group id
+            0x02, 0x00, 0x00, 0x00, // instance id
+            0x03, 0x00, 0x00, 0x00, // resource id
+            0x6C, 0x27, 0x4A, 0x1C, // kind (TXTR)
+            0x00, 0x00, 0x00, 0x00, // number of blocks
+        ],
+        ResourceCollection {
+            has_resource_ids: true,
+            links: vec![ResourceLink {
+                group_id: GroupId(1),
+                instance_id: InstanceId(2),
+                resource_id: Some(ResourceId(3)),
+                kind: DbpfId::TextureResource,
+            }],
+            blocks: vec![],
+            raw_blocks: None,
+        },
+        ResourceCollection,
+        rcol
+    );
+
+    test_parsing!(
+        [
+            0x10, // name length
+            0x63, 0x4F, 0x62, 0x6A, 0x65, 0x63, 0x74, 0x47, 0x72, 0x61, 0x70, 0x68, 0x4E, 0x6F,
+            0x64, 0x65, // cObjectGraphNode
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x04, 0x00, 0x00, 0x00, // block version
+            0x01, 0x00, 0x00, 0x00, // number of extensions
+            0x01, // enabled
+            0x00, // dependent
+            0x02, 0x00, 0x00, 0x00, // index
+            0x01, 0x4E, // N
+        ],
+        ObjectGraphNode {
+            header: BlockHeader::new(ObjectGraphNode::NAME, ObjectGraphNode::ID, 4),
+            extensions: vec![ObjectGraphReference {
+                enabled: true,
+                dependent: false,
+                index: 2,
+            }],
+            file_name: Some("N".to_string()),
+        },
+        ObjectGraphNode,
+        cobjectgraphnode
+    );
+
+    #[test]
+    fn unknown_trailing_blocks_are_kept() {
+        let data = [
+            0x00, 0x00, 0x00, 0x00, // number of links
+            0x01, 0x00, 0x00, 0x00, // number of blocks
+            0x01, 0x00, 0x00, 0x00, // block id
+            0x01, 0x63, // c
+            0x01, 0x00, 0x00, 0x00, // block id
+            0x01, 0x00, 0x00, 0x00, // block version
+            0xAA, 0xBB, // block data
+        ];
+        let rcol: ResourceCollection = Cursor::new(data).read_le().unwrap();
+
+        assert_eq!(
+            rcol.blocks,
+            vec![Block::Unknown(UnknownBlock {
+                header: BlockHeader::new("c", 1, 1),
+                data: vec![0xAA, 0xBB],
+            })]
+        );
+        let mut cursor = Cursor::new(vec![]);
+        cursor.write_le(&rcol).unwrap();
+        assert_eq!(cursor.into_inner(), data);
+    }
+
+    #[test]
+    fn unknown_blocks_in_the_middle_keep_the_blocks_raw() {
+        let data = [
+            0x00, 0x00, 0x00, 0x00, // number of links
+            0x02, 0x00, 0x00, 0x00, // number of blocks
+            0x01, 0x00, 0x00, 0x00, // block id
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x01, 0x63, // c
+            0x01, 0x00, 0x00, 0x00, // block id
+            0x01, 0x00, 0x00, 0x00, // block version
+            0xAA, 0xBB, // block data
+            0x0B, // name length
+            0x63, 0x53, 0x47, 0x52, 0x65, 0x73, 0x6F, 0x75, 0x72, 0x63, 0x65, // cSGResource
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x02, 0x00, 0x00, 0x00, // block version
+            0x01, 0x4E, // N
+        ];
+        let rcol: ResourceCollection = Cursor::new(data).read_le().unwrap();
+
+        assert!(rcol.blocks.is_empty());
+        assert_eq!(rcol.raw_blocks.as_deref(), Some(&data[4..]));
+        let mut cursor = Cursor::new(vec![]);
+        cursor.write_le(&rcol).unwrap();
+        assert_eq!(cursor.into_inner(), data);
+    }
+
+    #[test]
+    fn blocks_read_to_the_end_of_their_entry() {
+        use crate::constants::data_kinds::{DbpfId, DbpfKind};
+        use crate::test_helpers::package_bytes;
+        use crate::types::package::database_packed_file::{Dbpf, GroupId, InstanceId, Key};
+
+        let trailing = vec![
+            0x00, 0x00, 0x00, 0x00, // number of links
+            0x01, 0x00, 0x00, 0x00, // number of blocks
+            0x01, 0x00, 0x00, 0x00, // block id
+            0x01, 0x63, // c
+            0x01, 0x00, 0x00, 0x00, // block id
+            0x01, 0x00, 0x00, 0x00, // block version
+            0xAA, 0xBB, // block data
+        ];
+        let middle = vec![
+            0x00, 0x00, 0x00, 0x00, // number of links
+            0x02, 0x00, 0x00, 0x00, // number of blocks
+            0x01, 0x00, 0x00, 0x00, // block id
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x01, 0x63, // c
+            0x01, 0x00, 0x00, 0x00, // block id
+            0x01, 0x00, 0x00, 0x00, // block version
+            0xAA, 0xBB, // block data
+            0x0B, // name length
+            0x63, 0x53, 0x47, 0x52, 0x65, 0x73, 0x6F, 0x75, 0x72, 0x63, 0x65, // cSGResource
+            0x00, 0x00, 0x00, 0x00, // block id
+            0x02, 0x00, 0x00, 0x00, // block version
+            0x01, 0x4E, // N
+        ];
+        let key = |instance| {
+            Key::new(
+                DbpfId::CreationResource,
+                GroupId(1),
+                InstanceId(instance),
+                None,
+            )
+        };
+        let bytes = package_bytes(&[
+            (key(1), trailing),
+            (key(2), middle.clone()),
+            (key(3), vec![0x00; 8]),
+            (
+                Key::new(DbpfId::JpegImage, GroupId(1), InstanceId(4), None),
+                vec![0xCC; 8],
+            ),
+        ]);
+        let dbpf: Dbpf = Cursor::new(bytes).read_le().unwrap();
+        let rcol = |instance| match &dbpf.entries[&key(instance)].data {
+            DbpfKind::CreationResource(cres) => cres.collection.clone(),
+            other => panic!("{:?}", other),
+        };
+
+        assert_eq!(
+            rcol(1).blocks,
+            vec![Block::Unknown(UnknownBlock {
+                header: BlockHeader::new("c", 1, 1),
+                data: vec![0xAA, 0xBB],
+            })]
+        );
+        assert_eq!(rcol(2).raw_blocks.as_deref(), Some(&middle[4..]));
+        assert_eq!(rcol(3), ResourceCollection::default());
+    }
+}
@@ -0,0 +1,121 @@
+////////////////////////////////////////////////////////////////////////////////
+// This Source Code Form is subject to the terms of the Mozilla Public         /
+// License, v. 2.0. If a copy of the MPL was not distributed with this         /
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
+////////////////////////////////////////////////////////////////////////////////
+
+//! Strings prefixed with their length as a 7 bit encoded integer, the layout .NET's
+//! `BinaryWriter` uses and which many of the game's formats share.
+
+use std::io::{Read, Seek, Write};
+
+use binrw::{BinRead, BinResult, BinWrite, ReadOptions, WriteOptions};
+
+/// # Errors
+/// Fails when the reader runs out or the string isn't valid UTF-8.
+pub fn read_seven_bit_string<R: Read + Seek>(
+    reader: &mut R,
+    options: &ReadOptions,
+    _: (),
+) -> BinResult<String> {
+    let pos = reader.stream_position()?;
+    let mut len = 0usize;
+    let mut shift = 0;
+    loop {
+        let byte = u8::read_options(reader, options, ())?;
+        len |= usize::from(byte & 0x7F) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        if shift > 28 {
+            return Err(binrw::Error::AssertFail {
+                pos,
+                message: "String length prefix is too long".to_string(),
+            });
+        }
+    }
+    let mut bytes = vec![0u8; len];
+    reader.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map_err(|err| binrw::Error::Custom {
+        pos,
+        err: Box::new(err),
+    })
+}
+
+/// # Errors
+/// Fails when the writer does.
+// Signature is dictated by binrw's write_with
+#[allow(clippy::ptr_arg, clippy::trivially_copy_pass_by_ref)]
+pub fn write_seven_bit_string<W: Write + Seek>(
+    string: &String,
+    writer: &mut W,
+    options: &WriteOptions,
+    _: (),
+) -> BinResult<()> {
+    let mut len = string.len();
+    loop {
+        let byte = (len & 0x7F) as u8;
+        len >>= 7;
+        if len == 0 {
+            byte.write_options(writer, options, ())?;
+            break;
+        }
+        (byte | 0x80).write_options(writer, options, ())?;
+    }
+    writer.write_all(string.as_bytes())?;
+    Ok(())
+}
+
+/// Reads `count` 7 bit length prefixed strings.
+///
+/// # Errors
+/// Fails when the reader runs out or a string isn't valid UTF-8.
+pub fn read_seven_bit_strings<R: Read + Seek>(
+    reader: &mut R,
+    options: &ReadOptions,
+    (count,): (u32,),
+) -> BinResult<Vec<String>> {
+    (0..count)
+        .map(|_| read_seven_bit_string(reader, options, ()))
+        .collect()
+}
+
+/// # Errors
+/// Fails when the writer does.
+// Signature is dictated by binrw's write_with
+#[allow(clippy::ptr_arg, clippy::trivially_copy_pass_by_ref)]
+pub fn write_seven_bit_strings<W: Write + Seek>(
+    strings: &Vec<String>,
+    writer: &mut W,
+    options: &WriteOptions,
+    _: (),
+) -> BinResult<()> {
+    for string in strings {
+        write_seven_bit_string(string, writer, options, ())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use binrw::io::Cursor;
+    use binrw::Endian;
+
+    #[test]
+    fn long_strings_use_multiple_length_bytes() {
+        let string = "a".repeat(200);
+        let mut cursor = Cursor::new(vec![]);
+        write_seven_bit_string(&string, &mut cursor, &WriteOptions::new(Endian::Little), ())
+            .unwrap();
+
+        assert_eq!(&cursor.get_ref()[..2], &[0xC8, 0x01]);
+
+        cursor.set_position(0);
+        let out =
+            read_seven_bit_string(&mut cursor, &ReadOptions::new(Endian::Little), ()).unwrap();
+
+        assert_eq!(out, string);
+    }
+}
@@ -0,0 +1,410 @@
+////////////////////////////////////////////////////////////////////////////////
+// This Source Code Form is subject to the terms of the Mozilla Public         /
+// License, v. 2.0. If a copy of the MPL was not distributed with this         /
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
+////////////////////////////////////////////////////////////////////////////////
+
+//! The pixel formats scenegraph textures and large images are stored in, with a software codec
+//! for each of them.
+
+use crate::types::util::image::RgbaImage;
+use binrw::binrw;
+#[cfg(test)]
+use test_strategy::Arbitrary;
+
+#[binrw]
+#[brw(little, repr = u32)]
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(test, derive(Arbitrary))]
+pub enum TextureFormat {
+    /// BGRA, 8 bits per channel
+    Raw32 = 1,
+    /// BGR, 8 bits per channel
+    Raw24 = 2,
+    Dxt1 = 4,
+    Dxt3 = 5,
+    /// 8 bit grayscale
+    ExtRaw8 = 6,
+    /// 8 bit grayscale
+    Raw8 = 7,
+    Dxt5 = 8,
+    /// BGR, 8 bits per channel
+    ExtRaw24 = 9,
+}
+
+impl TextureFormat {
+    #[must_use]
+    pub fn is_compressed(self) -> bool {
+        matches!(
+            self,
+            TextureFormat::Dxt1 | TextureFormat::Dxt3 | TextureFormat::Dxt5
+        )
+    }
+
+    /// The number of bytes an image of this size takes up.
+    #[must_use]
+    pub fn data_size(self, width: u32, height: u32) -> usize {
+        let (width, height) = (width as usize, height as usize);
+        let blocks = ((width + 3) / 4).max(1) * ((height + 3) / 4).max(1);
+        match self {
+            TextureFormat::Raw32 => width * height * 4,
+            TextureFormat::Raw24 | TextureFormat::ExtRaw24 => width * height * 3,
+            TextureFormat::Raw8 | TextureFormat::ExtRaw8 => width * height,
+            TextureFormat::Dxt1 => blocks * 8,
+            TextureFormat::Dxt3 | TextureFormat::Dxt5 => blocks * 16,
+        }
+    }
+
+    /// Guesses the format from the amount of data an image has. DXT3 and DXT5 take up as much
+    /// space as 8 bit grayscale, in which case DXT5 is assumed.
+    #[must_use]
+    pub fn guess(width: u32, height: u32, size: usize) -> Option<Self> {
+        [
+            TextureFormat::Raw32,
+            TextureFormat::Raw24,
+            TextureFormat::Dxt5,
+            TextureFormat::Raw8,
+            TextureFormat::Dxt1,
+        ]
+        .iter()
+        .copied()
+        .find(|format| format.data_size(width, height) == size)
+    }
+
+    /// Decodes `data` into RGBA pixels, `None` if there isn't enough of it.
+    #[must_use]
+    pub fn decode(self, width: u32, height: u32, data: &[u8]) -> Option<RgbaImage> {
+        if data.len() < self.data_size(width, height) {
+            return None;
+        }
+        let mut image = RgbaImage::new(width, height);
+        match self {
+            TextureFormat::Raw32 => {
+                for (pixel, bgra) in image.data.chunks_exact_mut(4).zip(data.chunks_exact(4)) {
+                    pixel.copy_from_slice(&[bgra[2], bgra[1], bgra[0], bgra[3]]);
+                }
+            }
+            TextureFormat::Raw24 | TextureFormat::ExtRaw24 => {
+                for (pixel, bgr) in image.data.chunks_exact_mut(4).zip(data.chunks_exact(3)) {
+                    pixel.copy_from_slice(&[bgr[2], bgr[1], bgr[0], 0xFF]);
+                }
+            }
+            TextureFormat::Raw8 | TextureFormat::ExtRaw8 => {
+                for (pixel, &gray) in image.data.chunks_exact_mut(4).zip(data) {
+                    pixel.copy_from_slice(&[gray, gray, gray, 0xFF]);
+                }
+            }
+            TextureFormat::Dxt1 | TextureFormat::Dxt3 | TextureFormat::Dxt5 => {
+                let block_size = self.data_size(1, 1);
+                let blocks_wide = ((width + 3) / 4).max(1) as usize;
+                for (index, block) in data.chunks_exact(block_size).enumerate() {
+                    let block_x = (index % blocks_wide) as u32 * 4;
+                    let block_y = (index / blocks_wide) as u32 * 4;
+                    if block_y >= height {
+                        break;
+                    }
+                    let pixels = self.decode_block(block);
+                    for (offset, pixel) in pixels.iter().enumerate() {
+                        let x = block_x + offset as u32 % 4;
+                        let y = block_y + offset as u32 / 4;
+                        if x < width && y < height {
+                            image.set_pixel(x, y, *pixel);
+                        }
+                    }
+                }
+            }
+        }
+        Some(image)
+    }
+
+    /// Encodes `image` in this format. Compression picks the block endpoints from the bounding
+    /// box of each block's colors, which is fast but not the best possible quality.
+    #[must_use]
+    pub fn encode(self, image: &RgbaImage) -> Vec<u8> {
+        match self {
+            TextureFormat::Raw32 => image
+                .pixels()
+                .flat_map(|[r, g, b, a]| [b, g, r, a])
+                .collect(),
+            TextureFormat::Raw24 | TextureFormat::ExtRaw24 => {
+                image.pixels().flat_map(|[r, g, b, _]| [b, g, r]).collect()
+            }
+            TextureFormat::Raw8 | TextureFormat::ExtRaw8 => image.pixels().map(luminance).collect(),
+            TextureFormat::Dxt1 | TextureFormat::Dxt3 | TextureFormat::Dxt5 => {
+                let mut out = Vec::with_capacity(self.data_size(image.width, image.height));
+                for block_y in (0..image.height.max(1)).step_by(4) {
+                    for block_x in (0..image.width.max(1)).step_by(4) {
+                        let mut pixels = [[0u8; 4]; 16];
+                        for (offset, pixel) in pixels.iter_mut().enumerate() {
+                            // Blocks past the edge repeat the last row and column
+                            let x = (block_x + offset as u32 % 4).min(image.width.max(1) - 1);
+                            let y = (block_y + offset as u32 / 4).min(image.height.max(1) - 1);
+                            if x < image.width && y < image.height {
+                                *pixel = image.pixel(x, y);
+                            }
+                        }
+                        self.encode_block(&pixels, &mut out);
+                    }
+                }
+                out
+            }
+        }
+    }
+
+    fn decode_block(self, block: &[u8]) -> [[u8; 4]; 16] {
+        match self {
+            TextureFormat::Dxt1 => decode_color_block(block, true),
+            TextureFormat::Dxt3 => {
+                let mut pixels = decode_color_block(&block[8..], false);
+                for (index, pixel) in pixels.iter_mut().enumerate() {
+                    let alpha = (block[index / 2] >> (index % 2 * 4)) & 0x0F;
+                    pixel[3] = alpha * 0x11;
+                }
+                pixels
+            }
+            _ => {
+                let mut pixels = decode_color_block(&block[8..], false);
+                let alphas = alpha_palette(block[0], block[1]);
+                let indices = block_indices(&block[2..8]);
+                for (index, pixel) in pixels.iter_mut().enumerate() {
+                    pixel[3] = alphas[(indices >> (index * 3)) as usize & 7];
+                }
+                pixels
+            }
+        }
+    }
+
+    fn encode_block(self, pixels: &[[u8; 4]; 16], out: &mut Vec<u8>) {
+        match self {
+            TextureFormat::Dxt1 => out.extend_from_slice(&encode_color_block(pixels, true)),
+            TextureFormat::Dxt3 => {
+                for pair in pixels.chunks_exact(2) {
+                    out.push((pair[0][3] >> 4) | (pair[1][3] & 0xF0));
+                }
+                out.extend_from_slice(&encode_color_block(pixels, false));
+            }
+            _ => {
+                let max = pixels.iter().map(|pixel| pixel[3]).max().unwrap_or(0);
+                let min = pixels.iter().map(|pixel| pixel[3]).min().unwrap_or(0);
+                let alphas = alpha_palette(max, min);
+                let indices = pixels
+                    .iter()
+                    .enumerate()
+                    .fold(0u64, |indices, (index, pixel)| {
+                        indices | (nearest_alpha(alphas, pixel[3]) << (index * 3))
+                    });
+                out.extend_from_slice(&[max, min]);
+                out.extend_from_slice(&indices.to_le_bytes()[..6]);
+                out.extend_from_slice(&encode_color_block(pixels, false));
+            }
+        }
+    }
+}
+
+/// The gray level of a pixel, weighted the way the eye sees it.
+fn luminance(pixel: [u8; 4]) -> u8 {
+    ((u32::from(pixel[0]) * 299 + u32::from(pixel[1]) * 587 + u32::from(pixel[2]) * 114) / 1000)
+        as u8
+}
+
+fn from_565(color: u16) -> [u8; 3] {
+    let r = (color >> 11) & 0x1F;
+    let g = (color >> 5) & 0x3F;
+    let b = color & 0x1F;
+    [
+        ((r << 3) | (r >> 2)) as u8,
+        ((g << 2) | (g >> 4)) as u8,
+        ((b << 3) | (b >> 2)) as u8,
+    ]
+}
+
+fn to_565(rgb: [u8; 3]) -> u16 {
+    (u16::from(rgb[0] >> 3) << 11) | (u16::from(rgb[1] >> 2) << 5) | u16::from(rgb[2] >> 3)
+}
+
+fn mix(a: [u8; 3], b: [u8; 3], weight_a: u16, weight_b: u16) -> [u8; 4] {
+    let channel = |a: u8, b: u8| {
+        ((u16::from(a) * weight_a + u16::from(b) * weight_b) / (weight_a + weight_b)) as u8
+    };
+    [
+        channel(a[0], b[0]),
+        channel(a[1], b[1]),
+        channel(a[2], b[2]),
+        0xFF,
+    ]
+}
+
+/// The four colors a block picks from. DXT1 blocks with the endpoints in ascending order have
+/// only three, and use the last slot for transparent black.
+fn color_palette(color_0: u16, color_1: u16, dxt1: bool) -> [[u8; 4]; 4] {
+    let (a, b) = (from_565(color_0), from_565(color_1));
+    if color_0 > color_1 || !dxt1 {
+        [
+            mix(a, b, 1, 0),
+            mix(a, b, 0, 1),
+            mix(a, b, 2, 1),
+            mix(a, b, 1, 2),
+        ]
+    } else {
+        [mix(a, b, 1, 0), mix(a, b, 0, 1), mix(a, b, 1, 1), [0; 4]]
+    }
+}
+
+fn block_indices(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .rev()
+        .fold(0, |indices, &byte| (indices << 8) | u64::from(byte))
+}
+
+fn decode_color_block(block: &[u8], dxt1: bool) -> [[u8; 4]; 16] {
+    let color_0 = u16::from_le_bytes([block[0], block[1]]);
+    let color_1 = u16::from_le_bytes([block[2], block[3]]);
+    let palette = color_palette(color_0, color_1, dxt1);
+    let indices = block_indices(&block[4..8]);
+    let mut pixels = [[0u8; 4]; 16];
+    for (index, pixel) in pixels.iter_mut().enumerate() {
+        *pixel = palette[(indices >> (index * 2)) as usize & 3];
+    }
+    pixels
+}
+
+fn encode_color_block(pixels: &[[u8; 4]; 16], dxt1: bool) -> [u8; 8] {
+    let transparent = |pixel: &[u8; 4]| dxt1 && pixel[3] < 0x80;
+    let mut min = [0xFF; 3];
+    let mut max = [0; 3];
+    // Invisible pixels don't get a say in the endpoints
+    for pixel in pixels
+        .iter()
+        .filter(|pixel| pixel[3] != 0 && !transparent(pixel))
+    {
+        for ((min, max), &value) in min.iter_mut().zip(max.iter_mut()).zip(pixel) {
+            *min = (*min).min(value);
+            *max = (*max).max(value);
+        }
+    }
+    if min > max {
+        min = max;
+    }
+    let has_transparency = pixels.iter().any(transparent);
+    let (mut color_0, mut color_1) = (to_565(max), to_565(min));
+    // The endpoint order selects between the three and four color modes
+    if has_transparency == (color_0 > color_1) {
+        std::mem::swap(&mut color_0, &mut color_1);
+    }
+    let palette = color_palette(color_0, color_1, dxt1);
+    let choices = if dxt1 && color_0 <= color_1 { 3 } else { 4 };
+    let indices = pixels
+        .iter()
+        .enumerate()
+        .fold(0u32, |indices, (index, pixel)| {
+            let choice = if transparent(pixel) {
+                3
+            } else {
+                palette[..choices]
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, color)| {
+                        color
+                            .iter()
+                            .zip(pixel)
+                            .map(|(&a, &b)| (i32::from(a) - i32::from(b)).pow(2))
+                            .sum::<i32>()
+                    })
+                    .map_or(0, |(choice, _)| choice as u32)
+            };
+            indices | (choice << (index * 2))
+        });
+    let mut block = [0u8; 8];
+    block[..2].copy_from_slice(&color_0.to_le_bytes());
+    block[2..4].copy_from_slice(&color_1.to_le_bytes());
+    block[4..].copy_from_slice(&indices.to_le_bytes());
+    block
+}
+
+/// The eight alpha levels a DXT5 block picks from. With the endpoints in ascending order there
+/// are only six steps, plus fully transparent and fully opaque.
+fn alpha_palette(alpha_0: u8, alpha_1: u8) -> [u8; 8] {
+    let (a, b) = (u16::from(alpha_0), u16::from(alpha_1));
+    let mut palette = [alpha_0, alpha_1, 0, 0, 0, 0, 0, 0xFF];
+    let steps = if a > b { 7 } else { 5 };
+    for (step, value) in (1..steps).zip(palette.iter_mut().skip(2)) {
+        *value = ((a * (steps - step) + b * step) / steps) as u8;
+    }
+    palette
+}
+
+fn nearest_alpha(palette: [u8; 8], alpha: u8) -> u64 {
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, &value)| (i16::from(value) - i16::from(alpha)).abs())
+        .map_or(0, |(index, _)| index as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient(width: u32, height: u32) -> RgbaImage {
+        let mut image = RgbaImage::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let value = ((x + y) * 20) as u8;
+                image.set_pixel(x, y, [value, value, 0x80, 0xFF]);
+            }
+        }
+        image
+    }
+
+    #[test]
+    fn raw_round_trip() {
+        let image = gradient(3, 2);
+        for format in [TextureFormat::Raw32, TextureFormat::Raw24] {
+            let data = format.encode(&image);
+            assert_eq!(data.len(), format.data_size(3, 2));
+            assert_eq!(format.decode(3, 2, &data), Some(image.clone()));
+        }
+        assert_eq!(TextureFormat::Raw32.encode(&image)[..4], [0x80, 0, 0, 0xFF]);
+        assert_eq!(TextureFormat::Raw8.decode(3, 2, &[0; 5]), None);
+    }
+
+    #[test]
+    fn dxt1_block() {
+        // Red and blue endpoints, every pixel picking the color two thirds of the way to blue
+        let block = [0x00, 0xF8, 0x1F, 0x00, 0xFF, 0xFF, 0xFF, 0xFF];
+        let image = TextureFormat::Dxt1.decode(4, 4, &block).unwrap();
+
+        assert_eq!(image.pixel(3, 3), [0x55, 0, 0xAA, 0xFF]);
+    }
+
+    #[test]
+    fn dxt_round_trip() {
+        let mut image = gradient(6, 5);
+        image.set_pixel(0, 0, [0, 0, 0, 0]);
+        for format in [
+            TextureFormat::Dxt1,
+            TextureFormat::Dxt3,
+            TextureFormat::Dxt5,
+        ] {
+            let data = format.encode(&image);
+            assert_eq!(data.len(), format.data_size(6, 5));
+            let out = format.decode(6, 5, &data).unwrap();
+            assert_eq!(out.pixel(0, 0)[3], 0, "{:?}", format);
+            for (a, b) in out.pixels().zip(image.pixels()).skip(1) {
+                for (a, b) in a.iter().zip(b.iter()) {
+                    assert!((i16::from(*a) - i16::from(*b)).abs() <= 24, "{:?}", format);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn guess_by_size() {
+        assert_eq!(TextureFormat::guess(4, 4, 64), Some(TextureFormat::Raw32));
+        assert_eq!(TextureFormat::guess(4, 4, 8), Some(TextureFormat::Dxt1));
+        assert_eq!(TextureFormat::guess(4, 4, 16), Some(TextureFormat::Dxt5));
+        assert_eq!(TextureFormat::guess(4, 4, 17), None);
+    }
+}
@@ -0,0 +1,232 @@
+////////////////////////////////////////////////////////////////////////////////
+// This Source Code Form is subject to the terms of the Mozilla Public         /
+// License, v. 2.0. If a copy of the MPL was not distributed with this         /
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.                   /
+////////////////////////////////////////////////////////////////////////////////
+
+//! Wavefront OBJ export and import of meshes. OBJ has no skinning, so imported meshes are bound
+//! to the skeleton with [`GeometryData::import_mesh`](crate::types::scenegraph::geometric_data_container::GeometryData::import_mesh).
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::error::{DbpfResult, Error};
+use crate::types::scenegraph::geometric_data_container::Mesh;
+
+/// Writes meshes as one OBJ object each. Texture coordinates are flipped vertically, as OBJ puts
+/// the origin at the bottom left.
+#[must_use]
+pub fn to_obj(meshes: &[Mesh]) -> String {
+    let mut obj = String::new();
+    let (mut positions, mut uvs, mut normals) = (1, 1, 1);
+    for mesh in meshes {
+        // Writing to a String can't fail
+        let _ = writeln!(obj, "o {}", mesh.name);
+        for [x, y, z] in &mesh.positions {
+            let _ = writeln!(obj, "v {} {} {}", x, y, z);
+        }
+        for [u, v] in &mesh.uvs {
+            let _ = writeln!(obj, "vt {} {}", u, 1.0 - v);
+        }
+        for [x, y, z] in &mesh.normals {
+            let _ = writeln!(obj, "vn {} {} {}", x, y, z);
+        }
+        for face in mesh.indices.chunks_exact(3) {
+            obj.push('f');
+            for &index in face {
+                let index = index as usize;
+                let _ = match (mesh.uvs.is_empty(), mesh.normals.is_empty()) {
+                    (true, true) => write!(obj, " {}", positions + index),
+                    (false, true) => write!(obj, " {}/{}", positions + index, uvs + index),
+                    (true, false) => write!(obj, " {}//{}", positions + index, normals + index),
+                    (false, false) => write!(
+                        obj,
+                        " {}/{}/{}",
+                        positions + index,
+                        uvs + index,
+                        normals + index
+                    ),
+                };
+            }
+            obj.push('\n');
+        }
+        positions += mesh.positions.len();
+        uvs += mesh.uvs.len();
+        normals += mesh.normals.len();
+    }
+    obj
+}
+
+/// Reads every object or group of an OBJ file as a mesh. Polygons are split into triangles, and
+/// corners sharing a position, texture coordinate and normal become a single vertex.
+///
+/// # Errors
+/// Returns [`Error::MeshError`] if a line can't be understood or a face refers to missing data.
+pub fn from_obj(source: &str) -> DbpfResult<Vec<Mesh>> {
+    let mut positions: Vec<[f32; 3]> = vec![];
+    let mut uvs: Vec<[f32; 2]> = vec![];
+    let mut normals: Vec<[f32; 3]> = vec![];
+    let mut meshes = vec![];
+    let mut mesh = Mesh::default();
+    let mut vertices: HashMap<(usize, Option<usize>, Option<usize>), u32> = HashMap::new();
+
+    for (number, line) in source.lines().enumerate() {
+        let error = |message: &str| Error::MeshError(format!("line {}: {}", number + 1, message));
+        let mut words = line.split_whitespace();
+        let keyword = match words.next() {
+            Some(keyword) if !keyword.starts_with('#') => keyword,
+            _ => continue,
+        };
+        let floats = || -> DbpfResult<Vec<f32>> {
+            words
+                .clone()
+                .map(|word| word.parse().map_err(|_| error("invalid number")))
+                .collect()
+        };
+        match keyword {
+            "v" => positions.push(array(&floats()?).ok_or_else(|| error("expected x y z"))?),
+            "vt" => {
+                let [u, v]: [f32; 2] = array(&floats()?).ok_or_else(|| error("expected u v"))?;
+                uvs.push([u, 1.0 - v]);
+            }
+            "vn" => normals.push(array(&floats()?).ok_or_else(|| error("expected x y z"))?),
+            "o" | "g" => {
+                let name = words.collect::<Vec<_>>().join(" ");
+                if !mesh.indices.is_empty() {
+                    meshes.push(std::mem::take(&mut mesh));
+                    vertices.clear();
+                }
+                mesh.name = name;
+            }
+            "f" => {
+                let corners = words
+                    .map(|corner| {
+                        let mut parts = corner.split('/');
+                        let position = resolve(parts.next(), positions.len())
+                            .ok_or_else(|| error("invalid vertex index"))?;
+                        let uv = match parts.next() {
+                            Some("") | None => None,
+                            part => Some(
+                                resolve(part, uvs.len())
+                                    .ok_or_else(|| error("invalid texture index"))?,
+                            ),
+                        };
+                        let normal = match parts.next() {
+                            Some("") | None => None,
+                            part => Some(
+                                resolve(part, normals.len())
+                                    .ok_or_else(|| error("invalid normal index"))?,
+                            ),
+                        };
+                        let key = (position, uv, normal);
+                        let index = *vertices.entry(key).or_insert_with(|| {
+                            mesh.positions.push(positions[position]);
+                            if let Some(uv) = uv {
+                                mesh.uvs.push(uvs[uv]);
+                            }
+                            if let Some(normal) = normal {
+                                mesh.normals.push(normals[normal]);
+                            }
+                            mesh.positions.len() as u32 - 1
+                        });
+                        Ok(index)
+                    })
+                    .collect::<DbpfResult<Vec<u32>>>()?;
+                if corners.len() < 3 {
+                    return Err(error("faces need at least three corners"));
+                }
+                for pair in corners[1..].windows(2) {
+                    mesh.indices.extend([corners[0], pair[0], pair[1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    meshes.push(mesh);
+    for mesh in &mut meshes {
+        // Every vertex needs every attribute, or none of them
+        if mesh.uvs.len() != mesh.positions.len() {
+            mesh.uvs.clear();
+        }
+        if mesh.normals.len() != mesh.positions.len() {
+            mesh.normals.clear();
+        }
+    }
+    meshes.retain(|mesh| !mesh.positions.is_empty());
+    Ok(meshes)
+}
+
+fn array<const N: usize>(values: &[f32]) -> Option<[f32; N]> {
+    let mut array = [0.0; N];
+    if values.len() < N {
+        return None;
+    }
+    array.copy_from_slice(&values[..N]);
+    Some(array)
+}
+
+/// Turns a one-based or negative (counting back from the end) OBJ index into a zero-based one.
+fn resolve(index: Option<&str>, len: usize) -> Option<usize> {
+    let index: i64 = index?.parse().ok()?;
+    let resolved = if index < 0 {
+        i64::try_from(len).ok()? + index
+    } else {
+        index - 1
+    };
+    usize::try_from(resolved).ok().filter(|&index| index < len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mesh() -> Mesh {
+        Mesh {
+            name: "body".to_string(),
+            positions: vec![[0.0, 1.0, 2.0], [3.0, 4.5, 5.0], [6.0, 7.0, -8.25]],
+            normals: vec![[0.0, 1.0, 0.0], [0.0, 0.0, 1.0], [1.0, 0.0, 0.0]],
+            uvs: vec![[0.0, 0.25], [1.0, 0.5], [0.5, 1.0]],
+            joints: vec![],
+            weights: vec![],
+            indices: vec![0, 1, 2],
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let hair = Mesh {
+            name: "hair".to_string(),
+            normals: vec![],
+            ..mesh()
+        };
+        let meshes = vec![mesh(), hair];
+
+        assert_eq!(from_obj(&to_obj(&meshes)).unwrap(), meshes);
+    }
+
+    #[test]
+    fn quads_and_shared_corners() {
+        let obj = "# comment\n\
+            v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+            vt 0 0\n\
+            g quad\n\
+            f 1/1 2/1 3/1 -1/1\n";
+
+        let meshes = from_obj(obj).unwrap();
+
+        assert_eq!(meshes.len(), 1);
+        assert_eq!(meshes[0].name, "quad");
+        assert_eq!(meshes[0].positions.len(), 4);
+        assert_eq!(meshes[0].uvs, vec![[0.0, 1.0]; 4]);
+        assert_eq!(meshes[0].indices, vec![0, 1, 2, 0, 2, 3]);
+    }
+
+    #[test]
+    fn invalid_index() {
+        assert!(matches!(
+            from_obj("v 0 0 0\nf 1 2 3\n"),
+            Err(Error::MeshError(_))
+        ));
+    }
+}